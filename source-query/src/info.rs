@@ -0,0 +1,245 @@
+use crate::{packet::Reader, Error};
+
+/// The kind of server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerType {
+    Dedicated,
+    NonDedicated,
+    SourceTv,
+    Unknown(u8),
+}
+
+impl From<u8> for ServerType {
+    fn from(value: u8) -> Self {
+        match value {
+            b'd' => ServerType::Dedicated,
+            b'l' => ServerType::NonDedicated,
+            b'p' => ServerType::SourceTv,
+            other => ServerType::Unknown(other),
+        }
+    }
+}
+
+/// The response to A2S_INFO.
+///
+/// See https://developer.valvesoftware.com/wiki/Server_queries#A2S_INFO.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Info {
+    pub protocol: u8,
+    pub name: String,
+    pub map: String,
+    /// The directory containing the game files, `tf` for Team Fortress 2.
+    pub folder: String,
+    pub game: String,
+    pub app_id: u16,
+    /// The count of players, including the bots.
+    pub players: u8,
+    pub max_players: u8,
+    pub bots: u8,
+    pub server_type: ServerType,
+    pub password: bool,
+    pub vac: bool,
+    pub version: String,
+    /// The port of the game server, if it's not the port queried.
+    pub port: Option<u16>,
+    pub steam_id: Option<u64>,
+    /// The tags of the server, separated by commas.
+    pub keywords: Option<String>,
+    pub game_id: Option<u64>,
+}
+
+const APP_ID_THE_SHIP: u16 = 2400;
+
+impl Info {
+    /// Read the response, the header and the type byte `I` must already be read.
+    pub(crate) fn read(reader: &mut Reader) -> Result<Self, Error> {
+        let mut info = Info {
+            protocol: reader.u8()?,
+            name: reader.string()?,
+            map: reader.string()?,
+            folder: reader.string()?,
+            game: reader.string()?,
+            app_id: reader.u16()?,
+            players: reader.u8()?,
+            max_players: reader.u8()?,
+            bots: reader.u8()?,
+            server_type: ServerType::from(reader.u8()?),
+            password: {
+                // The environment is not needed.
+                reader.u8()?;
+                reader.u8()? != 0
+            },
+            vac: reader.u8()? != 0,
+            version: String::new(),
+            port: None,
+            steam_id: None,
+            keywords: None,
+            game_id: None,
+        };
+
+        if info.app_id == APP_ID_THE_SHIP {
+            // Mode, witnesses and duration.
+            reader.u8()?;
+            reader.u8()?;
+            reader.u8()?;
+        }
+
+        info.version = reader.string()?;
+
+        // The extra data flag is optional.
+        if reader.is_empty() {
+            return Ok(info);
+        }
+
+        let flags = reader.u8()?;
+
+        if flags & 0x80 != 0 {
+            info.port = Some(reader.u16()?);
+        }
+
+        if flags & 0x10 != 0 {
+            info.steam_id = Some(reader.u64()?);
+        }
+
+        if flags & 0x40 != 0 {
+            // SourceTV port and name.
+            reader.u16()?;
+            reader.string()?;
+        }
+
+        if flags & 0x20 != 0 {
+            info.keywords = Some(reader.string()?);
+        }
+
+        if flags & 0x01 != 0 {
+            info.game_id = Some(reader.u64()?);
+        }
+
+        Ok(info)
+    }
+
+    /// The count of players that are not bots.
+    pub fn humans(&self) -> u8 {
+        self.players.saturating_sub(self.bots)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        super::{Info, ServerType},
+        crate::{packet::Reader, Error},
+        test_case::test_case,
+    };
+
+    pub(crate) fn team_fortress_info() -> Info {
+        Info {
+            protocol: 17,
+            name: "Uncletopia | Paris | 1".to_string(),
+            map: "pl_upward".to_string(),
+            folder: "tf".to_string(),
+            game: "Team Fortress".to_string(),
+            app_id: 440,
+            players: 22,
+            max_players: 24,
+            bots: 0,
+            server_type: ServerType::Dedicated,
+            password: false,
+            vac: true,
+            version: "8604597".to_string(),
+            port: Some(27015),
+            steam_id: None,
+            keywords: Some("nocrits,payload,uncletopia".to_string()),
+            game_id: Some(440),
+        }
+    }
+
+    /// Write the response to A2S_INFO, without the header and the type byte.
+    pub(crate) fn write_info(info: &Info) -> Vec<u8> {
+        let mut bytes = vec![info.protocol];
+        let mut flags = 0u8;
+
+        for text in [&info.name, &info.map, &info.folder, &info.game] {
+            bytes.extend_from_slice(text.as_bytes());
+            bytes.push(0);
+        }
+
+        bytes.extend_from_slice(&info.app_id.to_le_bytes());
+        bytes.extend_from_slice(&[info.players, info.max_players, info.bots, b'd', b'l']);
+        bytes.extend_from_slice(&[info.password as u8, info.vac as u8]);
+        bytes.extend_from_slice(info.version.as_bytes());
+        bytes.push(0);
+
+        if info.port.is_some() {
+            flags |= 0x80;
+        }
+        if info.keywords.is_some() {
+            flags |= 0x20;
+        }
+        if info.game_id.is_some() {
+            flags |= 0x01;
+        }
+
+        bytes.push(flags);
+
+        if let Some(port) = info.port {
+            bytes.extend_from_slice(&port.to_le_bytes());
+        }
+        if let Some(keywords) = &info.keywords {
+            bytes.extend_from_slice(keywords.as_bytes());
+            bytes.push(0);
+        }
+        if let Some(game_id) = info.game_id {
+            bytes.extend_from_slice(&game_id.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn test_read_info() {
+        let expected = team_fortress_info();
+        let bytes = write_info(&expected);
+
+        assert_eq!(expected, Info::read(&mut Reader::new(&bytes)).unwrap());
+    }
+
+    #[test]
+    fn test_read_info_without_extra_data() {
+        let mut expected = team_fortress_info();
+
+        expected.port = None;
+        expected.keywords = None;
+        expected.game_id = None;
+
+        let mut bytes = write_info(&expected);
+
+        // Remove the extra data flag.
+        bytes.pop();
+
+        assert_eq!(expected, Info::read(&mut Reader::new(&bytes)).unwrap());
+    }
+
+    #[test_case(1)]
+    #[test_case(10)]
+    #[test_case(30)]
+    fn test_read_truncated_info(length: usize) {
+        let bytes = write_info(&team_fortress_info());
+
+        assert!(matches!(
+            Info::read(&mut Reader::new(&bytes[..length])),
+            Err(Error::Truncated)
+        ));
+    }
+
+    #[test]
+    fn test_humans() {
+        let info = Info {
+            players: 10,
+            bots: 3,
+            ..team_fortress_info()
+        };
+
+        assert_eq!(7, info.humans());
+    }
+}
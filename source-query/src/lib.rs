@@ -0,0 +1,479 @@
+//! Query the Source engine servers directly, using the A2S protocol over UDP.
+//!
+//! See https://developer.valvesoftware.com/wiki/Server_queries.
+//!
+//! The addresses of the servers can be listed with the master server query protocol, also over UDP.
+//! See https://developer.valvesoftware.com/wiki/Master_Server_Query_Protocol.
+
+pub use {
+    info::{Info, ServerType},
+    master::{Region, STEAM_MASTER_SERVER},
+    player::Player,
+    rules::Rules,
+};
+use {
+    log::{trace, warn},
+    master::{END_OF_LIST, M2A_SERVER_BATCH},
+    packet::{Reader, SplitPacket, SplitResponse, SINGLE_PACKET, SPLIT_PACKET},
+    std::{
+        net::{SocketAddr, SocketAddrV4},
+        time::{Duration, Instant},
+    },
+    tokio::net::{lookup_host, ToSocketAddrs, UdpSocket},
+};
+
+mod info;
+mod master;
+mod packet;
+mod player;
+mod rules;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The servers can answer a challenge to a request containing a challenge, the client gives up after that many
+/// challenges.
+const MAX_CHALLENGES: usize = 3;
+/// The packets are never bigger than 1400 bytes, except for some old engines.
+const MAX_PACKET_SIZE: usize = 4096;
+
+const A2S_INFO: u8 = b'T';
+const A2S_INFO_PAYLOAD: &[u8] = b"Source Engine Query\0";
+const S2A_INFO: u8 = b'I';
+const A2S_PLAYER: u8 = b'U';
+const S2A_PLAYER: u8 = b'D';
+const A2S_RULES: u8 = b'V';
+const S2A_RULES: u8 = b'E';
+const S2C_CHALLENGE: u8 = b'A';
+/// The challenge sent to request a challenge.
+const NO_CHALLENGE: i32 = -1;
+/// The master server sends about 230 addresses per page, this is enough for every server of a game.
+const MAX_MASTER_PAGES: usize = 100;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("No address found for the server")]
+    NoAddress,
+    #[error("The server did not answer")]
+    Timeout,
+    #[error("The response is truncated")]
+    Truncated,
+    #[error("The response is compressed, this is not supported")]
+    Compressed,
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+}
+
+/// Send the A2S queries.
+///
+/// A client does not keep any socket open, a new socket is opened for each query.
+#[derive(Debug, Clone)]
+pub struct Client {
+    timeout: Duration,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIMEOUT)
+    }
+}
+
+impl Client {
+    /// Create a client, `timeout` is the maximum duration to wait for each packet.
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+
+    /// Get the information about a server using A2S_INFO.
+    pub async fn info(&self, address: impl ToSocketAddrs) -> Result<Info, Error> {
+        let socket = Self::connect(address).await?;
+        let response = self.request(&socket, A2S_INFO, A2S_INFO_PAYLOAD, None, S2A_INFO).await?;
+
+        Info::read(&mut Reader::new(&response))
+    }
+
+    /// Measure the round-trip time to a server with A2S_INFO.
+    ///
+    /// Only the first response is awaited, a challenge is as good as the information to measure the latency.
+    /// Unlike ICMP, this does not need any privilege and it works with the hosts dropping ICMP.
+    pub async fn latency(&self, address: impl ToSocketAddrs) -> Result<Duration, Error> {
+        let socket = Self::connect(address).await?;
+        let mut request = Vec::with_capacity(4 + 1 + A2S_INFO_PAYLOAD.len());
+
+        request.extend_from_slice(&SINGLE_PACKET.to_le_bytes());
+        request.push(A2S_INFO);
+        request.extend_from_slice(A2S_INFO_PAYLOAD);
+
+        let start = Instant::now();
+
+        socket.send(&request).await?;
+        self.receive(&socket).await?;
+
+        Ok(start.elapsed())
+    }
+
+    /// Get the players connected to a server using A2S_PLAYER.
+    pub async fn players(&self, address: impl ToSocketAddrs) -> Result<Vec<Player>, Error> {
+        let socket = Self::connect(address).await?;
+        let response = self.request(&socket, A2S_PLAYER, &[], Some(NO_CHALLENGE), S2A_PLAYER).await?;
+
+        Player::read_all(&mut Reader::new(&response))
+    }
+
+    /// Get the rules (console variables) of a server using A2S_RULES.
+    pub async fn rules(&self, address: impl ToSocketAddrs) -> Result<Rules, Error> {
+        let socket = Self::connect(address).await?;
+        let response = self.request(&socket, A2S_RULES, &[], Some(NO_CHALLENGE), S2A_RULES).await?;
+
+        rules::read_rules(&mut Reader::new(&response))
+    }
+
+    /// List the addresses of the servers matching the filter using the master server query protocol.
+    ///
+    /// The filter is a list of key/value pairs separated by backslashes, like `\appid\440\empty\1`.
+    /// The master server sends the addresses by pages, each page is requested after the last address of the previous one.
+    pub async fn master_servers(
+        &self,
+        master_server: impl ToSocketAddrs,
+        region: Region,
+        filter: &str,
+    ) -> Result<Vec<SocketAddrV4>, Error> {
+        let socket = Self::connect(master_server).await?;
+        let mut addresses: Vec<SocketAddrV4> = Vec::new();
+        let mut seed = END_OF_LIST;
+
+        for _ in 0..MAX_MASTER_PAGES {
+            // Unlike the responses, the requests have no header.
+            socket.send(&master::write_request(region, seed, filter)).await?;
+
+            let response = self.receive(&socket).await?;
+            let mut reader = Reader::new(&response);
+
+            match reader.u8()? {
+                M2A_SERVER_BATCH => (),
+                kind => return Err(Error::InvalidResponse(format!("Unexpected response type {:#x}", kind))),
+            }
+
+            let page = master::read_addresses(&mut reader)?;
+            let finished = page.last().is_none_or(|last| *last == END_OF_LIST || *last == seed);
+
+            addresses.extend(page.into_iter().filter(|address| *address != END_OF_LIST));
+
+            if finished {
+                return Ok(addresses);
+            }
+
+            seed = *addresses.last().expect("at least one address");
+        }
+
+        warn!(
+            "The master server sent more than {} pages, the others are ignored",
+            MAX_MASTER_PAGES
+        );
+
+        Ok(addresses)
+    }
+
+    async fn connect(address: impl ToSocketAddrs) -> Result<UdpSocket, Error> {
+        let address = lookup_host(address).await?.next().ok_or(Error::NoAddress)?;
+        let local_address: SocketAddr = match address {
+            SocketAddr::V4(_) => ([0u8; 4], 0).into(),
+            SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+        };
+        let socket = UdpSocket::bind(local_address).await?;
+
+        socket.connect(address).await?;
+
+        Ok(socket)
+    }
+
+    /// Send a request and returns the response, without the header and the type byte.
+    ///
+    /// `challenge` is appended to the request, if the server answers with a challenge the request is sent again
+    /// with the challenge received.
+    async fn request(
+        &self,
+        socket: &UdpSocket,
+        kind: u8,
+        payload: &[u8],
+        mut challenge: Option<i32>,
+        expected: u8,
+    ) -> Result<Vec<u8>, Error> {
+        let mut request = Vec::with_capacity(4 + 1 + payload.len() + 4);
+
+        request.extend_from_slice(&SINGLE_PACKET.to_le_bytes());
+        request.push(kind);
+        request.extend_from_slice(payload);
+
+        let request_length = request.len();
+
+        for _ in 0..=MAX_CHALLENGES {
+            request.truncate(request_length);
+
+            if let Some(challenge) = challenge {
+                request.extend_from_slice(&challenge.to_le_bytes());
+            }
+
+            socket.send(&request).await?;
+
+            let response = self.receive(socket).await?;
+            let mut reader = Reader::new(&response);
+
+            match reader.u8()? {
+                S2C_CHALLENGE => {
+                    let received = reader.i32()?;
+
+                    trace!("Challenge {:#x} received", received);
+
+                    challenge = Some(received);
+                }
+                kind if kind == expected => return Ok(reader.remaining().to_vec()),
+                kind => return Err(Error::InvalidResponse(format!("Unexpected response type {:#x}", kind))),
+            }
+        }
+
+        Err(Error::InvalidResponse("Too many challenges".to_string()))
+    }
+
+    /// Receive a response, the responses split in several packets are reassembled.
+    async fn receive(&self, socket: &UdpSocket) -> Result<Vec<u8>, Error> {
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+        let mut split_response = SplitResponse::default();
+
+        loop {
+            let size = tokio::time::timeout(self.timeout, socket.recv(&mut buffer))
+                .await
+                .map_err(|_| Error::Timeout)??;
+            let mut reader = Reader::new(&buffer[..size]);
+
+            match reader.i32()? {
+                SINGLE_PACKET => return Ok(reader.remaining().to_vec()),
+                SPLIT_PACKET => {
+                    if let Some(response) = split_response.push(SplitPacket::read(&mut reader)?)? {
+                        return Ok(response);
+                    }
+                }
+                header => return Err(Error::InvalidResponse(format!("Invalid header {:#x}", header))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            info::tests::{team_fortress_info, write_info},
+            master::tests::write_addresses,
+            player::tests::{players, write_players},
+            rules::tests::{rules, write_rules},
+            Client, Error, Region,
+        },
+        std::{
+            net::{SocketAddr, SocketAddrV4},
+            time::Duration,
+        },
+        tokio::net::UdpSocket,
+    };
+
+    const CHALLENGE: [u8; 4] = [0x4A, 0x0B, 0xC0, 0x1D];
+
+    /// Start a stand-in server on the loopback, `respond` returns the packets to send for each request received.
+    async fn stand_in(respond: impl Fn(&[u8]) -> Vec<Vec<u8>> + Send + 'static) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = socket.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 1400];
+
+            while let Ok((size, from)) = socket.recv_from(&mut buffer).await {
+                for packet in respond(&buffer[..size]) {
+                    socket.send_to(&packet, from).await.unwrap();
+                }
+            }
+        });
+
+        address
+    }
+
+    fn single_packet(kind: u8, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0xFF, 0xFF, 0xFF, 0xFF, kind];
+
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    /// Answer a challenge, then the response if the challenge is sent back.
+    fn with_challenge(request: &[u8], response: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        match request.ends_with(&CHALLENGE) {
+            true => response,
+            false => vec![single_packet(b'A', &CHALLENGE)],
+        }
+    }
+
+    fn client() -> Client {
+        Client::new(Duration::from_millis(500))
+    }
+
+    #[tokio::test]
+    async fn test_info() {
+        let address = stand_in(|request| {
+            assert!(request.starts_with(b"\xFF\xFF\xFF\xFFTSource Engine Query\0"));
+
+            vec![single_packet(b'I', &write_info(&team_fortress_info()))]
+        })
+        .await;
+
+        assert_eq!(team_fortress_info(), client().info(address).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_info_with_challenge() {
+        let address =
+            stand_in(|request| with_challenge(request, vec![single_packet(b'I', &write_info(&team_fortress_info()))])).await;
+
+        assert_eq!(team_fortress_info(), client().info(address).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_info_split_packets() {
+        let address = stand_in(|request| {
+            let response = single_packet(b'I', &write_info(&team_fortress_info()));
+            let (first, second) = response.split_at(20);
+            let split_packet = |number: u8, payload: &[u8]| {
+                let mut packet = vec![0xFE, 0xFF, 0xFF, 0xFF, 0x01, 0x00, 0x00, 0x00, 2, number, 0xE0, 0x04];
+
+                packet.extend_from_slice(payload);
+                packet
+            };
+
+            // The packets are sent in reverse order.
+            with_challenge(request, vec![split_packet(1, second), split_packet(0, first)])
+        })
+        .await;
+
+        assert_eq!(team_fortress_info(), client().info(address).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_info_timeout() {
+        let address = stand_in(|_| Vec::new()).await;
+        let client = Client::new(Duration::from_millis(50));
+
+        assert!(matches!(client.info(address).await, Err(Error::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_info_unexpected_response() {
+        let address = stand_in(|_| vec![single_packet(b'D', &[0])]).await;
+
+        assert!(matches!(client().info(address).await, Err(Error::InvalidResponse(_))));
+    }
+
+    #[tokio::test]
+    async fn test_info_too_many_challenges() {
+        let address = stand_in(|_| vec![single_packet(b'A', &CHALLENGE)]).await;
+
+        assert!(matches!(client().info(address).await, Err(Error::InvalidResponse(_))));
+    }
+
+    #[tokio::test]
+    async fn test_players() {
+        let address = stand_in(|request| {
+            assert!(request.starts_with(b"\xFF\xFF\xFF\xFFU"));
+
+            with_challenge(request, vec![single_packet(b'D', &write_players(&players()))])
+        })
+        .await;
+
+        assert_eq!(players(), client().players(address).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_players_requests_challenge() {
+        let address = stand_in(|request| {
+            assert_eq!(b"\xFF\xFF\xFF\xFFU\xFF\xFF\xFF\xFF", request);
+
+            vec![single_packet(b'D', &[0])]
+        })
+        .await;
+
+        assert!(client().players(address).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rules_split_packets() {
+        let address = stand_in(|request| {
+            assert!(request.starts_with(b"\xFF\xFF\xFF\xFFV"));
+
+            let response = single_packet(b'E', &write_rules(&rules()));
+            let packets = response
+                .chunks(16)
+                .enumerate()
+                .map(|(number, payload)| {
+                    let mut packet = vec![0xFE, 0xFF, 0xFF, 0xFF, 0x02, 0x00, 0x00, 0x00];
+
+                    packet.extend_from_slice(&[response.len().div_ceil(16) as u8, number as u8, 0x10, 0x00]);
+                    packet.extend_from_slice(payload);
+                    packet
+                })
+                .collect();
+
+            with_challenge(request, packets)
+        })
+        .await;
+
+        assert_eq!(rules(), client().rules(address).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_latency() {
+        let address = stand_in(|request| with_challenge(request, Vec::new())).await;
+        let latency = client().latency(address).await.unwrap();
+
+        assert!(latency < Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_latency_timeout() {
+        let address = stand_in(|_| Vec::new()).await;
+
+        assert!(matches!(
+            Client::new(Duration::from_millis(50)).latency(address).await,
+            Err(Error::Timeout)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_master_servers() {
+        let first_page: Vec<SocketAddrV4> = vec!["1.2.3.4:27015".parse().unwrap(), "1.2.3.5:27015".parse().unwrap()];
+        let second_page: Vec<SocketAddrV4> = vec!["5.6.7.8:27016".parse().unwrap(), "0.0.0.0:0".parse().unwrap()];
+        let (first, second) = (first_page.clone(), second_page.clone());
+        let address = stand_in(move |request| {
+            let page = match request {
+                b"\x31\x030.0.0.0:0\0\\appid\\440\0" => &first,
+                b"\x31\x031.2.3.5:27015\0\\appid\\440\0" => &second,
+                request => panic!("Unexpected request {:?}", request),
+            };
+
+            vec![single_packet(0x66, &write_addresses(page))]
+        })
+        .await;
+
+        assert_eq!(
+            vec![first_page[0], first_page[1], second_page[0]],
+            client()
+                .master_servers(address, Region::Europe, "\\appid\\440")
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_master_servers_empty() {
+        let address = stand_in(|_| vec![single_packet(0x66, &write_addresses(&[]))]).await;
+
+        assert!(client().master_servers(address, Region::World, "").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+use {
+    crate::{packet::Reader, Error},
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{Display, Formatter},
+        net::{Ipv4Addr, SocketAddrV4},
+    },
+};
+
+/// The address of the Steam master server.
+pub const STEAM_MASTER_SERVER: &str = "hl2master.steampowered.com:27011";
+
+pub(crate) const A2M_GET_SERVERS_BATCH2: u8 = 0x31;
+pub(crate) const M2A_SERVER_BATCH: u8 = 0x66;
+/// The seed to request the first addresses, the master server also sends it to mark the end of the list.
+pub(crate) const END_OF_LIST: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0);
+
+/// The region of the servers listed by the master server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Region {
+    UsEast,
+    UsWest,
+    SouthAmerica,
+    Europe,
+    Asia,
+    Australia,
+    MiddleEast,
+    Africa,
+    #[default]
+    World,
+}
+
+impl Region {
+    pub const ALL: [Region; 9] = [
+        Region::World,
+        Region::UsEast,
+        Region::UsWest,
+        Region::SouthAmerica,
+        Region::Europe,
+        Region::Asia,
+        Region::Australia,
+        Region::MiddleEast,
+        Region::Africa,
+    ];
+
+    /// The code sent to the master server.
+    pub fn code(&self) -> u8 {
+        match self {
+            Region::UsEast => 0x00,
+            Region::UsWest => 0x01,
+            Region::SouthAmerica => 0x02,
+            Region::Europe => 0x03,
+            Region::Asia => 0x04,
+            Region::Australia => 0x05,
+            Region::MiddleEast => 0x06,
+            Region::Africa => 0x07,
+            Region::World => 0xFF,
+        }
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Region::UsEast => write!(f, "US East"),
+            Region::UsWest => write!(f, "US West"),
+            Region::SouthAmerica => write!(f, "South America"),
+            Region::Europe => write!(f, "Europe"),
+            Region::Asia => write!(f, "Asia"),
+            Region::Australia => write!(f, "Australia"),
+            Region::MiddleEast => write!(f, "Middle East"),
+            Region::Africa => write!(f, "Africa"),
+            Region::World => write!(f, "World"),
+        }
+    }
+}
+
+/// Write the request of the addresses following `seed`.
+///
+/// The filter is a list of key/value pairs separated by backslashes, like `\appid\440\empty\1`.
+/// See https://developer.valvesoftware.com/wiki/Master_Server_Query_Protocol#Filter.
+pub(crate) fn write_request(region: Region, seed: SocketAddrV4, filter: &str) -> Vec<u8> {
+    let seed = seed.to_string();
+    let mut request = Vec::with_capacity(2 + seed.len() + 1 + filter.len() + 1);
+
+    request.push(A2M_GET_SERVERS_BATCH2);
+    request.push(region.code());
+    request.extend_from_slice(seed.as_bytes());
+    request.push(0);
+    request.extend_from_slice(filter.as_bytes());
+    request.push(0);
+    request
+}
+
+/// Read the addresses of a response, the header and the type byte must already be read.
+///
+/// The port is the only big endian value of the protocols.
+pub(crate) fn read_addresses(reader: &mut Reader) -> Result<Vec<SocketAddrV4>, Error> {
+    // A line feed follows the type byte.
+    reader.u8()?;
+
+    let bytes = reader.remaining();
+
+    if !bytes.len().is_multiple_of(6) {
+        return Err(Error::Truncated);
+    }
+
+    Ok(bytes
+        .chunks_exact(6)
+        .map(|address| {
+            SocketAddrV4::new(
+                Ipv4Addr::new(address[0], address[1], address[2], address[3]),
+                u16::from_be_bytes([address[4], address[5]]),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        super::{read_addresses, write_request, Region, END_OF_LIST},
+        crate::{packet::Reader, Error},
+        std::net::SocketAddrV4,
+    };
+
+    /// Write the response to a request, without the header and the type byte.
+    pub(crate) fn write_addresses(addresses: &[SocketAddrV4]) -> Vec<u8> {
+        let mut bytes = vec![b'\n'];
+
+        for address in addresses {
+            bytes.extend_from_slice(&address.ip().octets());
+            bytes.extend_from_slice(&address.port().to_be_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn test_write_request() {
+        let seed: SocketAddrV4 = "1.2.3.4:27015".parse().unwrap();
+
+        assert_eq!(
+            b"\x31\x031.2.3.4:27015\0\\appid\\440\0".to_vec(),
+            write_request(Region::Europe, seed, "\\appid\\440")
+        );
+        assert_eq!(
+            b"\x31\xFF0.0.0.0:0\0\0".to_vec(),
+            write_request(Region::World, END_OF_LIST, "")
+        );
+    }
+
+    #[test]
+    fn test_read_addresses() {
+        let addresses = vec![
+            "1.2.3.4:27015".parse().unwrap(),
+            "192.168.0.1:27016".parse().unwrap(),
+            END_OF_LIST,
+        ];
+        let bytes = write_addresses(&addresses);
+
+        assert_eq!(addresses, read_addresses(&mut Reader::new(&bytes)).unwrap());
+    }
+
+    #[test]
+    fn test_read_truncated_addresses() {
+        let bytes = [b'\n', 1, 2, 3, 4, 0x69];
+
+        assert!(matches!(read_addresses(&mut Reader::new(&bytes)), Err(Error::Truncated)));
+    }
+}
@@ -0,0 +1,220 @@
+use {crate::Error, std::collections::BTreeMap};
+
+/// The header of a response sent in a single packet.
+pub(crate) const SINGLE_PACKET: i32 = -1;
+/// The header of a response split in several packets.
+pub(crate) const SPLIT_PACKET: i32 = -2;
+
+/// Read the little endian values of a response.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        if self.bytes.len() < N {
+            return Err(Error::Truncated);
+        }
+
+        let (value, rest) = self.bytes.split_at(N);
+
+        self.bytes = rest;
+
+        Ok(value.try_into().expect("slice of N bytes"))
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    pub fn i32(&mut self) -> Result<i32, Error> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    pub fn f32(&mut self) -> Result<f32, Error> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+
+    /// Read a null terminated string.
+    ///
+    /// The strings are not always valid UTF-8, the invalid sequences are replaced.
+    pub fn string(&mut self) -> Result<String, Error> {
+        let end = self.bytes.iter().position(|b| *b == 0).ok_or(Error::Truncated)?;
+        let value = String::from_utf8_lossy(&self.bytes[..end]).into_owned();
+
+        self.bytes = &self.bytes[end + 1..];
+
+        Ok(value)
+    }
+}
+
+/// One part of a response split in several packets.
+#[derive(Debug, PartialEq)]
+pub(crate) struct SplitPacket {
+    pub id: i32,
+    pub total: u8,
+    pub number: u8,
+    pub payload: Vec<u8>,
+}
+
+impl SplitPacket {
+    /// Read a split packet, the header `0xFFFFFFFE` must already be read.
+    pub fn read(reader: &mut Reader) -> Result<Self, Error> {
+        let id = reader.i32()?;
+
+        // The most significant bit is set when the payload is compressed with bzip2.
+        if id as u32 & 0x8000_0000 != 0 {
+            return Err(Error::Compressed);
+        }
+
+        let total = reader.u8()?;
+        let number = reader.u8()?;
+        let _size = reader.u16()?;
+
+        if total == 0 || number >= total {
+            return Err(Error::InvalidResponse(format!(
+                "Invalid split packet {} of {}",
+                number, total
+            )));
+        }
+
+        Ok(Self {
+            id,
+            total,
+            number,
+            payload: reader.remaining().to_vec(),
+        })
+    }
+}
+
+/// Reassemble the packets of a split response, whatever the order they are received.
+#[derive(Default)]
+pub(crate) struct SplitResponse {
+    id: Option<i32>,
+    total: u8,
+    packets: BTreeMap<u8, Vec<u8>>,
+}
+
+impl SplitResponse {
+    /// Add a packet, returns the complete payload when all the packets are received.
+    ///
+    /// The payload starts after the single packet header `0xFFFFFFFF` of the first packet.
+    pub fn push(&mut self, packet: SplitPacket) -> Result<Option<Vec<u8>>, Error> {
+        match self.id {
+            None => {
+                self.id = Some(packet.id);
+                self.total = packet.total;
+            }
+            Some(id) if id != packet.id || self.total != packet.total => {
+                return Err(Error::InvalidResponse(format!(
+                    "Unexpected split packet {} while reading response {}",
+                    packet.id, id
+                )));
+            }
+            Some(_) => (),
+        }
+
+        self.packets.insert(packet.number, packet.payload);
+
+        if self.packets.len() < self.total as usize {
+            return Ok(None);
+        }
+
+        let payload: Vec<u8> = std::mem::take(&mut self.packets).into_values().flatten().collect();
+        let mut reader = Reader::new(&payload);
+
+        if reader.i32()? != SINGLE_PACKET {
+            return Err(Error::InvalidResponse("Invalid header in split response".to_string()));
+        }
+
+        Ok(Some(reader.remaining().to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{Reader, SplitPacket, SplitResponse},
+        crate::Error,
+    };
+
+    fn split_packet(id: i32, total: u8, number: u8, payload: &[u8]) -> Vec<u8> {
+        let mut packet = Vec::new();
+
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.push(total);
+        packet.push(number);
+        packet.extend_from_slice(&1248u16.to_le_bytes());
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    #[test]
+    fn test_read_values() {
+        let bytes = [0x2A, 0x10, 0x27, b'h', b'i', 0, 0xFF, 0xFF, 0xFF, 0xFF];
+        let mut reader = Reader::new(&bytes);
+
+        assert_eq!(42, reader.u8().unwrap());
+        assert_eq!(10000, reader.u16().unwrap());
+        assert_eq!("hi", reader.string().unwrap());
+        assert_eq!(-1, reader.i32().unwrap());
+        assert!(reader.is_empty());
+        assert!(matches!(reader.u8(), Err(Error::Truncated)));
+    }
+
+    #[test]
+    fn test_read_unterminated_string() {
+        assert!(matches!(Reader::new(b"no end").string(), Err(Error::Truncated)));
+    }
+
+    #[test]
+    fn test_reassemble_out_of_order() {
+        let mut response = SplitResponse::default();
+        let first = split_packet(7, 2, 0, &[0xFF, 0xFF, 0xFF, 0xFF, b'I', 1]);
+        let second = split_packet(7, 2, 1, &[2, 3]);
+
+        let second = SplitPacket::read(&mut Reader::new(&second)).unwrap();
+        let first = SplitPacket::read(&mut Reader::new(&first)).unwrap();
+
+        assert_eq!(None, response.push(second).unwrap());
+        assert_eq!(Some(vec![b'I', 1, 2, 3]), response.push(first).unwrap());
+    }
+
+    #[test]
+    fn test_compressed_split_packet() {
+        let packet = split_packet(i32::MIN | 7, 2, 0, &[]);
+
+        assert!(matches!(SplitPacket::read(&mut Reader::new(&packet)), Err(Error::Compressed)));
+    }
+
+    #[test]
+    fn test_mixed_split_responses() {
+        let mut response = SplitResponse::default();
+        let first = split_packet(7, 2, 0, &[]);
+        let other = split_packet(8, 2, 1, &[]);
+
+        response.push(SplitPacket::read(&mut Reader::new(&first)).unwrap()).unwrap();
+
+        assert!(response.push(SplitPacket::read(&mut Reader::new(&other)).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use {
+    crate::{packet::Reader, Error},
+    std::time::Duration,
+};
+
+/// A player connected to a server, from the response to A2S_PLAYER.
+///
+/// See https://developer.valvesoftware.com/wiki/Server_queries#A2S_PLAYER.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Player {
+    /// The name is empty while the player is connecting.
+    pub name: String,
+    pub score: i32,
+    /// How long the player has been connected.
+    pub duration: Duration,
+}
+
+impl Player {
+    /// Read the response, the header and the type byte `D` must already be read.
+    pub(crate) fn read_all(reader: &mut Reader) -> Result<Vec<Self>, Error> {
+        let count = reader.u8()?;
+        let mut players = Vec::with_capacity(count as usize);
+
+        for _ in 0..count {
+            // The index is always 0 for Team Fortress 2.
+            reader.u8()?;
+
+            players.push(Player {
+                name: reader.string()?,
+                score: reader.i32()?,
+                duration: Duration::try_from_secs_f32(reader.f32()?).unwrap_or_default(),
+            });
+        }
+
+        Ok(players)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        super::Player,
+        crate::{packet::Reader, Error},
+        std::time::Duration,
+    };
+
+    pub(crate) fn players() -> Vec<Player> {
+        vec![
+            Player {
+                name: "Soldier".to_string(),
+                score: 12,
+                duration: Duration::from_secs(754),
+            },
+            Player {
+                name: "Медик".to_string(),
+                score: -1,
+                duration: Duration::from_millis(1500),
+            },
+        ]
+    }
+
+    /// Write the response to A2S_PLAYER, without the header and the type byte.
+    pub(crate) fn write_players(players: &[Player]) -> Vec<u8> {
+        let mut bytes = vec![players.len() as u8];
+
+        for player in players {
+            bytes.push(0);
+            bytes.extend_from_slice(player.name.as_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(&player.score.to_le_bytes());
+            bytes.extend_from_slice(&player.duration.as_secs_f32().to_le_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn test_read_players() {
+        let bytes = write_players(&players());
+
+        assert_eq!(players(), Player::read_all(&mut Reader::new(&bytes)).unwrap());
+    }
+
+    #[test]
+    fn test_read_negative_duration() {
+        let mut bytes = write_players(&players()[..1]);
+        let length = bytes.len();
+
+        bytes[length - 4..].copy_from_slice(&(-1.0f32).to_le_bytes());
+
+        assert_eq!(
+            Duration::ZERO,
+            Player::read_all(&mut Reader::new(&bytes)).unwrap()[0].duration
+        );
+    }
+
+    #[test]
+    fn test_read_missing_players() {
+        let bytes = write_players(&players());
+
+        assert!(matches!(
+            Player::read_all(&mut Reader::new(&bytes[..bytes.len() - 1])),
+            Err(Error::Truncated)
+        ));
+    }
+}
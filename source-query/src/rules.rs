@@ -0,0 +1,74 @@
+use {
+    crate::{packet::Reader, Error},
+    std::collections::BTreeMap,
+};
+
+/// The rules (console variables) of a server by name, from the response to A2S_RULES.
+///
+/// See https://developer.valvesoftware.com/wiki/Server_queries#A2S_RULES.
+pub type Rules = BTreeMap<String, String>;
+
+/// Read the response, the header and the type byte `E` must already be read.
+pub(crate) fn read_rules(reader: &mut Reader) -> Result<Rules, Error> {
+    let count = reader.u16()?;
+    let mut rules = Rules::new();
+
+    for _ in 0..count {
+        let name = reader.string()?;
+        let value = reader.string()?;
+
+        rules.insert(name, value);
+    }
+
+    Ok(rules)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        super::{read_rules, Rules},
+        crate::{packet::Reader, Error},
+    };
+
+    pub(crate) fn rules() -> Rules {
+        Rules::from([
+            ("mp_timelimit".to_string(), "30".to_string()),
+            ("sv_downloadurl".to_string(), "https://fastdl.example.com/tf/".to_string()),
+            ("sv_pure".to_string(), "2".to_string()),
+            ("tf_gamemode_payload".to_string(), "1".to_string()),
+        ])
+    }
+
+    /// Write the response to A2S_RULES, without the header and the type byte.
+    pub(crate) fn write_rules(rules: &Rules) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&(rules.len() as u16).to_le_bytes());
+
+        for (name, value) in rules {
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(value.as_bytes());
+            bytes.push(0);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn test_read_rules() {
+        let bytes = write_rules(&rules());
+
+        assert_eq!(rules(), read_rules(&mut Reader::new(&bytes)).unwrap());
+    }
+
+    #[test]
+    fn test_read_missing_rules() {
+        let bytes = write_rules(&rules());
+
+        assert!(matches!(
+            read_rules(&mut Reader::new(&bytes[..bytes.len() - 1])),
+            Err(Error::Truncated)
+        ));
+    }
+}
@@ -5,7 +5,7 @@ use {
     serde::{Deserialize, Serialize},
     std::{
         fmt::{Display, Formatter},
-        net::Ipv4Addr,
+        net::IpAddr,
         path::PathBuf,
     },
     tokio::io::{AsyncBufReadExt, BufReader},
@@ -14,7 +14,7 @@ use {
 #[derive(Eq, PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum BlacklistEntry {
     Text(String),
-    Ip(Ipv4Addr),
+    Ip(IpAddr),
     IpPort(IpPort),
 }
 
@@ -28,7 +28,7 @@ impl BlacklistEntry {
     pub fn accept(&self, server: &Server) -> bool {
         match self {
             BlacklistEntry::Text(text) => !server.name.contains(text),
-            BlacklistEntry::Ip(ip) => server.ip_port.ip() != ip,
+            BlacklistEntry::Ip(ip) => server.ip_port.host().ip() != Some(ip),
             BlacklistEntry::IpPort(ip_port) => &server.ip_port != ip_port,
         }
     }
@@ -52,55 +52,42 @@ impl Display for BlacklistEntry {
 
 mod parsing {
     use {
-        crate::application::{blacklist::BlacklistEntry, IpPort},
+        crate::application::{blacklist::BlacklistEntry, ip_port::Host, IpPort},
         nom::{
             branch::alt,
-            bytes::complete::tag,
-            character::complete::digit1,
-            combinator::{map, map_res},
-            sequence::tuple,
+            combinator::{map, map_opt, rest},
             IResult,
         },
-        std::net::Ipv4Addr,
+        std::{net::IpAddr, str::FromStr},
     };
 
-    fn parse_u8(input: &str) -> IResult<&str, u8> {
-        let mut parser = map_res(digit1, str::parse);
+    fn parse_host_port_impl(input: &str) -> Option<IpPort> {
+        let (host, port) = input.rsplit_once(':')?;
 
-        parser(input)
-    }
-
-    fn parse_u16(input: &str) -> IResult<&str, u16> {
-        let mut parser = map_res(digit1, str::parse);
-
-        parser(input)
-    }
-
-    fn parse_ip_impl(input: &str) -> IResult<&str, Ipv4Addr> {
-        let mut parser = map(
-            tuple((parse_u8, tag("."), parse_u8, tag("."), parse_u8, tag("."), parse_u8)),
-            |(a, _, b, _, c, _, d)| Ipv4Addr::new(a, b, c, d),
-        );
+        // An IPv6 followed by a port must be between brackets, like [::1]:27015.
+        if host.contains(':') && !(host.starts_with('[') && host.ends_with(']')) {
+            return None;
+        }
 
-        parser(input)
+        Some(IpPort::new(Host::from_str(host).ok()?, u16::from_str(port).ok()?))
     }
 
     fn parse_ip(input: &str) -> IResult<&str, BlacklistEntry> {
-        let mut parser = map(parse_ip_impl, |ip| BlacklistEntry::Ip(ip));
+        let mut parser = map_opt(rest, |input: &str| IpAddr::from_str(input).ok().map(BlacklistEntry::Ip));
 
         parser(input)
     }
 
     fn parse_ip_port(input: &str) -> IResult<&str, BlacklistEntry> {
-        let mut parser = map(tuple((parse_ip_impl, tag(":"), parse_u16)), |(ip, _sep, port)| {
-            BlacklistEntry::IpPort(IpPort::new(ip, port))
-        });
+        let mut parser = map_opt(rest, |input: &str| parse_host_port_impl(input).map(BlacklistEntry::IpPort));
 
         parser(input)
     }
 
     fn parse_text(input: &str) -> IResult<&str, BlacklistEntry> {
-        Ok(("", BlacklistEntry::Text(input.to_string())))
+        let mut parser = map(rest, |input: &str| BlacklistEntry::Text(input.to_string()));
+
+        parser(input)
     }
 
     pub fn parse_entry(input: &str) -> IResult<&str, BlacklistEntry> {
@@ -186,18 +173,26 @@ mod tests {
     use {
         crate::application::{
             blacklist::{Blacklist, BlacklistEntry},
+            ip_port::Host,
             IpPort, Server,
         },
-        std::net::Ipv4Addr,
+        std::net::{Ipv4Addr, Ipv6Addr},
         test_case::test_case,
     };
 
     #[test_case("hello", BlacklistEntry::Text("hello".to_string()))]
-    #[test_case("123.45.67.89", BlacklistEntry::Ip(Ipv4Addr::new(123, 45, 67, 89)))]
+    #[test_case("123.45.67.89", BlacklistEntry::Ip(Ipv4Addr::new(123, 45, 67, 89).into()))]
     #[test_case(
         "123.45.67.89:321",
         BlacklistEntry::IpPort(IpPort::new(Ipv4Addr::new(123, 45, 67, 89), 321))
     )]
+    #[test_case("2001:db8::1", BlacklistEntry::Ip(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).into()))]
+    #[test_case("[::1]:27015", BlacklistEntry::IpPort(IpPort::new(Ipv6Addr::LOCALHOST, 27015)))]
+    #[test_case(
+        "tf2.example.com:27015",
+        BlacklistEntry::IpPort(IpPort::new("tf2.example.com".parse::<Host>().unwrap(), 27015))
+    )]
+    #[test_case("1.2.3.4 hello", BlacklistEntry::Text("1.2.3.4 hello".to_string()))]
     fn test_entry_parse(input: &str, expected: BlacklistEntry) {
         assert_eq!(BlacklistEntry::parse(input), expected)
     }
@@ -206,6 +201,7 @@ mod tests {
     fn test_blacklist_by_ip() {
         let accepted_server = Server {
             name: "test 2".to_string(),
+            raw_name: "test 2".to_string(),
             max_players_count: 0,
             current_players_count: 0,
             map: Default::default(),
@@ -223,9 +219,11 @@ mod tests {
             has_all_talk: false,
             has_random_crits: false,
             need_password: false,
+            rules: Default::default(),
         };
         let rejected_server = Server {
             name: "test".to_string(),
+            raw_name: "test".to_string(),
             max_players_count: 0,
             current_players_count: 0,
             map: Default::default(),
@@ -243,10 +241,11 @@ mod tests {
             has_all_talk: false,
             has_random_crits: false,
             need_password: false,
+            rules: Default::default(),
         };
         let mut blacklist = Blacklist::default();
 
-        blacklist.push(BlacklistEntry::Ip(Ipv4Addr::new(1, 2, 3, 4)));
+        blacklist.push(BlacklistEntry::Ip(Ipv4Addr::new(1, 2, 3, 4).into()));
 
         assert_eq!(false, blacklist.accept(&rejected_server));
         assert_eq!(true, blacklist.accept(&accepted_server));
@@ -256,6 +255,7 @@ mod tests {
     fn test_blacklist_by_ip_port() {
         let accepted_server = Server {
             name: "test 2".to_string(),
+            raw_name: "test 2".to_string(),
             max_players_count: 0,
             current_players_count: 0,
             map: Default::default(),
@@ -273,9 +273,11 @@ mod tests {
             has_all_talk: false,
             has_random_crits: false,
             need_password: false,
+            rules: Default::default(),
         };
         let rejected_server = Server {
             name: "test".to_string(),
+            raw_name: "test".to_string(),
             max_players_count: 0,
             current_players_count: 0,
             map: Default::default(),
@@ -293,6 +295,7 @@ mod tests {
             has_all_talk: false,
             has_random_crits: false,
             need_password: false,
+            rules: Default::default(),
         };
         let mut blacklist = Blacklist::default();
 
@@ -306,6 +309,7 @@ mod tests {
     fn test_blacklist_by_text_port() {
         let accepted_server = Server {
             name: "test 2".to_string(),
+            raw_name: "test 2".to_string(),
             max_players_count: 0,
             current_players_count: 0,
             map: Default::default(),
@@ -323,9 +327,11 @@ mod tests {
             has_all_talk: false,
             has_random_crits: false,
             need_password: false,
+            rules: Default::default(),
         };
         let rejected_server = Server {
             name: "test_reject".to_string(),
+            raw_name: "test_reject".to_string(),
             max_players_count: 0,
             current_players_count: 0,
             map: Default::default(),
@@ -343,6 +349,7 @@ mod tests {
             has_all_talk: false,
             has_random_crits: false,
             need_password: false,
+            rules: Default::default(),
         };
         let mut blacklist = Blacklist::default();
 
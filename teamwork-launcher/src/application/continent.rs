@@ -0,0 +1,89 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// The continents of the United Nations geoscheme, used to group the countries.
+#[derive(Debug, Hash, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Continent {
+    Africa,
+    Antarctica,
+    Asia,
+    Europe,
+    NorthAmerica,
+    Oceania,
+    SouthAmerica,
+}
+
+impl Continent {
+    pub const ALL: [Continent; 7] = [
+        Continent::Africa,
+        Continent::Antarctica,
+        Continent::Asia,
+        Continent::Europe,
+        Continent::NorthAmerica,
+        Continent::Oceania,
+        Continent::SouthAmerica,
+    ];
+
+    /// The continent of a country, identified by its ISO 3166-1 alpha-2 code like the `iso_country` data.
+    pub fn from_country_code(code: &str) -> Option<Continent> {
+        let continent = match code.to_ascii_uppercase().as_str() {
+            "DZ" | "AO" | "BJ" | "BW" | "BF" | "BI" | "CV" | "CM" | "CF" | "TD" | "KM" | "CG" | "CD" | "CI" | "DJ"
+            | "EG" | "GQ" | "ER" | "SZ" | "ET" | "GA" | "GM" | "GH" | "GN" | "GW" | "KE" | "LS" | "LR" | "LY" | "MG"
+            | "MW" | "ML" | "MR" | "MU" | "YT" | "MA" | "MZ" | "NA" | "NE" | "NG" | "RE" | "RW" | "SH" | "ST" | "SN"
+            | "SC" | "SL" | "SO" | "ZA" | "SS" | "SD" | "TZ" | "TG" | "TN" | "UG" | "EH" | "ZM" | "ZW" => Continent::Africa,
+            "AQ" | "BV" | "GS" | "HM" | "TF" => Continent::Antarctica,
+            "AF" | "AM" | "AZ" | "BH" | "BD" | "BT" | "BN" | "KH" | "CN" | "CY" | "GE" | "HK" | "IN" | "ID" | "IR"
+            | "IQ" | "IL" | "JP" | "JO" | "KZ" | "KW" | "KG" | "LA" | "LB" | "MO" | "MY" | "MV" | "MN" | "MM" | "NP"
+            | "KP" | "OM" | "PK" | "PS" | "PH" | "QA" | "SA" | "SG" | "KR" | "LK" | "SY" | "TW" | "TJ" | "TH" | "TL"
+            | "TR" | "TM" | "AE" | "UZ" | "VN" | "YE" | "IO" | "CC" | "CX" => Continent::Asia,
+            "AX" | "AL" | "AD" | "AT" | "BY" | "BE" | "BA" | "BG" | "HR" | "CZ" | "DK" | "EE" | "FO" | "FI" | "FR"
+            | "DE" | "GI" | "GR" | "GG" | "VA" | "HU" | "IS" | "IE" | "IM" | "IT" | "JE" | "XK" | "LV" | "LI" | "LT"
+            | "LU" | "MT" | "MD" | "MC" | "ME" | "NL" | "MK" | "NO" | "PL" | "PT" | "RO" | "RU" | "SM" | "RS" | "SK"
+            | "SI" | "ES" | "SJ" | "SE" | "CH" | "UA" | "GB" => Continent::Europe,
+            "AI" | "AG" | "AW" | "BS" | "BB" | "BZ" | "BM" | "BQ" | "VG" | "CA" | "KY" | "CR" | "CU" | "CW" | "DM"
+            | "DO" | "SV" | "GL" | "GD" | "GP" | "GT" | "HT" | "HN" | "JM" | "MQ" | "MX" | "MS" | "NI" | "PA" | "PR"
+            | "BL" | "KN" | "LC" | "MF" | "PM" | "VC" | "SX" | "TT" | "TC" | "US" | "VI" | "UM" => Continent::NorthAmerica,
+            "AS" | "AU" | "CK" | "FJ" | "PF" | "GU" | "KI" | "MH" | "FM" | "NR" | "NC" | "NZ" | "NU" | "NF" | "MP"
+            | "PW" | "PG" | "PN" | "WS" | "SB" | "TK" | "TO" | "TV" | "VU" | "WF" => Continent::Oceania,
+            "AR" | "BO" | "BR" | "CL" | "CO" | "EC" | "FK" | "GF" | "GY" | "PY" | "PE" | "SR" | "UY" | "VE" => {
+                Continent::SouthAmerica
+            }
+            _ => return None,
+        };
+
+        Some(continent)
+    }
+}
+
+impl Display for Continent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Continent::Africa => write!(f, "Africa"),
+            Continent::Antarctica => write!(f, "Antarctica"),
+            Continent::Asia => write!(f, "Asia"),
+            Continent::Europe => write!(f, "Europe"),
+            Continent::NorthAmerica => write!(f, "North America"),
+            Continent::Oceania => write!(f, "Oceania"),
+            Continent::SouthAmerica => write!(f, "South America"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::Continent, test_case::test_case};
+
+    #[test_case("FR", Some(Continent::Europe))]
+    #[test_case("ru", Some(Continent::Europe))]
+    #[test_case("US", Some(Continent::NorthAmerica))]
+    #[test_case("BR", Some(Continent::SouthAmerica))]
+    #[test_case("SG", Some(Continent::Asia))]
+    #[test_case("AU", Some(Continent::Oceania))]
+    #[test_case("ZA", Some(Continent::Africa))]
+    #[test_case("EU", None)]
+    fn test_from_country_code(code: &str, expected: Option<Continent>) {
+        assert_eq!(expected, Continent::from_country_code(code));
+    }
+}
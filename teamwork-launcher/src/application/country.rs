@@ -1,4 +1,5 @@
 use {
+    crate::application::continent::Continent,
     serde::{Deserialize, Serialize},
     std::{fmt::Display, str::FromStr},
 };
@@ -18,6 +19,10 @@ impl Country {
         &self.code
     }
 
+    pub fn continent(&self) -> Option<Continent> {
+        Continent::from_country_code(&self.code)
+    }
+
     pub fn name(&self) -> String {
         match iso_country::Country::from_str(&self.code) {
             Ok(country) => country.name().to_string(),
@@ -1,9 +1,13 @@
 use {
-    crate::application::{server::Server, servers_source::SourceKey},
+    crate::application::{ip_port::IpPort, server::Server, servers_source::SourceKey},
     async_stream::stream,
-    iced::futures::{stream::FuturesUnordered, Stream, StreamExt},
+    iced::futures::{
+        stream::{self, FuturesUnordered},
+        Stream, StreamExt,
+    },
+    log::trace,
     std::sync::Arc,
-    teamwork::{Error, UrlWithKey},
+    teamwork::{Error, NameNormalization, UrlWithKey, ValidationReport},
 };
 
 #[derive(Debug, Clone)]
@@ -11,47 +15,222 @@ pub enum FetchServersEvent {
     Start,
     Finish,
     Servers(Vec<Server>),
+    /// The servers of a source that were rejected because they are invalid.
+    Rejected(SourceKey, ValidationReport),
     Error(Arc<Error>),
+    MasterServerError(SourceKey, Arc<source_query::Error>),
 }
 
-async fn get_servers(client: &teamwork::Client, url: UrlWithKey, key: SourceKey) -> Result<Vec<Server>, Error> {
-    let mut servers: Vec<Server> = client
-        .get_servers(url)
-        .await?
-        .into_iter()
-        .map(std::convert::Into::<Server>::into)
-        .collect();
+/// The request to send to get the servers of a source.
+pub enum SourceRequest {
+    Teamwork(UrlWithKey),
+    MasterServer {
+        address: String,
+        region: source_query::Region,
+        filter: String,
+    },
+}
+
+enum SourceError {
+    Teamwork(Error),
+    MasterServer(SourceKey, source_query::Error),
+}
 
-    for server in servers.iter_mut() {
-        server.source_key = Some(key.clone());
+async fn get_servers(
+    client: &teamwork::Client,
+    query_client: &source_query::Client,
+    name_normalization: &NameNormalization,
+    request: SourceRequest,
+    key: SourceKey,
+) -> Result<(SourceKey, Vec<Server>, ValidationReport), SourceError> {
+    match request {
+        SourceRequest::Teamwork(url) => get_teamwork_servers(client, url, key).await.map_err(SourceError::Teamwork),
+        SourceRequest::MasterServer { address, region, filter } => {
+            match get_master_servers(query_client, name_normalization, &address, region, &filter, &key).await {
+                Ok(servers) => Ok((key, servers, ValidationReport::new(address))),
+                Err(error) => Err(SourceError::MasterServer(key, error)),
+            }
+        }
     }
+}
+
+/// List the servers with the master server, then query the information to each server.
+///
+/// The master server lists a lot of servers that do not answer, they are ignored.
+async fn get_master_servers(
+    query_client: &source_query::Client,
+    name_normalization: &NameNormalization,
+    address: &str,
+    region: source_query::Region,
+    filter: &str,
+    key: &SourceKey,
+) -> Result<Vec<Server>, source_query::Error> {
+    const MAX_CONCURRENT_QUERIES: usize = 64;
+
+    let addresses = query_client.master_servers(address, region, filter).await?;
+    let servers = stream::iter(addresses)
+        .map(|address| async move { (address, query_client.info(address).await) })
+        .buffer_unordered(MAX_CONCURRENT_QUERIES)
+        .filter_map(|(address, result)| async move {
+            match result {
+                Ok(info) => {
+                    let mut server = Server::from_info(IpPort::new(*address.ip(), address.port()), info, name_normalization);
+
+                    server.source_key = Some(key.clone());
+
+                    Some(server)
+                }
+                Err(error) => {
+                    trace!("Server {} skipped: {}", address, error);
+                    None
+                }
+            }
+        })
+        .collect()
+        .await;
 
     Ok(servers)
 }
 
-pub fn fetch_servers(urls: Vec<(SourceKey, UrlWithKey)>) -> impl Stream<Item = FetchServersEvent> {
+async fn get_teamwork_servers(
+    client: &teamwork::Client,
+    url: UrlWithKey,
+    key: SourceKey,
+) -> Result<(SourceKey, Vec<Server>, ValidationReport), Error> {
+    let (teamwork_servers, mut report) = client.get_servers(url).await?;
+    let mut servers = Vec::with_capacity(teamwork_servers.len());
+
+    // The servers with an address the launcher can't use are added to the report,
+    // as JSON like the servers rejected by the client.
+    for (index, server) in report.accepted_indices().into_iter().zip(teamwork_servers) {
+        let entry = serde_json::to_string(&server).unwrap_or_else(|_| server.name.clone());
+
+        match Server::try_from(server) {
+            Ok(mut server) => {
+                server.source_key = Some(key.clone());
+                servers.push(server);
+            }
+            Err(error) => report.reject(index, error, entry),
+        }
+    }
+
+    Ok((key, servers, report))
+}
+
+pub fn fetch_servers(
+    client: teamwork::Client,
+    query_client: source_query::Client,
+    name_normalization: NameNormalization,
+    requests: Vec<(SourceKey, SourceRequest)>,
+) -> impl Stream<Item = FetchServersEvent> {
     const SERVERS_CHUNK_SIZE: usize = 50;
 
     stream! {
         yield FetchServersEvent::Start;
-        let client = teamwork::Client::default();
-        let mut request_servers = FuturesUnordered::from_iter(urls.into_iter().map(|(source_key, url)| get_servers(&client, url, source_key)));
+        let mut request_servers = FuturesUnordered::from_iter(requests.into_iter().map(|(source_key, request)| get_servers(&client, &query_client, &name_normalization, request, source_key)));
 
         while let Some(result) = request_servers.next().await {
             match result {
-                Ok(servers) => {
-                    let servers: Vec<Server> = servers.into_iter().collect();
+                Ok((source_key, servers, report)) => {
+                    if !report.is_empty() {
+                        yield FetchServersEvent::Rejected(source_key, report)
+                    }
 
                     for chunk in servers.as_slice().chunks(SERVERS_CHUNK_SIZE) {
                         yield FetchServersEvent::Servers(chunk.to_vec())
                     }
                 }
-                Err(error) => {
+                Err(SourceError::Teamwork(error)) => {
                     yield FetchServersEvent::Error(Arc::new(error))
                 }
+                Err(SourceError::MasterServer(source_key, error)) => {
+                    yield FetchServersEvent::MasterServerError(source_key, Arc::new(error))
+                }
             }
         }
 
         yield FetchServersEvent::Finish;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{fetch_servers, FetchServersEvent, SourceRequest},
+        crate::application::{
+            create_replay_client,
+            servers_source::{ServersSource, SourceKey},
+        },
+        iced::futures::{executor::block_on, Stream, StreamExt},
+        teamwork::UrlWithKey,
+    };
+
+    fn fetch(requests: Vec<(SourceKey, SourceRequest)>) -> impl Stream<Item = FetchServersEvent> {
+        fetch_servers(
+            create_replay_client(),
+            source_query::Client::default(),
+            Default::default(),
+            requests,
+        )
+    }
+
+    #[test]
+    fn test_fetch_servers() {
+        let source = ServersSource::new("Payload", "https://teamwork.tf/api/v1/quickplay/payload/servers");
+        let requests = vec![(
+            source.key().clone(),
+            SourceRequest::Teamwork(UrlWithKey::new(source.url(), "key")),
+        )];
+        let events: Vec<FetchServersEvent> = block_on(fetch(requests).collect());
+
+        assert_eq!(3, events.len());
+        assert!(matches!(events[0], FetchServersEvent::Start));
+        assert!(matches!(events[2], FetchServersEvent::Finish));
+
+        match &events[1] {
+            FetchServersEvent::Servers(servers) => {
+                assert_eq!(2, servers.len());
+                assert!(servers.iter().all(|server| server.source_key.as_ref() == Some(source.key())));
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_fetch_servers_rejected() {
+        let source = ServersSource::new("KOTH", "https://teamwork.tf/api/v1/quickplay/koth/servers");
+        let requests = vec![(
+            source.key().clone(),
+            SourceRequest::Teamwork(UrlWithKey::new(source.url(), "key")),
+        )];
+        let events: Vec<FetchServersEvent> = block_on(fetch(requests).collect());
+
+        assert_eq!(4, events.len());
+
+        match &events[1] {
+            FetchServersEvent::Rejected(source_key, report) => {
+                assert_eq!(source.key(), source_key);
+                assert_eq!(
+                    vec![2, 3, 4, 5],
+                    report.rejected.iter().map(|rejected| rejected.index).collect::<Vec<_>>()
+                );
+                assert!(report.rejected[3].entry.contains(r#""name":"Fixture KOTH #6""#));
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+
+        assert!(matches!(&events[2], FetchServersEvent::Servers(servers) if servers.len() == 2));
+    }
+
+    #[test]
+    fn test_fetch_servers_missing_source() {
+        let source = ServersSource::new("Unknown", "https://teamwork.tf/api/v1/quickplay/unknown/servers");
+        let requests = vec![(
+            source.key().clone(),
+            SourceRequest::Teamwork(UrlWithKey::new(source.url(), "key")),
+        )];
+        let events: Vec<FetchServersEvent> = block_on(fetch(requests).collect());
+
+        assert!(matches!(&events[1], FetchServersEvent::Error(error) if error.is_missing_fixture()));
+    }
+}
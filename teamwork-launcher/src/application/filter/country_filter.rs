@@ -1,13 +1,26 @@
 use {
-    crate::application::{filter::filter_dictionary::FilterDictionary, Country, PromisedValue},
+    crate::application::{continent::Continent, filter::filter_dictionary::FilterDictionary, Country, PromisedValue},
     serde::{Deserialize, Serialize},
+    std::collections::BTreeSet,
 };
 
-#[derive(Serialize, Deserialize)]
+/// The state of the checkbox of a group of countries.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GroupState {
+    Checked,
+    Unchecked,
+    /// Some countries of the group are checked, but not all.
+    Mixed,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct CountryFilter {
     pub dictionary: FilterDictionary<Country>,
     pub no_countries: bool,
     pub enabled: bool,
+    /// The continents displayed with their countries, the others are collapsed.
+    #[serde(default)]
+    pub expanded_continents: BTreeSet<Continent>,
 }
 
 impl Default for CountryFilter {
@@ -16,6 +29,7 @@ impl Default for CountryFilter {
             dictionary: FilterDictionary::new(),
             no_countries: true,
             enabled: false,
+            expanded_continents: BTreeSet::new(),
         }
     }
 }
@@ -32,4 +46,81 @@ impl CountryFilter {
             PromisedValue::None => self.no_countries,
         }
     }
+
+    /// Returns `None` if no country of the continent is known.
+    pub fn continent_state(&self, continent: Continent) -> Option<GroupState> {
+        let (checked, total) = self
+            .dictionary
+            .iter()
+            .filter(|(country, _)| country.continent() == Some(continent))
+            .fold((0usize, 0usize), |(checked, total), (_, is_checked)| {
+                (checked + is_checked as usize, total + 1)
+            });
+
+        match (checked, total) {
+            (_, 0) => None,
+            (0, _) => Some(GroupState::Unchecked),
+            (checked, total) if checked == total => Some(GroupState::Checked),
+            _ => Some(GroupState::Mixed),
+        }
+    }
+
+    pub fn set_continent_checked(&mut self, continent: Continent, checked: bool) {
+        self.dictionary
+            .set_checked_if(|country| country.continent() == Some(continent), checked);
+    }
+
+    pub fn set_continent_expanded(&mut self, continent: Continent, expanded: bool) {
+        match expanded {
+            true => self.expanded_continents.insert(continent),
+            false => self.expanded_continents.remove(&continent),
+        };
+    }
+
+    pub fn is_continent_expanded(&self, continent: Continent) -> bool {
+        self.expanded_continents.contains(&continent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{CountryFilter, GroupState},
+        crate::application::{continent::Continent, Country},
+    };
+
+    fn create_filter() -> CountryFilter {
+        let mut filter = CountryFilter::default();
+
+        filter.dictionary.extend(["FR", "DE", "US"].iter().map(Country::new));
+
+        filter
+    }
+
+    #[test]
+    fn test_continent_state() {
+        let mut filter = create_filter();
+
+        assert_eq!(Some(GroupState::Checked), filter.continent_state(Continent::Europe));
+        assert_eq!(None, filter.continent_state(Continent::Asia));
+
+        filter.dictionary.set_checked(&Country::new(&"FR"), false);
+
+        assert_eq!(Some(GroupState::Mixed), filter.continent_state(Continent::Europe));
+
+        filter.dictionary.set_checked(&Country::new(&"DE"), false);
+
+        assert_eq!(Some(GroupState::Unchecked), filter.continent_state(Continent::Europe));
+    }
+
+    #[test]
+    fn test_set_continent_checked() {
+        let mut filter = create_filter();
+
+        filter.set_continent_checked(Continent::Europe, false);
+
+        assert!(!filter.dictionary.is_checked(&Country::new(&"FR")));
+        assert!(!filter.dictionary.is_checked(&Country::new(&"DE")));
+        assert!(filter.dictionary.is_checked(&Country::new(&"US")));
+    }
 }
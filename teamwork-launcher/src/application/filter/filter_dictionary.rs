@@ -5,7 +5,7 @@ use {
 
 /// Core implementation of filters for multiple
 /// values (such as Country or Game Mode).
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct FilterDictionary<K>
 where
     K: Ord,
@@ -56,6 +56,15 @@ impl<K: Ord> FilterDictionary<K> {
         }
     }
 
+    /// Check or uncheck all the keys matching the predicate.
+    pub fn set_checked_if(&mut self, predicate: impl Fn(&K) -> bool, check: bool) {
+        for (k, v) in self.entries.iter_mut() {
+            if predicate(k) {
+                *v = check;
+            }
+        }
+    }
+
     pub fn is_checked(&self, key: &K) -> bool {
         self.entries.get(key).copied().unwrap_or_default()
     }
@@ -0,0 +1,260 @@
+use {
+    crate::application::filter::filter_servers::Filter,
+    rfd::AsyncFileDialog,
+    serde::{Deserialize, Serialize},
+    std::{collections::BTreeMap, path::PathBuf},
+};
+
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum FilterPresetError {
+    #[error("The name of the preset is empty")]
+    EmptyName,
+    #[error("A preset named '{0}' already exists")]
+    AlreadyExists(String),
+    #[error("No preset is selected")]
+    NoSelection,
+    #[error("Invalid preset file: {0}")]
+    Json(String),
+    #[error("Failed to access the preset file {0}: {1}")]
+    Io(PathBuf, String),
+}
+
+/// The format of an exported preset.
+#[derive(Serialize, Deserialize)]
+struct ExportedPreset {
+    name: String,
+    filter: Filter,
+}
+
+/// Named snapshots of the whole filter.
+#[derive(Serialize, Deserialize, Default)]
+pub struct FilterPresets {
+    presets: BTreeMap<String, Filter>,
+    /// The preset loaded last.
+    selected: Option<String>,
+    /// The name typed by the user to save or rename a preset.
+    #[serde(skip)]
+    pub name: String,
+}
+
+impl FilterPresets {
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.presets.keys()
+    }
+
+    pub fn selected(&self) -> Option<&String> {
+        self.selected.as_ref()
+    }
+
+    /// Returns a copy of the filter of the preset, so the preset is not modified while the filter is edited.
+    pub fn select(&mut self, name: &str) -> Option<Filter> {
+        let filter = self.presets.get(name)?.clone();
+
+        self.selected = Some(name.to_string());
+        self.name = name.to_string();
+
+        Some(filter)
+    }
+
+    /// Save the filter with the typed name, a preset with the same name is replaced.
+    pub fn save_as(&mut self, filter: &Filter) -> Result<(), FilterPresetError> {
+        let name = Self::validate_name(&self.name)?;
+
+        self.presets.insert(name.clone(), filter.clone());
+        self.selected = Some(name);
+
+        Ok(())
+    }
+
+    /// Rename the selected preset with the typed name.
+    pub fn rename(&mut self) -> Result<(), FilterPresetError> {
+        let new_name = Self::validate_name(&self.name)?;
+        let old_name = self.selected.clone().ok_or(FilterPresetError::NoSelection)?;
+
+        if new_name == old_name {
+            return Ok(());
+        }
+
+        if self.presets.contains_key(&new_name) {
+            return Err(FilterPresetError::AlreadyExists(new_name));
+        }
+
+        let filter = self.presets.remove(&old_name).ok_or(FilterPresetError::NoSelection)?;
+
+        self.presets.insert(new_name.clone(), filter);
+        self.selected = Some(new_name);
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self) -> Result<(), FilterPresetError> {
+        let name = self.selected.take().ok_or(FilterPresetError::NoSelection)?;
+
+        self.presets.remove(&name);
+        self.name.clear();
+
+        Ok(())
+    }
+
+    /// Serialize the selected preset, to share it.
+    pub fn export(&self) -> Result<(String, String), FilterPresetError> {
+        let name = self.selected.as_ref().ok_or(FilterPresetError::NoSelection)?;
+        let filter = self.presets.get(name).ok_or(FilterPresetError::NoSelection)?;
+        let preset = ExportedPreset {
+            name: name.clone(),
+            filter: filter.clone(),
+        };
+        let json = serde_json::to_string_pretty(&preset).map_err(|error| FilterPresetError::Json(error.to_string()))?;
+
+        Ok((name.clone(), json))
+    }
+
+    /// Add an exported preset, renamed if a preset with the same name exists.
+    ///
+    /// Returns the name of the imported preset.
+    pub fn import(&mut self, json: &str) -> Result<String, FilterPresetError> {
+        let preset: ExportedPreset =
+            serde_json::from_str(json).map_err(|error| FilterPresetError::Json(error.to_string()))?;
+        let name = Self::validate_name(&preset.name)?;
+        let name = match self.presets.contains_key(&name) {
+            false => name,
+            true => (2..)
+                .map(|index| format!("{} ({})", name, index))
+                .find(|name| !self.presets.contains_key(name))
+                .expect("a free name"),
+        };
+
+        self.presets.insert(name.clone(), preset.filter);
+
+        Ok(name)
+    }
+
+    fn validate_name(name: &str) -> Result<String, FilterPresetError> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(FilterPresetError::EmptyName);
+        }
+
+        Ok(name.to_string())
+    }
+}
+
+/// Returns the content of the file picked by the user, `None` if no file is picked.
+pub async fn import_preset() -> Result<Option<String>, FilterPresetError> {
+    let Some(file_handle) = AsyncFileDialog::new()
+        .add_filter("Filter preset", &["json"])
+        .pick_file()
+        .await
+    else {
+        return Ok(None);
+    };
+
+    tokio::fs::read_to_string(file_handle.path())
+        .await
+        .map(Some)
+        .map_err(|error| FilterPresetError::Io(file_handle.path().to_path_buf(), error.to_string()))
+}
+
+/// Returns the path of the file picked by the user, `None` if no file is picked.
+pub async fn export_preset(name: String, json: String) -> Result<Option<PathBuf>, FilterPresetError> {
+    let Some(file_handle) = AsyncFileDialog::new()
+        .add_filter("Filter preset", &["json"])
+        .set_file_name(&format!("{}.json", name))
+        .save_file()
+        .await
+    else {
+        return Ok(None);
+    };
+
+    tokio::fs::write(file_handle.path(), json)
+        .await
+        .map(|_| Some(file_handle.path().to_path_buf()))
+        .map_err(|error| FilterPresetError::Io(file_handle.path().to_path_buf(), error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{FilterPresetError, FilterPresets},
+        crate::application::filter::filter_servers::Filter,
+    };
+
+    fn save(presets: &mut FilterPresets, name: &str, text: &str) {
+        let mut filter = Filter::default();
+
+        filter.text.set_text(text);
+        presets.name = name.to_string();
+        presets.save_as(&filter).unwrap();
+    }
+
+    #[test]
+    fn test_save_and_select() {
+        let mut presets = FilterPresets::default();
+
+        save(&mut presets, " payload ", "upward");
+        save(&mut presets, "koth", "viaduct");
+
+        assert_eq!(vec!["koth", "payload"], presets.names().collect::<Vec<_>>());
+        assert_eq!("upward", presets.select("payload").unwrap().text.text());
+        assert_eq!(Some(&"payload".to_string()), presets.selected());
+        assert!(presets.select("ctf").is_none());
+    }
+
+    #[test]
+    fn test_save_empty_name() {
+        let mut presets = FilterPresets {
+            name: "  ".to_string(),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            presets.save_as(&Filter::default()),
+            Err(FilterPresetError::EmptyName)
+        ));
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut presets = FilterPresets::default();
+
+        save(&mut presets, "payload", "upward");
+        save(&mut presets, "koth", "viaduct");
+        presets.name = "payload".to_string();
+
+        assert!(matches!(presets.rename(), Err(FilterPresetError::AlreadyExists(_))));
+
+        presets.name = "late-night koth".to_string();
+        presets.rename().unwrap();
+
+        assert_eq!(vec!["late-night koth", "payload"], presets.names().collect::<Vec<_>>());
+        assert_eq!("viaduct", presets.select("late-night koth").unwrap().text.text());
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut presets = FilterPresets::default();
+
+        assert!(matches!(presets.delete(), Err(FilterPresetError::NoSelection)));
+
+        save(&mut presets, "payload", "upward");
+        presets.delete().unwrap();
+
+        assert_eq!(0, presets.names().count());
+        assert_eq!(None, presets.selected());
+    }
+
+    #[test]
+    fn test_export_import() {
+        let mut presets = FilterPresets::default();
+
+        save(&mut presets, "payload", "upward");
+
+        let (name, json) = presets.export().unwrap();
+
+        assert_eq!("payload", name);
+        assert_eq!("payload (2)", presets.import(&json).unwrap());
+        assert_eq!("upward", presets.select("payload (2)").unwrap().text.text());
+        assert!(matches!(presets.import("{}"), Err(FilterPresetError::Json(_))));
+    }
+}
@@ -1,22 +1,48 @@
 use {
     crate::application::{
         filter::{
-            country_filter::CountryFilter,
-            game_mode_filter::GameModeFilter,
-            map_filter::MapFilter,
-            ping_filter::PingFilter,
-            player_filter::PlayerFilter,
-            properties_filter::PropertyFilterSwitch,
-            provider_filter::ProviderFilter,
-            sort_servers::{SortCriterion, SortDirection},
-            text_filter::TextFilter,
+            country_filter::CountryFilter, game_mode_filter::GameModeFilter, map_filter::MapFilter, ping_filter::PingFilter,
+            player_filter::PlayerFilter, properties_filter::PropertyFilterSwitch, provider_filter::ProviderFilter,
+            rules_filter::RulesFilter, sort_servers::SortKeys, text_filter::TextFilter,
         },
+        server::Property,
         Bookmarks, Server,
     },
     serde::{Deserialize, Serialize},
 };
 
-#[derive(Serialize, Deserialize)]
+/// The filters displaying a count of servers next to each value.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Facet {
+    Country,
+    GameMode,
+    Map,
+    Provider,
+    Property(Property),
+}
+
+/// How the filter handles a server, see [`Filter::acceptance`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Acceptance {
+    Accepted,
+    /// Only the filter of this facet rejects the server.
+    RejectedBy(Facet),
+    /// Several filters reject the server, or a filter without facet rejects it.
+    Rejected,
+}
+
+impl Acceptance {
+    /// Returns true if the server is accepted when the filter of the facet is ignored.
+    pub fn is_accepted_by_others(&self, facet: Facet) -> bool {
+        match self {
+            Acceptance::Accepted => true,
+            Acceptance::RejectedBy(rejecting_facet) => *rejecting_facet == facet,
+            Acceptance::Rejected => false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Filter {
     pub text: TextFilter,
     pub country: CountryFilter,
@@ -32,8 +58,10 @@ pub struct Filter {
     pub no_respawn_time: PropertyFilterSwitch,
     pub random_crits: PropertyFilterSwitch,
     pub password: PropertyFilterSwitch,
-    pub sort_criterion: SortCriterion,
-    pub sort_direction: SortDirection,
+    #[serde(default)]
+    pub rules: RulesFilter,
+    #[serde(flatten)]
+    pub sort: SortKeys,
 }
 
 impl Default for Filter {
@@ -53,14 +81,19 @@ impl Default for Filter {
             no_respawn_time: PropertyFilterSwitch::Ignore,
             random_crits: PropertyFilterSwitch::Ignore,
             password: PropertyFilterSwitch::Ignore,
-            sort_criterion: SortCriterion::Ip,
-            sort_direction: SortDirection::Ascending,
+            rules: RulesFilter::default(),
+            sort: SortKeys::default(),
         }
     }
 }
 
 impl Filter {
     pub fn accept(&self, server: &Server, bookmarks: &Bookmarks) -> bool {
+        self.accept_except_rules(server, bookmarks) && self.filter_by_rules(server)
+    }
+
+    /// Accept the server whatever its rules, the rules are only known once the server is queried.
+    pub fn accept_except_rules(&self, server: &Server, bookmarks: &Bookmarks) -> bool {
         self.filter_by_bookmark(server, bookmarks)
             && self.filter_by_text(server)
             && self.filter_by_player(server)
@@ -72,11 +105,55 @@ impl Filter {
             && self.filter_by_providers(server)
     }
 
+    /// Find which facet rejects the server, to count the servers each value of a facet would accept
+    /// given the other filters.
+    pub fn acceptance(&self, server: &Server, bookmarks: &Bookmarks) -> Acceptance {
+        if !(self.filter_by_bookmark(server, bookmarks)
+            && self.filter_by_text(server)
+            && self.filter_by_player(server)
+            && self.filter_by_ping(server)
+            && self.filter_by_rules(server))
+        {
+            return Acceptance::Rejected;
+        }
+
+        let facets = [
+            (Facet::Country, self.filter_by_countries(server)),
+            (Facet::GameMode, self.filter_by_game_mode(server)),
+            (Facet::Map, self.filter_by_maps(server)),
+            (Facet::Provider, self.filter_by_providers(server)),
+        ]
+        .into_iter()
+        .chain(
+            self.property_filters()
+                .into_iter()
+                .map(|(property, switch)| (Facet::Property(property), switch.accept(|s| s.has_property(property), server))),
+        );
+        let mut rejecting_facets = facets.filter(|(_, accepted)| !accepted).map(|(facet, _)| facet);
+
+        match (rejecting_facets.next(), rejecting_facets.next()) {
+            (None, _) => Acceptance::Accepted,
+            (Some(facet), None) => Acceptance::RejectedBy(facet),
+            (Some(_), Some(_)) => Acceptance::Rejected,
+        }
+    }
+
+    fn property_filters(&self) -> [(Property, PropertyFilterSwitch); 6] {
+        [
+            (Property::AllTalk, self.all_talk),
+            (Property::VacSecured, self.vac_secured),
+            (Property::Rtd, self.rtd),
+            (Property::NoRespawnTime, self.no_respawn_time),
+            (Property::Password, self.password),
+            (Property::RandomCrits, self.random_crits),
+        ]
+    }
+
     fn filter_by_countries(&self, server: &Server) -> bool {
         self.country.accept(&server.country)
     }
     fn filter_by_text(&self, server: &Server) -> bool {
-        self.text.accept(&server.name)
+        self.text.accept(server)
     }
     fn filter_by_bookmark(&self, server: &Server, bookmarks: &Bookmarks) -> bool {
         !self.bookmarked_only || bookmarks.is_bookmarked(&server.ip_port)
@@ -88,12 +165,9 @@ impl Filter {
         self.game_modes.accept(server)
     }
     fn filter_by_properties(&self, server: &Server) -> bool {
-        self.all_talk.accept(|s| s.has_all_talk, server)
-            && self.vac_secured.accept(|s| s.vac_secured, server)
-            && self.rtd.accept(|s| s.has_rtd, server)
-            && self.no_respawn_time.accept(|s| s.has_no_respawn_time, server)
-            && self.password.accept(|s| s.need_password, server)
-            && self.random_crits.accept(|s| s.has_random_crits, server)
+        self.property_filters()
+            .into_iter()
+            .all(|(property, switch)| switch.accept(|s| s.has_property(property), server))
     }
     fn filter_by_player(&self, server: &Server) -> bool {
         self.players.accept(server)
@@ -108,4 +182,7 @@ impl Filter {
     fn filter_by_providers(&self, server: &Server) -> bool {
         self.providers.accept(server)
     }
+    fn filter_by_rules(&self, server: &Server) -> bool {
+        self.rules.accept(server)
+    }
 }
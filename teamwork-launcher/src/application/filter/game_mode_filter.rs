@@ -1,12 +1,19 @@
 use {
     crate::application::{filter::filter_dictionary::FilterDictionary, game_mode::GameModeId, Server},
     serde::{Deserialize, Serialize},
+    std::collections::BTreeSet,
 };
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct GameModeFilter {
     pub dictionary: FilterDictionary<GameModeId>,
     pub enabled: bool,
+    /// Accept only the servers running at least one official game mode that is checked.
+    #[serde(default)]
+    pub official_only: bool,
+    /// The official game modes, filled when the game modes are received from Teamwork.tf.
+    #[serde(skip)]
+    pub official_modes: BTreeSet<GameModeId>,
 }
 
 impl Default for GameModeFilter {
@@ -14,6 +21,8 @@ impl Default for GameModeFilter {
         Self {
             dictionary: FilterDictionary::new(),
             enabled: false,
+            official_only: false,
+            official_modes: BTreeSet::new(),
         }
     }
 }
@@ -24,8 +33,11 @@ impl GameModeFilter {
             return true;
         }
 
+        // The official game modes are unknown until they are received, meanwhile every game mode is accepted.
+        let official_only = self.official_only && !self.official_modes.is_empty();
+
         for id in &server.game_modes {
-            if self.dictionary.is_checked(id) {
+            if self.dictionary.is_checked(id) && (!official_only || self.official_modes.contains(id)) {
                 return true;
             }
         }
@@ -49,12 +61,14 @@ mod tests {
             title: "A".to_string(),
             description: "A desc".to_string(),
             color: None,
+            official: true,
         };
         let gmb = teamwork::GameMode {
             id: "b".to_string(),
             title: "B".to_string(),
             description: "B desc".to_string(),
             color: None,
+            official: false,
         };
 
         filter.dictionary.add(GameModeId::new(gma.id.clone()));
@@ -94,5 +108,31 @@ mod tests {
 
         assert_eq!(filter.accept(&server_gma), true);
         assert_eq!(filter.accept(&server_gmb), false);
+
+        filter.official_modes.insert(GameModeId::new(gma.id.clone()));
+        filter.official_only = true;
+        filter.dictionary.set_checked(&GameModeId::new(gmb.id.clone()), true);
+
+        assert_eq!(filter.accept(&server_gma), true);
+        assert_eq!(filter.accept(&server_gmb), false);
+    }
+
+    #[test]
+    fn test_accept_official_modes_unknown() {
+        let mut filter = GameModeFilter::default();
+        let server = Server {
+            game_modes: vec![GameModeId::new("payload")],
+            ..Default::default()
+        };
+
+        filter.dictionary.add(GameModeId::new("payload"));
+        filter.enabled = true;
+        filter.official_only = true;
+
+        assert!(filter.accept(&server));
+
+        filter.official_modes.insert(GameModeId::new("koth"));
+
+        assert!(!filter.accept(&server));
     }
 }
@@ -1,21 +1,131 @@
 use {
     crate::application::{filter::filter_dictionary::FilterDictionary, map::MapName},
+    regex::Regex,
     serde::{Deserialize, Serialize},
 };
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(from = "SerializedMapFilter")]
 pub struct MapFilter {
     pub dictionary: FilterDictionary<MapName>,
     pub enabled: bool,
-    pub text: String,
+    /// Narrow the list of maps displayed.
+    text: String,
+    /// The text is a regular expression instead of a part of the map name.
+    regex: bool,
+    /// Regular expression compiled from the text, `None` if the text is empty or not a regular expression.
+    #[serde(skip)]
+    compiled: Option<Regex>,
+    #[serde(skip)]
+    error: Option<regex::Error>,
 }
 
-impl Default for MapFilter {
-    fn default() -> Self {
-        Self {
-            dictionary: FilterDictionary::new(),
-            enabled: false,
-            text: String::new(),
+/// The regular expression is compiled when the filter is read.
+#[derive(Deserialize)]
+struct SerializedMapFilter {
+    dictionary: FilterDictionary<MapName>,
+    enabled: bool,
+    text: String,
+    #[serde(default)]
+    regex: bool,
+}
+
+impl From<SerializedMapFilter> for MapFilter {
+    fn from(serialized: SerializedMapFilter) -> Self {
+        let mut filter = Self {
+            dictionary: serialized.dictionary,
+            enabled: serialized.enabled,
+            text: serialized.text,
+            regex: serialized.regex,
+            compiled: None,
+            error: None,
+        };
+
+        filter.compile();
+
+        filter
+    }
+}
+
+impl MapFilter {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.compile();
+    }
+
+    pub fn regex(&self) -> bool {
+        self.regex
+    }
+
+    pub fn set_regex(&mut self, regex: bool) {
+        self.regex = regex;
+        self.compile();
+    }
+
+    pub fn error(&self) -> Option<&regex::Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns true if the map is displayed in the list of maps.
+    pub fn matches_name(&self, name: &MapName) -> bool {
+        match &self.compiled {
+            Some(regex) => regex.is_match(name.as_str()),
+            None if self.regex => true,
+            None => name.as_str().contains(&self.text),
+        }
+    }
+
+    /// An invalid regular expression is reported and the last valid one is used until the text is fixed.
+    fn compile(&mut self) {
+        if !self.regex || self.text.is_empty() {
+            self.compiled = None;
+            self.error = None;
+            return;
         }
+
+        match Regex::new(&self.text) {
+            Ok(regex) => {
+                self.compiled = Some(regex);
+                self.error = None;
+            }
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::MapFilter, crate::application::map::MapName, test_case::test_case};
+
+    #[test_case("upward", false, true; "part of the name")]
+    #[test_case("^pl_", false, false; "not a regular expression")]
+    #[test_case("^pl_", true, true; "regular expression")]
+    #[test_case("^(koth|cp)_", true, false; "rejected by the regular expression")]
+    #[test_case("", true, true; "empty regular expression")]
+    fn test_matches_name(text: &str, regex: bool, expected: bool) {
+        let mut filter = MapFilter::default();
+
+        filter.set_regex(regex);
+        filter.set_text(text);
+
+        assert_eq!(None, filter.error());
+        assert_eq!(expected, filter.matches_name(&MapName::new("pl_upward")));
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let mut filter = MapFilter::default();
+
+        filter.set_regex(true);
+        filter.set_text("^koth_");
+        filter.set_text("^koth_[");
+
+        assert!(filter.error().is_some());
+        assert!(!filter.matches_name(&MapName::new("pl_upward")));
+        assert!(filter.matches_name(&MapName::new("koth_product")));
     }
 }
@@ -1,5 +1,6 @@
 pub mod country_filter;
 pub mod filter_dictionary;
+pub mod filter_presets;
 pub mod filter_servers;
 pub mod game_mode_filter;
 pub mod map_filter;
@@ -7,6 +8,7 @@ pub mod ping_filter;
 pub mod player_filter;
 pub mod properties_filter;
 pub mod provider_filter;
+pub mod rules_filter;
 pub mod sort_servers;
 pub mod text_filter;
 
@@ -3,7 +3,7 @@ use {
     serde::{Deserialize, Serialize},
 };
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct PingFilter {
     pub max_ping: u32,
     pub accept_ping_timeout: bool,
@@ -18,7 +18,7 @@ impl PingFilter {
         }
 
         match server.ping {
-            PromisedValue::Ready(ping) => ping.as_millis() <= self.max_ping as u128,
+            PromisedValue::Ready(ping) => ping.median.as_millis() <= self.max_ping as u128,
             PromisedValue::Loading => true,
             PromisedValue::None => self.accept_ping_timeout,
         }
@@ -0,0 +1,111 @@
+use {
+    crate::application::Server,
+    serde::{Deserialize, Serialize},
+};
+
+/// A condition on a rule (console variable) of a server, like `sv_pure` = `2`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RuleCondition {
+    pub name: String,
+    /// If empty, the rule only has to be defined.
+    pub value: String,
+}
+
+impl RuleCondition {
+    pub fn new(name: impl ToString, value: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.name.trim().is_empty()
+    }
+
+    fn accept(&self, rules: &source_query::Rules) -> bool {
+        match rules.get(self.name.trim()) {
+            None => false,
+            Some(_) if self.value.trim().is_empty() => true,
+            Some(value) => value.trim().eq_ignore_ascii_case(self.value.trim()),
+        }
+    }
+}
+
+/// Accept the servers matching all the conditions.
+///
+/// The rules are queried directly to the servers, while the rules of a server are not known the server is rejected.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct RulesFilter {
+    pub enabled: bool,
+    pub conditions: Vec<RuleCondition>,
+}
+
+impl RulesFilter {
+    pub fn accept(&self, server: &Server) -> bool {
+        let mut conditions = self.conditions.iter().filter(|condition| !condition.is_empty()).peekable();
+
+        if !self.enabled || conditions.peek().is_none() {
+            return true;
+        }
+
+        match server.rules.get() {
+            Some(rules) => conditions.all(|condition| condition.accept(rules)),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{RuleCondition, RulesFilter},
+        crate::application::{PromisedValue, Server},
+        std::sync::Arc,
+        test_case::test_case,
+    };
+
+    fn server_with_rules() -> Server {
+        let rules = source_query::Rules::from([
+            ("sv_pure".to_string(), "2".to_string()),
+            ("tf_gamemode_payload".to_string(), "1".to_string()),
+            ("sv_tags".to_string(), "Payload".to_string()),
+        ]);
+
+        Server {
+            rules: PromisedValue::Ready(Arc::new(rules)),
+            ..Default::default()
+        }
+    }
+
+    #[test_case(vec![], true; "no condition")]
+    #[test_case(vec![RuleCondition::new("sv_pure", "2")], true; "equal")]
+    #[test_case(vec![RuleCondition::new("sv_pure", "0")], false; "different")]
+    #[test_case(vec![RuleCondition::new("sv_tags", "payload")], true; "ignore case")]
+    #[test_case(vec![RuleCondition::new("tf_gamemode_payload", "")], true; "defined")]
+    #[test_case(vec![RuleCondition::new("tf_gamemode_ctf", "")], false; "not defined")]
+    #[test_case(vec![RuleCondition::new(" ", "1")], true; "empty condition")]
+    #[test_case(vec![RuleCondition::new("sv_pure", "2"), RuleCondition::new("sv_cheats", "0")], false; "all conditions")]
+    fn test_accept(conditions: Vec<RuleCondition>, expected: bool) {
+        let filter = RulesFilter {
+            enabled: true,
+            conditions,
+        };
+
+        assert_eq!(expected, filter.accept(&server_with_rules()));
+    }
+
+    #[test]
+    fn test_reject_unknown_rules() {
+        let mut filter = RulesFilter {
+            enabled: true,
+            conditions: vec![RuleCondition::new("sv_pure", "2")],
+        };
+
+        assert!(!filter.accept(&Server::default()));
+
+        filter.enabled = false;
+
+        assert!(filter.accept(&Server::default()));
+    }
+}
@@ -1,5 +1,5 @@
 use {
-    crate::application::Server,
+    crate::application::{Bookmarks, Country, Server},
     serde::{Deserialize, Serialize},
     std::{
         cmp::Ordering,
@@ -12,11 +12,18 @@ pub enum SortCriterion {
     Ip,
     Name,
     Country,
+    /// By continent, then by country.
+    Region,
     Ping,
     PlayerSlots,
     Players,
     FreePlayerSlots,
     Map,
+    GameMode,
+    Provider,
+    NextMap,
+    /// In ascending order, the bookmarked servers are first.
+    Bookmarked,
 }
 
 impl Display for SortCriterion {
@@ -31,6 +38,9 @@ impl Display for SortCriterion {
             SortCriterion::Country => {
                 write!(f, "Country")
             }
+            SortCriterion::Region => {
+                write!(f, "Region")
+            }
             SortCriterion::Ping => {
                 write!(f, "Ping")
             }
@@ -46,6 +56,18 @@ impl Display for SortCriterion {
             SortCriterion::Map => {
                 write!(f, "Map")
             }
+            SortCriterion::GameMode => {
+                write!(f, "Game mode")
+            }
+            SortCriterion::Provider => {
+                write!(f, "Provider")
+            }
+            SortCriterion::NextMap => {
+                write!(f, "Next map")
+            }
+            SortCriterion::Bookmarked => {
+                write!(f, "Bookmarked")
+            }
         }
     }
 }
@@ -69,15 +91,228 @@ impl Display for SortDirection {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SortKey {
+    pub criterion: SortCriterion,
+    pub direction: SortDirection,
+}
+
+impl SortKey {
+    pub fn new(criterion: SortCriterion, direction: SortDirection) -> Self {
+        Self { criterion, direction }
+    }
+
+    fn compare(&self, bookmarks: &Bookmarks, left: &Server, right: &Server) -> Ordering {
+        let ordering = match self.criterion {
+            SortCriterion::Bookmarked => {
+                let bookmarked = |server: &Server| bookmarks.is_bookmarked(&server.ip_port);
+
+                bookmarked(right).cmp(&bookmarked(left))
+            }
+            criterion => sort_servers(criterion, left, right),
+        };
+
+        match self.direction {
+            SortDirection::Ascending => ordering,
+            SortDirection::Descending => ordering.reverse(),
+        }
+    }
+}
+
+/// The keys used to sort the servers, like "bookmarked first, then free slots descending, then ping ascending".
+///
+/// The servers are compared with the first key, the next keys are used only if the servers are equal.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(from = "SerializedSortKeys")]
+pub struct SortKeys {
+    sort_keys: Vec<SortKey>,
+}
+
+/// The previous versions stored a single criterion and direction.
+#[derive(Deserialize)]
+struct SerializedSortKeys {
+    sort_keys: Option<Vec<SortKey>>,
+    sort_criterion: Option<SortCriterion>,
+    sort_direction: Option<SortDirection>,
+}
+
+impl From<SerializedSortKeys> for SortKeys {
+    fn from(serialized: SerializedSortKeys) -> Self {
+        match (serialized.sort_keys, serialized.sort_criterion) {
+            (Some(sort_keys), _) => Self { sort_keys },
+            (None, Some(criterion)) => Self {
+                sort_keys: vec![SortKey::new(
+                    criterion,
+                    serialized.sort_direction.unwrap_or(SortDirection::Ascending),
+                )],
+            },
+            (None, None) => Self::default(),
+        }
+    }
+}
+
+impl Default for SortKeys {
+    fn default() -> Self {
+        Self {
+            sort_keys: vec![SortKey::new(SortCriterion::Ip, SortDirection::Ascending)],
+        }
+    }
+}
+
+impl SortKeys {
+    pub fn iter(&self) -> impl Iterator<Item = &SortKey> {
+        self.sort_keys.iter()
+    }
+
+    /// Add a key with the first criterion not used yet, if any.
+    pub fn add(&mut self, criteria: &[SortCriterion]) {
+        if let Some(criterion) = criteria
+            .iter()
+            .find(|criterion| !self.sort_keys.iter().any(|key| key.criterion == **criterion))
+        {
+            self.sort_keys.push(SortKey::new(*criterion, SortDirection::Ascending));
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.sort_keys.len() {
+            self.sort_keys.remove(index);
+        }
+    }
+
+    pub fn set_criterion(&mut self, index: usize, criterion: SortCriterion) {
+        if let Some(key) = self.sort_keys.get_mut(index) {
+            key.criterion = criterion;
+        }
+    }
+
+    pub fn set_direction(&mut self, index: usize, direction: SortDirection) {
+        if let Some(key) = self.sort_keys.get_mut(index) {
+            key.direction = direction;
+        }
+    }
+
+    pub fn compare(&self, bookmarks: &Bookmarks, left: &Server, right: &Server) -> Ordering {
+        self.sort_keys
+            .iter()
+            .map(|key| key.compare(bookmarks, left, right))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Compare two servers by a criterion, in ascending order.
+///
+/// [`SortCriterion::Bookmarked`] requires the bookmarks, use [`SortKeys::compare`] instead.
 pub fn sort_servers(criterion: SortCriterion, left: &Server, right: &Server) -> Ordering {
     match criterion {
         SortCriterion::Name => left.name.cmp(&right.name),
         SortCriterion::Ip => left.ip_port.cmp(&right.ip_port),
         SortCriterion::Country => left.country.cmp(&right.country),
+        SortCriterion::Region => {
+            let continent = |server: &Server| server.country.get().and_then(Country::continent);
+
+            continent(left)
+                .cmp(&continent(right))
+                .then_with(|| left.country.cmp(&right.country))
+        }
+        // The ping statistics are compared by median first.
         SortCriterion::Ping => left.ping.cmp(&right.ping),
         SortCriterion::PlayerSlots => left.max_players_count.cmp(&right.max_players_count),
         SortCriterion::Players => left.current_players_count.cmp(&right.current_players_count),
         SortCriterion::FreePlayerSlots => left.free_slots().cmp(&right.free_slots()),
         SortCriterion::Map => left.map.cmp(&right.map),
+        SortCriterion::GameMode => left.game_modes.cmp(&right.game_modes),
+        SortCriterion::Provider => left.provider.cmp(&right.provider),
+        SortCriterion::NextMap => left.next_map.cmp(&right.next_map),
+        SortCriterion::Bookmarked => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{SortCriterion, SortDirection, SortKey, SortKeys},
+        crate::application::{servers_source::SourceKey, Bookmarks, IpPort, Server},
+        std::net::Ipv4Addr,
+    };
+
+    fn create_server(name: &str, players: u8, port: u16) -> Server {
+        Server {
+            name: name.to_string(),
+            current_players_count: players,
+            max_players_count: 24,
+            ip_port: IpPort::new(Ipv4Addr::LOCALHOST, port),
+            ..Default::default()
+        }
+    }
+
+    fn names(servers: &[Server]) -> Vec<&str> {
+        servers.iter().map(|server| server.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_multiple_keys() {
+        let mut sort = SortKeys::default();
+        let bookmarks = Bookmarks::default();
+        let mut servers = vec![
+            create_server("b", 12, 1),
+            create_server("c", 20, 2),
+            create_server("a", 12, 3),
+        ];
+
+        sort.set_criterion(0, SortCriterion::Players);
+        sort.set_direction(0, SortDirection::Descending);
+        sort.add(&[SortCriterion::Players, SortCriterion::Name]);
+        servers.sort_by(|l, r| sort.compare(&bookmarks, l, r));
+
+        assert_eq!(vec!["c", "a", "b"], names(&servers));
+    }
+
+    #[test]
+    fn test_stable() {
+        let sort = SortKeys {
+            sort_keys: vec![SortKey::new(SortCriterion::Players, SortDirection::Descending)],
+        };
+        let bookmarks = Bookmarks::default();
+        let mut servers = vec![
+            create_server("b", 12, 1),
+            create_server("c", 20, 2),
+            create_server("a", 12, 3),
+        ];
+
+        servers.sort_by(|l, r| sort.compare(&bookmarks, l, r));
+
+        assert_eq!(vec!["c", "b", "a"], names(&servers));
+    }
+
+    #[test]
+    fn test_bookmarked_first() {
+        let mut sort = SortKeys {
+            sort_keys: vec![SortKey::new(SortCriterion::Bookmarked, SortDirection::Ascending)],
+        };
+        let mut bookmarks = Bookmarks::default();
+        let mut servers = vec![create_server("b", 12, 1), create_server("a", 12, 2)];
+
+        bookmarks.add(IpPort::new(Ipv4Addr::LOCALHOST, 2), SourceKey::new("source"));
+        sort.add(&[SortCriterion::Name]);
+        servers.sort_by(|l, r| sort.compare(&bookmarks, l, r));
+
+        assert_eq!(vec!["a", "b"], names(&servers));
+
+        sort.set_direction(0, SortDirection::Descending);
+        servers.sort_by(|l, r| sort.compare(&bookmarks, l, r));
+
+        assert_eq!(vec!["b", "a"], names(&servers));
+    }
+
+    #[test]
+    fn test_deserialize_single_criterion() {
+        let sort: SortKeys = serde_json::from_str(r#"{"sort_criterion":"Ping","sort_direction":"Descending"}"#).unwrap();
+
+        assert_eq!(
+            vec![&SortKey::new(SortCriterion::Ping, SortDirection::Descending)],
+            sort.iter().collect::<Vec<_>>()
+        );
     }
 }
@@ -1,56 +1,276 @@
 use {
-    nom::Finish,
+    crate::application::Server,
+    regex::{Regex, RegexBuilder},
     serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
 };
+
+/// The textual properties of a server a term can be searched in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextField {
+    Name,
+    Map,
+    Provider,
+    GameMode,
+    Country,
+}
+
+/// The numeric properties of a server a number can be compared to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumericField {
+    Players,
+    /// The count of free slots.
+    Slots,
+    /// The median latency in milliseconds.
+    Ping,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn apply(&self, left: u32, right: u32) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+/// A parsed query.
+///
+/// The terms separated by spaces must all match, `OR` separates alternatives and has a lower precedence.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    /// `term` searches the name, `map:term` searches the map name, etc.
+    Text(TextField, String),
+    /// `players>12`, `ping<60`, `slots>=2`
+    Compare(NumericField, Comparison, u32),
+    /// `-term`
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The index of the character where the error occurs.
+    pub position: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at character {}", self.message, self.position + 1)
+    }
+}
+
+#[derive(thiserror::Error, Clone, Debug, PartialEq)]
+pub enum TextFilterError {
+    #[error("{0}")]
+    Query(ParseError),
+    #[error("Invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+}
+
+/// What the input text is compiled to.
+#[derive(Clone, Debug)]
+enum Matcher {
+    Query(Query),
+    /// In regular expression mode, the expression is matched against the server name.
+    Regex(Regex),
+}
+
 /// Text filter
 ///
-/// This filter accepts multiple terms and search for text containing all the terms.
+/// This filter accepts a query made of terms searched in the server name, like `upward "no crits"`,
+/// or in another field, like `map:pl_upward`, `provider:skial`, `mode:koth` or `country:FR`.
+/// The numeric fields can be compared: `players>12`, `ping<60`, `slots>=2`.
+/// A term can be negated with `-trade`, alternatives are separated by `OR` and grouped with parentheses.
+///
+/// In regular expression mode, the whole text is a regular expression searched in the server name.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "SerializedTextFilter")]
 pub struct TextFilter {
     /// Original input text.
     text: String,
-    /// Terms parsed from the input text.
-    terms: Vec<String>,
+    /// Query or regular expression compiled from the input text, `None` if the text is empty.
+    #[serde(skip)]
+    matcher: Option<Matcher>,
+    /// The error of the input text, the last valid matcher is used until the text is fixed.
+    #[serde(skip)]
+    error: Option<TextFilterError>,
     /// Ignore the case when filtering
-    pub ignore_case: bool,
-    /// Ignore the case when filtering
-    pub ignore_accents: bool,
+    ignore_case: bool,
+    /// Ignore the accents when filtering
+    ignore_accents: bool,
+    /// The text is a regular expression instead of a query
+    regex: bool,
+}
+
+/// Only the text is stored, the query is parsed when the filter is read.
+#[derive(Deserialize)]
+struct SerializedTextFilter {
+    text: String,
+    ignore_case: bool,
+    ignore_accents: bool,
+    #[serde(default)]
+    regex: bool,
+}
+
+impl From<SerializedTextFilter> for TextFilter {
+    fn from(serialized: SerializedTextFilter) -> Self {
+        let mut filter = Self {
+            matcher: None,
+            error: None,
+            text: serialized.text,
+            ignore_case: serialized.ignore_case,
+            ignore_accents: serialized.ignore_accents,
+            regex: serialized.regex,
+        };
+
+        filter.compile();
+
+        filter
+    }
 }
 
 impl TextFilter {
     pub fn new(text: &str) -> Self {
         let mut filter = Self {
-            terms: Default::default(),
-            text: String::new(),
+            matcher: None,
+            error: None,
+            text: text.to_string(),
             ignore_case: true,
             ignore_accents: true,
+            regex: false,
         };
 
-        filter.set_text(text);
+        filter.compile();
 
         filter
     }
 
-    fn parse_terms(text: &str) -> Vec<String> {
-        match parser::parse_terms(text).finish() {
-            Ok((_, terms)) => terms,
-            Err(_) => Vec::new(),
-        }
-    }
-
     pub fn set_text(&mut self, text: &str) {
         self.text = text.to_string();
-        self.terms = Self::parse_terms(text);
+        self.compile();
     }
 
     pub fn text(&self) -> &str {
         &self.text
     }
 
-    pub fn accept(&self, text: &str) -> bool {
-        let cleaned_input = self.clean_text(text);
+    pub fn ignore_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    /// The regular expression is compiled again, with or without the case insensitive flag.
+    pub fn set_ignore_case(&mut self, ignore_case: bool) {
+        self.ignore_case = ignore_case;
+        self.compile();
+    }
+
+    pub fn ignore_accents(&self) -> bool {
+        self.ignore_accents
+    }
+
+    pub fn set_ignore_accents(&mut self, ignore_accents: bool) {
+        self.ignore_accents = ignore_accents;
+    }
+
+    pub fn regex(&self) -> bool {
+        self.regex
+    }
 
-        self.terms.is_empty() || self.terms.iter().all(|term| cleaned_input.contains(&self.clean_text(term)))
+    pub fn set_regex(&mut self, regex: bool) {
+        self.regex = regex;
+        self.compile();
+    }
+
+    pub fn error(&self) -> Option<&TextFilterError> {
+        self.error.as_ref()
+    }
+
+    pub fn accept(&self, server: &Server) -> bool {
+        match &self.matcher {
+            Some(Matcher::Query(query)) => self.matches(query, server),
+            Some(Matcher::Regex(regex)) => match self.ignore_accents {
+                true => regex.is_match(&unidecode::unidecode(&server.name)),
+                false => regex.is_match(&server.name),
+            },
+            None => true,
+        }
+    }
+
+    /// Compile the text once, instead of each time a server is filtered.
+    fn compile(&mut self) {
+        let result = match self.regex {
+            true => self.compile_regex(),
+            false => parser::parse_query(&self.text)
+                .map(|query| query.map(Matcher::Query))
+                .map_err(TextFilterError::Query),
+        };
+
+        match result {
+            Ok(matcher) => {
+                self.matcher = matcher;
+                self.error = None;
+            }
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    fn compile_regex(&self) -> Result<Option<Matcher>, TextFilterError> {
+        if self.text.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let regex = RegexBuilder::new(&self.text).case_insensitive(self.ignore_case).build()?;
+
+        Ok(Some(Matcher::Regex(regex)))
+    }
+
+    fn matches(&self, query: &Query, server: &Server) -> bool {
+        match query {
+            Query::Text(field, term) => {
+                let term = self.clean_text(term);
+                let contains = |text: &str| self.clean_text(text).contains(&term);
+
+                match field {
+                    TextField::Name => contains(&server.name),
+                    TextField::Map => contains(server.map.as_str()),
+                    TextField::Provider => contains(&server.provider),
+                    TextField::GameMode => server.game_modes.iter().any(|mode| contains(&mode.to_string())),
+                    TextField::Country => server
+                        .country
+                        .get()
+                        .is_some_and(|country| country.code().eq_ignore_ascii_case(&term) || contains(&country.name())),
+                }
+            }
+            Query::Compare(field, comparison, number) => {
+                let value = match field {
+                    NumericField::Players => Some(server.current_players_count as u32),
+                    NumericField::Slots => Some(server.free_slots() as u32),
+                    NumericField::Ping => server.ping.get().map(|stats| stats.median.as_millis() as u32),
+                };
+
+                value.is_some_and(|value| comparison.apply(value, *number))
+            }
+            Query::Not(query) => !self.matches(query, server),
+            Query::And(queries) => queries.iter().all(|query| self.matches(query, server)),
+            Query::Or(queries) => queries.iter().any(|query| self.matches(query, server)),
+        }
     }
 
     fn clean_text(&self, text: &str) -> String {
@@ -78,9 +298,12 @@ impl Default for TextFilter {
 
 #[cfg(test)]
 mod tests {
-    use test_case::test_case;
-
-    use super::TextFilter;
+    use {
+        super::{TextFilter, TextFilterError},
+        crate::application::{game_mode::GameModeId, map::MapName, Country, PingStats, PromisedValue, Server},
+        std::time::Duration,
+        test_case::test_case,
+    };
 
     #[test_case("hello", "", true; "accept all if no filter")]
     #[test_case("hello", "   ", true; "accept all if no filter (trimmed)")]
@@ -88,38 +311,314 @@ mod tests {
     #[test_case("a", "b", false; "reject")]
     fn test_text_filter(input: &str, filter: &str, expect_accept: bool) {
         let text_filter = TextFilter::new(filter);
+        let server = Server {
+            name: input.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(expect_accept, text_filter.accept(&server))
+    }
+
+    fn create_server() -> Server {
+        Server {
+            name: "Skial | Upward 24/7".to_string(),
+            map: MapName::new("pl_upward"),
+            provider: "Skial".to_string(),
+            game_modes: vec![GameModeId::new("payload")],
+            country: PromisedValue::Ready(Country::new(&"US")),
+            ping: PromisedValue::Ready(PingStats::from_samples(&[Some(Duration::from_millis(42))]).unwrap()),
+            current_players_count: 20,
+            max_players_count: 24,
+            ..Default::default()
+        }
+    }
+
+    #[test_case("upward", true; "name")]
+    #[test_case("map:pl_upward", true; "map")]
+    #[test_case("map:koth", false; "other map")]
+    #[test_case("provider:skial", true; "provider")]
+    #[test_case("mode:payload", true; "game mode")]
+    #[test_case("country:us", true; "country code")]
+    #[test_case("country:\"united states\"", true; "quoted country name")]
+    #[test_case("country:FR", false; "other country")]
+    #[test_case("players>12", true; "players greater")]
+    #[test_case("players>=21", false; "players greater or equal")]
+    #[test_case("slots>=4", true; "free slots")]
+    #[test_case("slots=5", false; "free slots equal")]
+    #[test_case("ping<60", true; "ping")]
+    #[test_case("ping!=42", false; "ping not equal")]
+    #[test_case("-trade", true; "negation")]
+    #[test_case("-upward", false; "negated name")]
+    #[test_case("trade OR upward", true; "or")]
+    #[test_case("trade OR koth", false; "or rejected")]
+    #[test_case("(trade OR map:pl_) ping<60", true; "group")]
+    #[test_case("-(trade OR map:pl_)", false; "negated group")]
+    fn test_query(query: &str, expect_accept: bool) {
+        let text_filter = TextFilter::new(query);
+
+        assert_eq!(None, text_filter.error());
+        assert_eq!(expect_accept, text_filter.accept(&create_server()));
+    }
+
+    #[test]
+    fn test_unknown_ping() {
+        let server = Server {
+            ping: PromisedValue::None,
+            ..create_server()
+        };
+
+        assert!(!TextFilter::new("ping<60").accept(&server));
+        assert!(TextFilter::new("-ping<60").accept(&server));
+    }
+
+    #[test]
+    fn test_keep_last_valid_query() {
+        let mut text_filter = TextFilter::new("koth");
+
+        text_filter.set_text("koth ping<");
+
+        assert!(text_filter.error().is_some());
+        assert!(!text_filter.accept(&create_server()));
+
+        text_filter.set_text("upward");
+
+        assert_eq!(None, text_filter.error());
+        assert!(text_filter.accept(&create_server()));
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let text_filter: TextFilter = serde_json::from_str(
+            r#"{"text":"upward players>12","terms":["upward","players>12"],"ignore_case":true,"ignore_accents":false}"#,
+        )
+        .unwrap();
+
+        assert!(!text_filter.ignore_accents());
+        assert!(!text_filter.regex());
+        assert!(text_filter.accept(&create_server()));
+    }
+
+    #[test_case("^skial", true; "anchored")]
+    #[test_case("(uncletopia|skial)", true; "alternatives")]
+    #[test_case("^upward", false; "rejected")]
+    #[test_case("map:pl_upward", false; "query syntax is not parsed")]
+    fn test_regex(pattern: &str, expect_accept: bool) {
+        let mut text_filter = TextFilter::new(pattern);
+
+        text_filter.set_regex(true);
 
-        assert_eq!(expect_accept, text_filter.accept(input))
+        assert_eq!(None, text_filter.error());
+        assert_eq!(expect_accept, text_filter.accept(&create_server()));
+    }
+
+    #[test]
+    fn test_regex_ignore_case() {
+        let mut text_filter = TextFilter::new("^SKIAL");
+
+        text_filter.set_regex(true);
+        assert!(text_filter.accept(&create_server()));
+
+        text_filter.set_ignore_case(false);
+        assert!(!text_filter.accept(&create_server()));
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let mut text_filter = TextFilter::new("upward");
+
+        text_filter.set_regex(true);
+        text_filter.set_text("(upward");
+
+        assert!(matches!(text_filter.error(), Some(TextFilterError::Regex(_))));
+        assert!(text_filter.accept(&create_server()));
+
+        text_filter.set_text("^trade");
+
+        assert_eq!(None, text_filter.error());
+        assert!(!text_filter.accept(&create_server()));
     }
 }
 
 mod parser {
-    use nom::{
-        branch::alt,
-        bytes::complete::is_not,
-        character::complete::{char, multispace1},
-        combinator::complete,
-        multi::separated_list0,
-        sequence::{delimited, preceded},
-        IResult,
+    use {
+        super::{Comparison, NumericField, ParseError, Query, TextField},
+        nom::{
+            branch::alt,
+            bytes::complete::{is_not, tag, tag_no_case},
+            character::complete::{char, multispace0, multispace1, u32},
+            combinator::{cut, map, not, value, verify},
+            error::{context, VerboseError, VerboseErrorKind},
+            multi::{many0, separated_list1},
+            sequence::{delimited, pair, preceded, terminated, tuple},
+            IResult,
+        },
     };
 
-    fn parse_term(s: &str) -> IResult<&str, &str> {
+    type ParseResult<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+
+    const OR: &str = "OR";
+
+    fn parse_quoted(s: &str) -> ParseResult<'_, &str> {
         let parse_quoted = delimited(char('\"'), is_not("\""), char('\"'));
         let parse_missing_quote = preceded(char('\"'), is_not("\""));
-        let parse_word = is_not(" \t\r\n");
 
-        alt((parse_quoted, parse_missing_quote, parse_word))(s)
+        alt((parse_quoted, parse_missing_quote))(s)
     }
 
-    pub(crate) fn parse_terms(s: &str) -> IResult<&str, Vec<String>> {
-        complete(separated_list0(multispace1, parse_term))(s.trim())
-            .map(|(remaining, terms)| (remaining, terms.iter().map(ToString::to_string).collect()))
+    fn parse_word(s: &str) -> ParseResult<'_, &str> {
+        verify(is_not(" \t\r\n\"()"), |word: &str| word != OR)(s)
+    }
+
+    fn parse_term(s: &str) -> ParseResult<'_, &str> {
+        alt((parse_quoted, parse_word))(s)
+    }
+
+    fn parse_text_field(s: &str) -> ParseResult<'_, TextField> {
+        alt((
+            value(TextField::Name, tag_no_case("name")),
+            value(TextField::Map, tag_no_case("map")),
+            value(TextField::Provider, tag_no_case("provider")),
+            value(TextField::GameMode, tag_no_case("mode")),
+            value(TextField::Country, tag_no_case("country")),
+        ))(s)
+    }
+
+    fn parse_numeric_field(s: &str) -> ParseResult<'_, NumericField> {
+        alt((
+            value(NumericField::Players, tag_no_case("players")),
+            value(NumericField::Slots, tag_no_case("slots")),
+            value(NumericField::Ping, tag_no_case("ping")),
+        ))(s)
+    }
+
+    fn parse_comparison(s: &str) -> ParseResult<'_, Comparison> {
+        alt((
+            value(Comparison::GreaterOrEqual, tag(">=")),
+            value(Comparison::LessOrEqual, tag("<=")),
+            value(Comparison::NotEqual, tag("!=")),
+            value(Comparison::Greater, tag(">")),
+            value(Comparison::Less, tag("<")),
+            value(Comparison::Equal, tag("=")),
+            value(Comparison::Equal, tag(":")),
+        ))(s)
+    }
+
+    fn parse_field_term(s: &str) -> ParseResult<'_, Query> {
+        map(
+            pair(terminated(parse_text_field, char(':')), cut(context("a value", parse_term))),
+            |(field, term)| Query::Text(field, term.to_string()),
+        )(s)
+    }
+
+    fn parse_comparison_term(s: &str) -> ParseResult<'_, Query> {
+        map(
+            tuple((parse_numeric_field, parse_comparison, cut(context("a number", u32)))),
+            |(field, comparison, number)| Query::Compare(field, comparison, number),
+        )(s)
+    }
+
+    fn parse_group(s: &str) -> ParseResult<'_, Query> {
+        preceded(
+            terminated(char('('), multispace0),
+            cut(terminated(
+                context("an expression", parse_or),
+                context("a closing parenthesis", preceded(multispace0, char(')'))),
+            )),
+        )(s)
+    }
+
+    fn parse_primary(s: &str) -> ParseResult<'_, Query> {
+        alt((
+            parse_group,
+            parse_comparison_term,
+            parse_field_term,
+            map(parse_term, |term| Query::Text(TextField::Name, term.to_string())),
+        ))(s)
+    }
+
+    fn parse_unary(s: &str) -> ParseResult<'_, Query> {
+        alt((
+            map(preceded(char('-'), parse_primary), |query| Query::Not(Box::new(query))),
+            parse_primary,
+        ))(s)
+    }
+
+    fn parse_and(s: &str) -> ParseResult<'_, Query> {
+        map(separated_list1(multispace1, parse_unary), |mut queries| match queries.len() {
+            1 => queries.remove(0),
+            _ => Query::And(queries),
+        })(s)
+    }
+
+    fn parse_or(s: &str) -> ParseResult<'_, Query> {
+        // `OR` must be a whole word, `ORANGE` is a term.
+        let parse_separator = tuple((multispace1, tag(OR), not(is_not(" \t\r\n\"()"))));
+
+        map(
+            pair(
+                parse_and,
+                many0(preceded(
+                    pair(parse_separator, multispace0),
+                    cut(context("an expression after OR", parse_and)),
+                )),
+            ),
+            |(first, others)| match others.is_empty() {
+                true => first,
+                false => Query::Or(std::iter::once(first).chain(others).collect()),
+            },
+        )(s)
+    }
+
+    /// Parse a query, returns `None` if the text is blank.
+    pub(crate) fn parse_query(s: &str) -> Result<Option<Query>, ParseError> {
+        if s.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let error_at = |remaining: &str, message: String| ParseError {
+            position: s[..s.len() - remaining.len()].chars().count(),
+            message,
+        };
+        let unexpected = |remaining: &str| {
+            let message = match remaining.split_whitespace().next() {
+                Some(token) if token.starts_with(')') => "Unexpected ')'".to_string(),
+                Some(token) => format!("Unexpected '{}'", token),
+                None => "Unexpected end of the query".to_string(),
+            };
+
+            error_at(remaining, message)
+        };
+
+        match delimited(multispace0, parse_or, multispace0)(s) {
+            Ok(("", query)) => Ok(Some(query)),
+            Ok((remaining, _)) => Err(unexpected(remaining)),
+            Err(nom::Err::Error(error)) | Err(nom::Err::Failure(error)) => {
+                let expected = error.errors.iter().find_map(|(remaining, kind)| match kind {
+                    VerboseErrorKind::Context(expected) => Some((*remaining, *expected)),
+                    _ => None,
+                });
+
+                match (expected, error.errors.first()) {
+                    (Some((remaining, expected)), _) => Err(error_at(remaining, format!("Expected {}", expected))),
+                    (None, Some((remaining, _))) => Err(unexpected(remaining)),
+                    (None, None) => Err(unexpected(s)),
+                }
+            }
+            Err(nom::Err::Incomplete(_)) => Err(unexpected("")),
+        }
     }
 
     #[cfg(test)]
     mod tests {
-        use test_case::test_case;
+        use {
+            super::parse_query,
+            crate::application::filter::text_filter::{Comparison, NumericField, Query, TextField},
+            test_case::test_case,
+        };
+
+        fn name(term: &str) -> Query {
+            Query::Text(TextField::Name, term.to_string())
+        }
 
         #[test_case("", &[]; "empty")]
         #[test_case("  ", &[]; "empty space only")]
@@ -130,9 +629,55 @@ mod parser {
         #[test_case(" a ", &["a"]; "trim one")]
         #[test_case(" a b ", &["a", "b"]; "trim 2")]
         fn test_parse_terms(input: &str, expected_terms: &[&str]) {
-            let (_, parsed_terms) = super::parse_terms(input).unwrap();
+            let expected = match expected_terms {
+                [] => None,
+                [term] => Some(name(term)),
+                terms => Some(Query::And(terms.iter().map(|term| name(term)).collect())),
+            };
+
+            assert_eq!(expected, parse_query(input).unwrap())
+        }
+
+        #[test]
+        fn test_parse_query() {
+            let expected = Query::And(vec![
+                Query::Or(vec![
+                    Query::Text(TextField::Map, "pl_upward".to_string()),
+                    Query::And(vec![
+                        Query::Text(TextField::GameMode, "koth".to_string()),
+                        Query::Compare(NumericField::Players, Comparison::Greater, 12),
+                    ]),
+                ]),
+                Query::Not(Box::new(name("trade"))),
+                Query::Compare(NumericField::Slots, Comparison::GreaterOrEqual, 2),
+            ]);
+
+            assert_eq!(
+                Some(expected),
+                parse_query("(map:pl_upward OR mode:koth players>12) -trade slots>=2").unwrap()
+            );
+        }
+
+        #[test_case("ORANGE", name("ORANGE"); "or prefix")]
+        #[test_case("players", name("players"); "field without comparison")]
+        #[test_case("1.2.3.4:27015", name("1.2.3.4:27015"); "unknown field")]
+        #[test_case("-", name("-"); "dash")]
+        fn test_parse_term(input: &str, expected: Query) {
+            assert_eq!(Some(expected), parse_query(input).unwrap())
+        }
+
+        #[test_case("ping<", 5, "Expected a number"; "missing number")]
+        #[test_case("ping<abc", 5, "Expected a number"; "invalid number")]
+        #[test_case("map:", 4, "Expected a value"; "missing value")]
+        #[test_case("(a b", 4, "Expected a closing parenthesis"; "missing parenthesis")]
+        #[test_case("a)", 1, "Unexpected ')'"; "unexpected parenthesis")]
+        #[test_case("a OR", 4, "Expected an expression after OR"; "missing alternative")]
+        #[test_case("a OR )", 5, "Expected an expression after OR"; "invalid alternative")]
+        #[test_case("OR a", 0, "Unexpected 'OR'"; "starts with or")]
+        fn test_parse_error(input: &str, position: usize, message: &str) {
+            let error = parse_query(input).unwrap_err();
 
-            assert_eq!(expected_terms, parsed_terms)
+            assert_eq!((position, message), (error.position, error.message.as_str()));
         }
     }
 }
@@ -1,5 +1,5 @@
 use {
-    crate::application::GameModesMessage,
+    crate::application::{map::MapName, GameModesMessage},
     async_stream::stream,
     iced::{subscription, Color, Subscription},
     serde::{Deserialize, Serialize},
@@ -31,6 +31,8 @@ pub struct GameMode {
     pub title: String,
     pub description: String,
     pub color: Option<Color>,
+    /// True for the game modes of TF2, false for the game modes created by the community.
+    pub official: bool,
 }
 
 impl From<teamwork::GameMode> for GameMode {
@@ -40,6 +42,7 @@ impl From<teamwork::GameMode> for GameMode {
             title: value.title,
             description: value.description,
             color: value.color.map(|c| iced::Color::from_rgb8(c.r, c.g, c.b)),
+            official: value.official,
         }
     }
 }
@@ -48,6 +51,15 @@ pub struct GameModes {
     game_modes_info: BTreeMap<GameModeId, GameMode>,
 }
 
+/// The game modes of the maps, by prefix of the map name, with the title of the game mode on Teamwork.tf.
+const MAP_PREFIX_GAME_MODES: [(&str, &str); 5] = [
+    ("pl_", "Payload"),
+    ("plr_", "Payload Race"),
+    ("koth_", "King Of The Hill"),
+    ("ctf_", "Capture The Flag"),
+    ("cp_", "Control Point"),
+];
+
 impl GameModes {
     pub fn new() -> Self {
         Self {
@@ -65,13 +77,34 @@ impl GameModes {
     pub fn get(&self, id: &GameModeId) -> Option<&GameMode> {
         self.game_modes_info.get(id)
     }
+
+    pub fn official_ids(&self) -> impl Iterator<Item = &GameModeId> {
+        self.game_modes_info
+            .iter()
+            .filter_map(|(id, mode)| mode.official.then_some(id))
+    }
+
+    /// Guess the game modes of a map from the prefix of its name, for the servers not listed by Teamwork.tf.
+    ///
+    /// The game modes are found by title in the game modes received from Teamwork.tf,
+    /// so nothing is guessed until they are received.
+    pub fn guess_from_map(&self, map: &MapName) -> Vec<GameModeId> {
+        MAP_PREFIX_GAME_MODES
+            .iter()
+            .filter(|(prefix, _)| map.as_str().starts_with(prefix))
+            .filter_map(|(_, title)| {
+                self.game_modes_info
+                    .iter()
+                    .find(|(_, mode)| mode.title.eq_ignore_ascii_case(title))
+                    .map(|(id, _)| id.clone())
+            })
+            .collect()
+    }
 }
 
-pub fn subscription(id: u64, teamwork_api_key: &str) -> Subscription<GameModesMessage> {
+pub fn subscription(id: u64, client: teamwork::Client, teamwork_api_key: &str) -> Subscription<GameModesMessage> {
     let teamwork_api_key = teamwork_api_key.to_string();
     let s = stream! {
-        let client = teamwork::Client::default();
-
         match client.get_game_modes(&teamwork_api_key).await {
              Ok(game_modes) => {
                  yield GameModesMessage::GameModes(game_modes);
@@ -84,3 +117,50 @@ pub fn subscription(id: u64, teamwork_api_key: &str) -> Subscription<GameModesMe
 
     subscription::run_with_id(id, s)
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{GameModeId, GameModes},
+        crate::application::{create_replay_client, map::MapName},
+        iced::futures::executor::block_on,
+        test_case::test_case,
+    };
+
+    #[test]
+    fn test_reset_game_modes() {
+        let mut game_modes = GameModes::new();
+
+        game_modes.reset(&block_on(create_replay_client().get_game_modes("key")).unwrap());
+
+        let koth = game_modes.get(&GameModeId::new("koth")).unwrap();
+
+        assert_eq!("King Of The Hill", koth.title);
+        assert_eq!(Some(iced::Color::from_rgb8(0xb0, 0x30, 0x30)), koth.color);
+        assert!(koth.official);
+        assert!(!game_modes.get(&GameModeId::new("vsh")).unwrap().official);
+        assert!(game_modes.get(&GameModeId::new("unknown")).is_none());
+        assert_eq!(
+            vec![&GameModeId::new("koth"), &GameModeId::new("payload")],
+            game_modes.official_ids().collect::<Vec<_>>()
+        );
+    }
+
+    #[test_case("pl_upward", &["payload"])]
+    #[test_case("koth_harvest_final", &["koth"])]
+    #[test_case("vsh_distillery", &[]; "community game mode")]
+    #[test_case("itemtest", &[]; "no prefix")]
+    fn test_guess_from_map(map: &str, expected: &[&str]) {
+        let mut game_modes = GameModes::new();
+        let map = MapName::new(map);
+
+        assert!(game_modes.guess_from_map(&map).is_empty());
+
+        game_modes.reset(&block_on(create_replay_client().get_game_modes("key")).unwrap());
+
+        assert_eq!(
+            expected.iter().map(GameModeId::new).collect::<Vec<_>>(),
+            game_modes.guess_from_map(&map)
+        );
+    }
+}
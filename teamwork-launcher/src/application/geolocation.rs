@@ -1,18 +1,20 @@
 use {
-    crate::application::{country::Country, message::CountryServiceMessage},
+    crate::application::{
+        country::Country,
+        geolocation_database::GeolocationDatabase,
+        ip_port::Host,
+        message::CountryServiceMessage,
+        worker_pool::{WorkerPool, WorkerRequest, DEFAULT_CONCURRENCY},
+    },
     iced::{
         futures::{
             channel::mpsc::{unbounded, UnboundedReceiver},
-            StreamExt,
+            FutureExt,
         },
         subscription, Subscription,
     },
     serde::{Deserialize, Serialize},
-    std::{
-        collections::{btree_map::Entry, BTreeMap},
-        net::Ipv4Addr,
-        time::Duration,
-    },
+    std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Duration},
 };
 
 #[derive(thiserror::Error, Debug, Clone)]
@@ -37,9 +39,10 @@ struct CountryIsResponse {
     country: String,
 }
 
-async fn locate(ip: &Ipv4Addr, timeout: Duration) -> Result<Country, Error> {
+async fn locate(host: &Host, timeout: Duration) -> Result<Country, Error> {
     const COUNTRY_IS_API_URL: &str = "https://api.country.is";
 
+    let ip = host.resolve().await.map_err(|error| Error::new(host.to_string(), &error))?;
     let url = format!("{}/{}", COUNTRY_IS_API_URL, ip);
     let ip = ip.to_string();
     let client = reqwest::Client::builder()
@@ -60,44 +63,115 @@ async fn locate(ip: &Ipv4Addr, timeout: Duration) -> Result<Country, Error> {
     Ok(Country::new(&response.country))
 }
 
+type LocateResult = (Host, Result<Country, Error>);
+
+/// Find the country of a host with the local database, and with the web service if allowed.
+#[derive(Clone)]
+struct Locator {
+    database: Option<Arc<GeolocationDatabase>>,
+    http_fallback: bool,
+}
+
+impl Locator {
+    async fn locate(&self, host: &Host) -> Result<Country, Error> {
+        if let Some(database) = &self.database {
+            let ip = host.resolve().await.map_err(|error| Error::new(host.to_string(), &error))?;
+
+            if let Some(country) = database.locate(ip) {
+                return Ok(country);
+            }
+
+            if !self.http_fallback {
+                return Err(Error::new(ip.to_string(), &"not found in the geolocation database"));
+            }
+        } else if !self.http_fallback {
+            return Err(Error::new(host.to_string(), &"the geolocation database is not available"));
+        }
+
+        locate(host, Duration::from_secs(10)).await
+    }
+}
+
 enum State {
-    Starting,
-    Ready(UnboundedReceiver<Ipv4Addr>, BTreeMap<Ipv4Addr, Country>),
+    Starting(Option<PathBuf>, bool),
+    Ready(
+        UnboundedReceiver<WorkerRequest<Host>>,
+        BTreeMap<Host, Country>,
+        WorkerPool<Host, LocateResult>,
+        Locator,
+    ),
 }
 
-pub fn subscription() -> Subscription<CountryServiceMessage> {
+/// The service is restarted when the database or the fallback setting change.
+///
+/// Without database, the countries are requested to the web service whatever the fallback setting.
+/// If the database can't be opened, the web service is used only if the fallback is enabled.
+pub fn subscription(database: Option<PathBuf>, http_fallback: bool) -> Subscription<CountryServiceMessage> {
     struct Geolocation;
 
-    subscription::unfold(std::any::TypeId::of::<Geolocation>(), State::Starting, |state| async move {
-        match state {
-            State::Starting => {
-                let (sender, receiver) = unbounded();
+    let http_fallback = http_fallback || database.is_none();
 
-                (
-                    CountryServiceMessage::Started(sender),
-                    State::Ready(receiver, BTreeMap::new()),
-                )
-            }
-            State::Ready(mut receiver, mut cache) => {
-                let ip = receiver.select_next_some().await;
+    subscription::unfold(
+        (std::any::TypeId::of::<Geolocation>(), database.clone(), http_fallback),
+        State::Starting(database, http_fallback),
+        |state| async move {
+            match state {
+                State::Starting(Some(path), http_fallback) => match GeolocationDatabase::open(&path).await {
+                    Ok(database) => start(Some(Arc::new(database)), http_fallback),
+                    // Start without the database, the error is reported once.
+                    Err(error) => (
+                        CountryServiceMessage::DatabaseError(error),
+                        State::Starting(None, http_fallback),
+                    ),
+                },
+                State::Starting(None, http_fallback) => start(None, http_fallback),
+                State::Ready(mut receiver, mut cache, mut pool, locator) => {
+                    let (host, result) = pool
+                        .next(&mut receiver, |host| {
+                            let cached = cache.get(&host).cloned();
+                            let locator = locator.clone();
+
+                            async move {
+                                let result = match cached {
+                                    Some(country) => Ok(country),
+                                    None => locator.locate(&host).await,
+                                };
+
+                                (host, result)
+                            }
+                            .boxed()
+                        })
+                        .await;
 
-                match cache.entry(ip) {
-                    Entry::Vacant(vacant) => match locate(&ip, Duration::from_secs(10)).await {
+                    match result {
                         Ok(country) => {
-                            vacant.insert(country.clone());
+                            cache.insert(host.clone(), country.clone());
                             (
-                                CountryServiceMessage::CountryFound(ip, country),
-                                State::Ready(receiver, cache),
+                                CountryServiceMessage::CountryFound(host, country),
+                                State::Ready(receiver, cache, pool, locator),
                             )
                         }
-                        Err(error) => (CountryServiceMessage::Error(ip, error), State::Ready(receiver, cache)),
-                    },
-                    Entry::Occupied(occupied) => (
-                        CountryServiceMessage::CountryFound(ip, occupied.get().clone()),
-                        State::Ready(receiver, cache),
-                    ),
+                        Err(error) => (
+                            CountryServiceMessage::Error(host, error),
+                            State::Ready(receiver, cache, pool, locator),
+                        ),
+                    }
                 }
             }
-        }
-    })
+        },
+    )
+}
+
+fn start(database: Option<Arc<GeolocationDatabase>>, http_fallback: bool) -> (CountryServiceMessage, State) {
+    let (sender, receiver) = unbounded();
+
+    (
+        CountryServiceMessage::Started(sender),
+        State::Ready(
+            receiver,
+            BTreeMap::new(),
+            WorkerPool::new(DEFAULT_CONCURRENCY),
+            Locator { database, http_fallback },
+        ),
+    )
 }
@@ -0,0 +1,194 @@
+use {
+    crate::application::country::Country,
+    rfd::AsyncFileDialog,
+    std::{
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        path::{Path, PathBuf},
+    },
+};
+
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum Error {
+    #[error("Failed to read the geolocation database {0}: {1}")]
+    Io(PathBuf, String),
+    #[error("Invalid MaxMind database: {0}")]
+    MaxMind(String),
+    #[error("Invalid IP range at line {0}: {1}")]
+    InvalidRange(usize, String),
+}
+
+struct IpRange {
+    first: IpAddr,
+    last: IpAddr,
+    country: Country,
+}
+
+/// The countries of ranges of IP addresses, read from a CSV file.
+///
+/// Each line contains the first and the last address of the range followed by the country code, like
+/// `1.0.0.0,1.0.0.255,AU`. The other columns are ignored, this is the format of the free databases of DB-IP
+/// and IP2Location.
+pub struct IpRanges {
+    /// Sorted by first address.
+    ranges: Vec<IpRange>,
+}
+
+impl IpRanges {
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut ranges = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(',').map(|field| field.trim().trim_matches('"')).take(3).collect();
+            let (first, last) = match (
+                fields.first().and_then(|field| parse_address(field)),
+                fields.get(1).and_then(|field| parse_address(field)),
+            ) {
+                (Some(first), Some(last)) if fields.len() == 3 => (first, last),
+                // The first line can be the names of the columns.
+                _ if index == 0 => continue,
+                _ => return Err(Error::InvalidRange(index + 1, line.to_string())),
+            };
+            let code = fields[2];
+
+            // The unknown countries are marked with `-` or `ZZ`.
+            if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) || code.eq_ignore_ascii_case("ZZ") {
+                continue;
+            }
+
+            ranges.push(IpRange {
+                first,
+                last,
+                country: Country::new(&code.to_ascii_uppercase()),
+            });
+        }
+
+        ranges.sort_by_key(|range| range.first);
+
+        Ok(Self { ranges })
+    }
+
+    pub fn locate(&self, ip: IpAddr) -> Option<&Country> {
+        let find = |ip: IpAddr| {
+            let count = self.ranges.partition_point(|range| range.first <= ip);
+
+            self.ranges[..count]
+                .last()
+                .filter(|range| ip <= range.last)
+                .map(|range| &range.country)
+        };
+
+        // The IPv6 databases store the IPv4 addresses as IPv4-mapped IPv6 addresses.
+        find(ip).or_else(|| match ip {
+            IpAddr::V4(ip) => find(IpAddr::V6(ip.to_ipv6_mapped())),
+            IpAddr::V6(_) => None,
+        })
+    }
+}
+
+/// Parse an address, written as usual or as a number.
+fn parse_address(text: &str) -> Option<IpAddr> {
+    match text.parse::<u128>() {
+        Ok(number) => Some(match u32::try_from(number) {
+            Ok(number) => IpAddr::V4(Ipv4Addr::from(number)),
+            Err(_) => IpAddr::V6(Ipv6Addr::from(number)),
+        }),
+        Err(_) => text.parse().ok(),
+    }
+}
+
+/// A local database used to find the country of an IP address without sending it to a web service.
+pub enum GeolocationDatabase {
+    MaxMind(maxminddb::Reader<Vec<u8>>),
+    Ranges(IpRanges),
+}
+
+impl GeolocationDatabase {
+    /// Open a MaxMind database if the file extension is `mmdb`, otherwise a CSV file of IP ranges.
+    pub async fn open(path: &Path) -> Result<Self, Error> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|error| Error::Io(path.to_path_buf(), error.to_string()))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("mmdb") => maxminddb::Reader::from_source(bytes)
+                .map(GeolocationDatabase::MaxMind)
+                .map_err(|error| Error::MaxMind(error.to_string())),
+            _ => IpRanges::parse(&String::from_utf8_lossy(&bytes)).map(GeolocationDatabase::Ranges),
+        }
+    }
+
+    pub fn locate(&self, ip: IpAddr) -> Option<Country> {
+        match self {
+            GeolocationDatabase::MaxMind(reader) => reader
+                .lookup::<maxminddb::geoip2::Country>(ip)
+                .ok()
+                .and_then(|country| country.country.or(country.registered_country))
+                .and_then(|country| country.iso_code)
+                .map(|code| Country::new(&code)),
+            GeolocationDatabase::Ranges(ranges) => ranges.locate(ip).cloned(),
+        }
+    }
+}
+
+/// Ask the user to select a geolocation database.
+pub async fn pick_database_file() -> Option<PathBuf> {
+    AsyncFileDialog::new()
+        .add_filter("IP geolocation database", &["mmdb", "csv"])
+        .pick_file()
+        .await
+        .map(|file_handle| file_handle.path().to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{Error, IpRanges},
+        crate::application::Country,
+        std::net::IpAddr,
+        test_case::test_case,
+    };
+
+    const RANGES: &str = "ip_start,ip_end,country\n\
+        1.0.0.0,1.0.0.255,AU\n\
+        \"5.39.0.0\",\"5.39.127.255\",\"fr\"\n\
+        # Comment\n\
+        16777472,16778239,CN,China\n\
+        10.0.0.0,10.255.255.255,ZZ\n\
+        2001:200::,2001:200:ffff:ffff:ffff:ffff:ffff:ffff,JP\n";
+
+    #[test_case("1.0.0.42", Some("AU"); "first range")]
+    #[test_case("5.39.12.34", Some("FR"); "quoted lowercase")]
+    #[test_case("1.0.1.1", Some("CN"); "numeric range")]
+    #[test_case("1.0.4.1", None; "between ranges")]
+    #[test_case("10.1.2.3", None; "unknown country")]
+    #[test_case("2001:200::1", Some("JP"); "ipv6")]
+    #[test_case("0.0.0.1", None; "before first range")]
+    fn test_locate(ip: &str, expected: Option<&str>) {
+        let ranges = IpRanges::parse(RANGES).unwrap();
+        let ip: IpAddr = ip.parse().unwrap();
+
+        assert_eq!(expected.map(|code| Country::new(&code)).as_ref(), ranges.locate(ip));
+    }
+
+    #[test]
+    fn test_locate_ipv4_mapped() {
+        // ::ffff:1.0.0.0 to ::ffff:1.0.0.255
+        let ranges = IpRanges::parse("281470698520576,281470698520831,AU").unwrap();
+
+        assert_eq!(Some(&Country::new(&"AU")), ranges.locate("1.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_parse_invalid_range() {
+        assert!(matches!(
+            IpRanges::parse("1.0.0.0,1.0.0.255,AU\n1.0.1.0,AU"),
+            Err(Error::InvalidRange(2, _))
+        ));
+    }
+}
@@ -1,27 +1,135 @@
 use {
-    serde::{Deserialize, Serialize},
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
     std::{
         fmt::{Display, Formatter},
-        net::Ipv4Addr,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        str::FromStr,
     },
 };
 
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid server address '{0}'")]
+pub struct InvalidHost(pub String);
+
+/// The address of a server: an IPv4, an IPv6 or a DNS host name.
+#[derive(Debug, Hash, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Host {
+    Ip(IpAddr),
+    Name(String),
+}
+
+impl Host {
+    pub fn ip(&self) -> Option<&IpAddr> {
+        match self {
+            Host::Ip(ip) => Some(ip),
+            Host::Name(_) => None,
+        }
+    }
+
+    /// Returns the IP of the host, the host names are resolved using the DNS.
+    pub async fn resolve(&self) -> std::io::Result<IpAddr> {
+        match self {
+            Host::Ip(ip) => Ok(*ip),
+            Host::Name(name) => tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .next()
+                .map(|address| address.ip())
+                .ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::NotFound, format!("No address found for '{}'", name))
+                }),
+        }
+    }
+
+    fn is_valid_name(name: &str) -> bool {
+        name.len() <= 253
+            && name.split('.').all(|label| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+            // A name made only of digits and dots is a malformed IPv4.
+            && !name.chars().all(|c| c.is_ascii_digit() || c == '.')
+    }
+}
+
+impl FromStr for Host {
+    type Err = InvalidHost;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+
+        if let Ok(ip) = IpAddr::from_str(input.trim_start_matches('[').trim_end_matches(']')) {
+            return Ok(Host::Ip(ip));
+        }
+
+        match Self::is_valid_name(input) {
+            true => Ok(Host::Name(input.to_ascii_lowercase())),
+            false => Err(InvalidHost(input.to_string())),
+        }
+    }
+}
+
+impl From<IpAddr> for Host {
+    fn from(ip: IpAddr) -> Self {
+        Host::Ip(ip)
+    }
+}
+
+impl From<Ipv4Addr> for Host {
+    fn from(ip: Ipv4Addr) -> Self {
+        Host::Ip(IpAddr::V4(ip))
+    }
+}
+
+impl From<Ipv6Addr> for Host {
+    fn from(ip: Ipv6Addr) -> Self {
+        Host::Ip(IpAddr::V6(ip))
+    }
+}
+
+impl Display for Host {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Host::Ip(ip) => write!(f, "{}", ip),
+            Host::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+// Serialized as a string, so the files written when only IPv4 was supported can still be read.
+impl Serialize for Host {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Host {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+
+        Host::from_str(&text).map_err(serde::de::Error::custom)
+    }
+}
+
 /// The unique key identifying a server.
 #[derive(Debug, Hash, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct IpPort {
-    ip: Ipv4Addr,
+    #[serde(rename = "ip")]
+    host: Host,
     port: u16,
 }
 
 impl IpPort {
-    pub fn new(ip: Ipv4Addr, port: u16) -> Self {
-        Self { ip, port }
+    pub fn new(host: impl Into<Host>, port: u16) -> Self {
+        Self { host: host.into(), port }
     }
     pub fn steam_connection_string(&self) -> String {
-        format!("connect {}:{}", self.ip, self.port)
+        format!("connect {}", self)
     }
-    pub fn ip(&self) -> &Ipv4Addr {
-        &self.ip
+    pub fn host(&self) -> &Host {
+        &self.host
     }
     pub fn port(&self) -> u16 {
         self.port
@@ -30,21 +138,60 @@ impl IpPort {
 
 impl Default for IpPort {
     fn default() -> Self {
-        Self {
-            ip: Ipv4Addr::UNSPECIFIED,
-            port: Default::default(),
-        }
+        Self::new(Ipv4Addr::UNSPECIFIED, Default::default())
     }
 }
 
-impl From<(Ipv4Addr, u16)> for IpPort {
-    fn from(input: (Ipv4Addr, u16)) -> Self {
+impl<H: Into<Host>> From<(H, u16)> for IpPort {
+    fn from(input: (H, u16)) -> Self {
         Self::new(input.0, input.1)
     }
 }
 
 impl Display for IpPort {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}", self.ip(), self.port())
+        match &self.host {
+            Host::Ip(IpAddr::V6(ip)) => write!(f, "[{}]:{}", ip, self.port),
+            host => write!(f, "{}:{}", host, self.port),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{Host, InvalidHost, IpPort},
+        std::{
+            net::{IpAddr, Ipv4Addr, Ipv6Addr},
+            str::FromStr,
+        },
+        test_case::test_case,
+    };
+
+    #[test_case("1.2.3.4", Ok(Host::Ip(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)))))]
+    #[test_case("2001:db8::1", Ok(Host::Ip(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)))))]
+    #[test_case("[::1]", Ok(Host::Ip(IpAddr::V6(Ipv6Addr::LOCALHOST))))]
+    #[test_case("tf2.Example.com", Ok(Host::Name("tf2.example.com".to_string())))]
+    #[test_case("1.2.3.400", Err(InvalidHost("1.2.3.400".to_string())))]
+    #[test_case("", Err(InvalidHost(String::new())))]
+    #[test_case("not a host", Err(InvalidHost("not a host".to_string())))]
+    #[test_case("-bad.example.com", Err(InvalidHost("-bad.example.com".to_string())))]
+    fn test_parse_host(input: &str, expected: Result<Host, InvalidHost>) {
+        assert_eq!(expected, Host::from_str(input));
+    }
+
+    #[test_case(IpPort::new(Ipv4Addr::new(1, 2, 3, 4), 27015), "1.2.3.4:27015")]
+    #[test_case(IpPort::new(Ipv6Addr::LOCALHOST, 27015), "[::1]:27015")]
+    #[test_case(IpPort::new(Host::Name("tf2.example.com".to_string()), 27015), "tf2.example.com:27015")]
+    fn test_display(ip_port: IpPort, expected: &str) {
+        assert_eq!(expected, ip_port.to_string());
+    }
+
+    #[test]
+    fn test_deserialize_ipv4() {
+        let ip_port: IpPort = serde_json::from_str(r#"{"ip":"1.2.3.4","port":27015}"#).unwrap();
+
+        assert_eq!(IpPort::new(Ipv4Addr::new(1, 2, 3, 4), 27015), ip_port);
+        assert_eq!(r#"{"ip":"1.2.3.4","port":27015}"#, serde_json::to_string(&ip_port).unwrap());
     }
 }
@@ -82,7 +82,7 @@ impl ExecutableArgument {
     pub fn format_to_string(&self, ip_port: &IpPort) -> String {
         match self {
             ExecutableArgument::Argument(argument) => argument.clone(),
-            ExecutableArgument::Server => format!("{}:{}", ip_port.ip(), ip_port.port()),
+            ExecutableArgument::Server => ip_port.to_string(),
         }
     }
 }
@@ -0,0 +1,16 @@
+use {
+    crate::application::{map::MapName, message::MapStatsMessage, Message},
+    iced::Command,
+};
+
+pub fn fetch_map_stats(client: teamwork::Client, map_name: MapName, api_key: String) -> Command<Message> {
+    let requested_map_name = map_name.clone();
+
+    Command::perform(
+        async move { client.get_map_stats(&api_key, requested_map_name.as_str()).await },
+        move |result| match result {
+            Ok(stats) => Message::MapStats(MapStatsMessage::Ready(map_name.clone(), stats.into())),
+            Err(error) => Message::MapStats(MapStatsMessage::Error(map_name.clone(), error.into())),
+        },
+    )
+}
@@ -1,24 +1,28 @@
 use {
     crate::application::{
         blacklist::{BlacklistEntry, ImportBlacklistError},
+        continent::Continent,
         filter::{
+            filter_presets::FilterPresetError,
             properties_filter::PropertyFilterSwitch,
             sort_servers::{SortCriterion, SortDirection},
         },
         game_mode::GameModeId,
-        geolocation,
+        geolocation, geolocation_database,
+        ip_port::Host,
         map::MapName,
         ping,
         servers_source::SourceKey,
-        user_settings::LauncherTheme,
-        Country, FetchServersEvent, IpPort, PingRequest, Server,
+        user_settings::{LatencyBackend, LauncherTheme},
+        worker_pool::WorkerRequest,
+        Country, FetchServersEvent, IpPort, PingRequest, PingStats, Server,
     },
     iced::{
         futures::channel::mpsc::UnboundedSender,
         widget::{image, pane_grid, scrollable::Viewport},
     },
     mods_manager::{Install, ModName, PackageEntry, Source},
-    std::{net::Ipv4Addr, path::PathBuf, sync::Arc, time::Duration},
+    std::{path::PathBuf, sync::Arc},
 };
 
 #[derive(Debug, Clone)]
@@ -26,22 +30,34 @@ pub enum FetchServersMessage {
     FetchServersStart,
     FetchServersFinish,
     FetchServersError(Arc<teamwork::Error>),
+    MasterServerError(SourceKey, Arc<source_query::Error>),
     NewServers(Vec<Server>),
+    Rejected(SourceKey, teamwork::ValidationReport),
     ServerInfoReady(Option<Server>),
+    LiveInfoReady(IpPort, source_query::Info),
+    LiveInfoError(IpPort, Arc<source_query::Error>),
 }
 
 #[derive(Debug, Clone)]
 pub enum CountryServiceMessage {
-    Started(UnboundedSender<Ipv4Addr>),
-    CountryFound(Ipv4Addr, Country),
-    Error(Ipv4Addr, geolocation::Error),
+    Started(UnboundedSender<WorkerRequest<Host>>),
+    CountryFound(Host, Country),
+    Error(Host, geolocation::Error),
+    DatabaseError(geolocation_database::Error),
 }
 
 #[derive(Debug, Clone)]
 pub enum PingServiceMessage {
-    Started(UnboundedSender<PingRequest>),
-    Answer(Ipv4Addr, Duration, bool),
-    Error(Ipv4Addr, ping::Error, bool),
+    Started(UnboundedSender<WorkerRequest<PingRequest>>),
+    Answer(PingRequest, PingStats),
+    Error(PingRequest, ping::Error),
+}
+
+#[derive(Debug, Clone)]
+pub enum RulesServiceMessage {
+    Started(UnboundedSender<WorkerRequest<IpPort>>),
+    Answer(IpPort, Arc<source_query::Rules>),
+    Error(IpPort, Arc<source_query::Error>),
 }
 
 #[derive(Debug, Clone)]
@@ -55,31 +71,36 @@ pub enum ThumbnailMessage {
     Started(UnboundedSender<MapName>),
     Thumbnail(MapName, Option<image::Handle>),
     Error(MapName, Arc<teamwork::Error>),
-    Wait,
 }
 
 #[derive(Debug, Clone)]
 pub enum FilterMessage {
     CountryChecked(Country, bool),
+    ContinentChecked(Continent, bool),
+    ContinentExpanded(Continent, bool),
     NoCountryChecked(bool),
     TextChanged(String),
     BookmarkedOnlyChecked(bool),
     IgnoreCaseChanged(bool),
     IgnoreAccentChanged(bool),
+    RegexChanged(bool),
     MaxPingChanged(u32),
     AcceptPingTimeoutChanged(bool),
     PingFilterEnabled(bool),
     GameModeChecked(GameModeId, bool),
     CountryFilterEnabled(bool),
     GameModeFilterEnabled(bool),
+    GameModeOfficialOnly(bool),
     VacSecuredChanged(PropertyFilterSwitch),
     RtdChanged(PropertyFilterSwitch),
     AllTalkChanged(PropertyFilterSwitch),
     NoRespawnTimeChanged(PropertyFilterSwitch),
     PasswordChanged(PropertyFilterSwitch),
     RandomCritsChanged(PropertyFilterSwitch),
-    SortCriterionChanged(SortCriterion),
-    SortDirectionChanged(SortDirection),
+    SortKeyAdded,
+    SortKeyRemoved(usize),
+    SortCriterionChanged(usize, SortCriterion),
+    SortDirectionChanged(usize, SortDirection),
     MinimumPlayersChanged(u8),
     MinimumFreeSlotsChanged(u8),
     PlayerFilterEnabled(bool),
@@ -88,6 +109,32 @@ pub enum FilterMessage {
     ProviderChecked(String, bool),
     ProviderFilterEnabled(bool),
     MapNameFilterChanged(String),
+    MapNameRegexChanged(bool),
+    RulesFilterEnabled(bool),
+    RuleConditionAdded,
+    RuleConditionRemoved(usize),
+    RuleNameChanged(usize, String),
+    RuleValueChanged(usize, String),
+}
+
+impl FilterMessage {
+    /// True if the message can change the servers accepted by the filter.
+    ///
+    /// The sort, the map names displayed and the expanded continents only change the view.
+    /// A new rule condition is empty, so it's ignored until it's edited.
+    pub fn changes_acceptance(&self) -> bool {
+        !matches!(
+            self,
+            FilterMessage::ContinentExpanded(..)
+                | FilterMessage::SortKeyAdded
+                | FilterMessage::SortKeyRemoved(_)
+                | FilterMessage::SortCriterionChanged(..)
+                | FilterMessage::SortDirectionChanged(..)
+                | FilterMessage::MapNameFilterChanged(_)
+                | FilterMessage::MapNameRegexChanged(_)
+                | FilterMessage::RuleConditionAdded
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -95,13 +142,21 @@ pub enum SettingsMessage {
     TeamworkApiKeyChanged(String),
     SteamExecutableChanged(String),
     SourceEnabled(SourceKey, bool),
+    SourceRegionChanged(SourceKey, source_query::Region),
+    SourceFilterChanged(SourceKey, String),
     QuitWhenLaunchChecked(bool),
     QuitWhenCopyChecked(bool),
     WindowMoved { x: i32, y: i32 },
     WindowResized { width: u32, height: u32 },
     ThemeChanged(LauncherTheme),
+    LatencyBackendChanged(LatencyBackend),
     OpenDirectory(PathBuf),
     MaxCacheSizeChanged(u64),
+    ConcurrentRequestsChanged(usize),
+    PickGeolocationDatabase,
+    GeolocationDatabasePicked(Option<PathBuf>),
+    RemoveGeolocationDatabase,
+    GeolocationHttpFallbackChecked(bool),
 }
 
 #[derive(Debug, Clone)]
@@ -129,6 +184,19 @@ pub enum ScreenshotsMessage {
     Error(Arc<teamwork::Error>),
 }
 
+#[derive(Debug, Clone)]
+pub enum MapStatsMessage {
+    Ready(MapName, Arc<teamwork::MapStats>),
+    Error(MapName, Arc<teamwork::Error>),
+}
+
+#[derive(Debug, Clone)]
+pub enum PlayersMessage {
+    Refresh,
+    Ready(IpPort, Vec<source_query::Player>),
+    Error(IpPort, Arc<source_query::Error>),
+}
+
 #[derive(Clone, Debug)]
 pub enum AddViewMessage {
     Show,
@@ -172,6 +240,22 @@ pub enum BlacklistMessage {
     ImportFailed(ImportBlacklistError),
 }
 
+#[derive(Debug, Clone)]
+pub enum FilterPresetMessage {
+    Selected(String),
+    NameChanged(String),
+    SaveAs,
+    Rename,
+    Delete,
+    Import,
+    /// The content of the imported file, `None` if no file is picked.
+    Imported(Option<String>),
+    Export,
+    /// The path of the exported file, `None` if no file is picked.
+    Exported(Option<PathBuf>),
+    Error(FilterPresetError),
+}
+
 #[derive(Debug, Clone)]
 pub enum Message {
     Servers(FetchServersMessage),
@@ -179,18 +263,24 @@ pub enum Message {
     Ping(PingServiceMessage),
     Thumbnail(ThumbnailMessage),
     Filter(FilterMessage),
+    FilterPreset(FilterPresetMessage),
     Settings(SettingsMessage),
     Pane(PaneMessage),
     GameModes(GameModesMessage),
     Keyboard(KeyboardMessage),
     Notification(NotificationMessage),
     Screenshots(ScreenshotsMessage),
+    MapStats(MapStatsMessage),
+    Players(PlayersMessage),
+    Rules(RulesServiceMessage),
     Blacklist(BlacklistMessage),
     Mods(ModsMessage),
     RefreshServers,
     RefreshServer(IpPort),
     ShowSettings,
     ShowServer(IpPort, MapName),
+    ShowMap(MapName),
+    ServerRulesExpanded(bool),
     ShowMods,
     LaunchGame(IpPort),
     CopyConnectionString(IpPort),
@@ -207,7 +297,13 @@ impl From<FetchServersEvent> for Message {
             FetchServersEvent::Start => Message::Servers(FetchServersMessage::FetchServersStart),
             FetchServersEvent::Finish => Message::Servers(FetchServersMessage::FetchServersFinish),
             FetchServersEvent::Servers(servers) => Message::Servers(FetchServersMessage::NewServers(servers)),
+            FetchServersEvent::Rejected(source_key, report) => {
+                Message::Servers(FetchServersMessage::Rejected(source_key, report))
+            }
             FetchServersEvent::Error(error) => Message::Servers(FetchServersMessage::FetchServersError(error)),
+            FetchServersEvent::MasterServerError(source_key, error) => {
+                Message::Servers(FetchServersMessage::MasterServerError(source_key, error))
+            }
         }
     }
 }
@@ -218,6 +314,12 @@ impl From<CountryServiceMessage> for Message {
     }
 }
 
+impl From<RulesServiceMessage> for Message {
+    fn from(message: RulesServiceMessage) -> Self {
+        Message::Rules(message)
+    }
+}
+
 impl From<PingServiceMessage> for Message {
     fn from(message: PingServiceMessage) -> Self {
         Message::Ping(message)
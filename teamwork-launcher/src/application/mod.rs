@@ -1,22 +1,29 @@
 pub mod blacklist;
 mod bookmarks;
+pub mod continent;
 pub mod country;
 pub mod fetch_servers;
 pub mod filter;
 pub mod game_mode;
 mod geolocation;
+pub mod geolocation_database;
 pub mod ip_port;
 mod launcher;
 pub mod map;
+mod map_stats;
 pub mod message;
 mod mods_management;
+pub mod network_cache;
 pub mod notifications;
 pub mod palettes;
 pub mod paths;
 mod ping;
+pub mod ping_stats;
+pub mod players;
 mod process_detection;
 pub mod progress;
 pub mod promised_value;
+mod rules;
 pub mod screens;
 pub mod screenshots;
 pub mod server;
@@ -24,6 +31,7 @@ pub mod servers_counts;
 pub mod servers_source;
 mod thumbnail;
 pub mod user_settings;
+pub mod worker_pool;
 
 use {
     crate::ui::{self, main::ViewContext},
@@ -39,15 +47,11 @@ use {
     },
     iced_views::Views,
     itertools::Itertools,
-    log::{debug, error, trace},
+    log::{debug, error, trace, warn},
     std::{
-        collections::{
-            btree_map::Entry::{Occupied, Vacant},
-            BTreeMap, BTreeSet,
-        },
-        net::Ipv4Addr,
+        collections::{BTreeMap, BTreeSet},
         sync::Arc,
-        time::{Duration, Instant},
+        time::{Duration, Instant, SystemTime},
     },
     teamwork::UrlWithKey,
 };
@@ -56,12 +60,14 @@ pub use {
     crate::application::user_settings::UserSettings,
     bookmarks::Bookmarks,
     country::Country,
-    fetch_servers::{fetch_servers, FetchServersEvent},
-    ip_port::IpPort,
+    fetch_servers::{fetch_servers, FetchServersEvent, SourceRequest},
+    ip_port::{Host, IpPort},
     message::{
-        BlacklistMessage, CountryServiceMessage, FetchServersMessage, FilterMessage, GameModesMessage, Message, PaneMessage,
-        PingServiceMessage, SettingsMessage, ThumbnailMessage,
+        BlacklistMessage, CountryServiceMessage, FetchServersMessage, FilterMessage, FilterPresetMessage, GameModesMessage,
+        MapStatsMessage, Message, PaneMessage, PingServiceMessage, PlayersMessage, RulesServiceMessage, SettingsMessage,
+        ThumbnailMessage,
     },
+    ping_stats::PingStats,
     promised_value::PromisedValue,
     server::Server,
 };
@@ -70,29 +76,33 @@ use {
         application::{
             blacklist::{import_blacklist, Blacklist},
             filter::{
+                filter_presets::{export_preset, import_preset, FilterPresets},
                 filter_servers::Filter,
-                sort_servers::{sort_servers, SortDirection},
+                rules_filter::RuleCondition,
+                sort_servers::SortCriterion,
             },
             game_mode::{GameModeId, GameModes},
             launcher::ExecutableLauncher,
             map::MapName,
             message::{KeyboardMessage, NotificationMessage, ScreenshotsMessage},
+            network_cache::NetworkCache,
             notifications::{Notification, NotificationKind, Notifications},
             paths::PathsProvider,
             process_detection::ProcessDetection,
             progress::Progress,
-            screens::{PaneId, PaneView},
+            screens::{MapView, PaneId, PaneView},
             screenshots::Screenshots,
-            servers_source::{ServersSource, SourceKey},
+            servers_source::{ServersSource, SourceKey, SourceKind},
             thumbnail::ThumbnailCache,
+            user_settings::LatencyBackend,
+            worker_pool::{Priority, WorkerRequest},
         },
         common_settings::{write_bin_file, write_file},
-        ui::{main::ServersList, styles::MainBackground},
+        ui::{main::ServersList, styles::MainBackground, AVAILABLE_CRITERION},
         ApplicationFlags,
     },
     mods_manager::{ModName, Registry},
     screens::{Screens, ServerView},
-    server::Property,
     servers_counts::ServersCounts,
 };
 
@@ -112,9 +122,23 @@ pub enum ViewMode {
     Compact,
 }
 
+#[derive(Debug, Clone)]
 pub struct PingRequest {
-    pub ip: Ipv4Addr,
+    pub ip_port: IpPort,
     pub sort: bool,
+    pub backend: LatencyBackend,
+}
+
+impl PingRequest {
+    /// True if the latency measured applies to the server.
+    ///
+    /// ICMP measures the latency of the host, shared by all the servers of the host.
+    pub fn applies_to(&self, ip_port: &IpPort) -> bool {
+        match self.backend {
+            LatencyBackend::Icmp => self.ip_port.host() == ip_port.host(),
+            LatencyBackend::A2s => &self.ip_port == ip_port,
+        }
+    }
 }
 
 pub struct TeamworkLauncher {
@@ -123,24 +147,33 @@ pub struct TeamworkLauncher {
     servers_counts: ServersCounts,
     user_settings: UserSettings,
     filter: Filter,
+    filter_presets: FilterPresets,
     servers_sources: Vec<ServersSource>,
+    /// The servers rejected during the last refresh, by source.
+    rejected_servers: BTreeMap<SourceKey, teamwork::ValidationReport>,
     blacklist: Blacklist,
     launcher: ExecutableLauncher,
     process_detection: ProcessDetection,
     bookmarks: Bookmarks,
     game_modes: GameModes,
     notifications: Notifications,
+    /// The Teamwork.tf errors notified since the last refresh, each error is notified once.
+    notified_teamwork_errors: BTreeSet<&'static str>,
     screenshots: Screenshots,
     servers_list: ServersList,
     mods_registry: Registry,
     selected_mod: Option<ModName>,
     paths: Box<dyn PathsProvider>,
     testing_mode_enabled: bool,
+    teamwork_client: teamwork::Client,
+    source_query_client: source_query::Client,
 
-    country_request_sender: Option<UnboundedSender<Ipv4Addr>>,
-    ping_request_sender: Option<UnboundedSender<PingRequest>>,
+    country_request_sender: Option<UnboundedSender<WorkerRequest<Host>>>,
+    ping_request_sender: Option<UnboundedSender<WorkerRequest<PingRequest>>>,
+    rules_request_sender: Option<UnboundedSender<WorkerRequest<IpPort>>>,
     map_thumbnail_request_sender: Option<UnboundedSender<MapName>>,
     thumbnails_cache: ThumbnailCache,
+    network_cache: NetworkCache,
     progress: Progress,
 
     fetch_servers_subscription_id: u64,
@@ -170,6 +203,25 @@ impl iced::Application for TeamworkLauncher {
             error!("Failed to load thumbnails cache: {}", error);
         }
 
+        let http_cache_directory = flags.paths.get_http_cache_directory();
+        let teamwork_client = flags
+            .user_settings
+            .teamwork_client
+            .create_client(http_cache_directory, flags.teamwork_fixtures)
+            .unwrap_or_else(|error| {
+                error!(
+                    "Failed to create the Teamwork.tf client, using the default settings: {}",
+                    error
+                );
+                notifications.push(Notification::new(
+                    "Invalid Teamwork.tf client settings.\nThe default settings are used.",
+                    None,
+                    NotificationKind::Error,
+                ));
+
+                teamwork::Client::default()
+            });
+
         if !flags.user_settings.has_teamwork_api_key() {
             notifications.push(Notification::new(
                 "No Teamwork.tf API key specified.\nSet your API key in the settings.",
@@ -199,7 +251,9 @@ impl iced::Application for TeamworkLauncher {
                 servers_counts: ServersCounts::default(),
                 user_settings: flags.user_settings,
                 filter: flags.filter,
+                filter_presets: flags.filter_presets,
                 servers_sources: flags.servers_sources,
+                rejected_servers: BTreeMap::new(),
                 blacklist: flags.blacklist,
                 bookmarks: flags.bookmarks,
                 launcher: ExecutableLauncher::new(false),
@@ -207,18 +261,23 @@ impl iced::Application for TeamworkLauncher {
                 game_modes: GameModes::new(),
                 country_request_sender: None,
                 ping_request_sender: None,
+                rules_request_sender: None,
                 map_thumbnail_request_sender: None,
                 fetch_servers_subscription_id: 0,
                 shift_pressed: false,
                 theme,
                 is_loading_servers: false,
                 notifications,
+                notified_teamwork_errors: BTreeSet::new(),
                 screenshots: Screenshots::new(),
                 servers_list: ServersList::new(),
                 thumbnails_cache,
+                network_cache: flags.network_cache,
                 progress: Progress::default(),
                 paths: flags.paths,
                 testing_mode_enabled: flags.testing_mode_enabled,
+                teamwork_client,
+                source_query_client: source_query::Client::default(),
                 mods_registry: flags.mods,
                 selected_mod: None,
                 is_loading_mods: false,
@@ -245,7 +304,7 @@ impl iced::Application for TeamworkLauncher {
             Message::RefreshServers => self.refresh_servers(),
             Message::RefreshServer(ip_port) => return self.refresh_server(ip_port),
             Message::Servers(message) => {
-                self.process_server_message(message);
+                return self.process_server_message(message);
             }
             Message::Mods(message) => {
                 return self.process_mods_message(message);
@@ -262,6 +321,9 @@ impl iced::Application for TeamworkLauncher {
             Message::Filter(message) => {
                 self.process_filter_message(message);
             }
+            Message::FilterPreset(message) => {
+                return self.process_filter_preset_message(message);
+            }
             Message::GameModes(message) => {
                 self.process_game_modes_message(message);
             }
@@ -272,7 +334,7 @@ impl iced::Application for TeamworkLauncher {
                 self.process_notification_message(message);
             }
             Message::Settings(settings_message) => {
-                self.process_settings_message(settings_message);
+                return self.process_settings_message(settings_message);
             }
             Message::Pane(message) => {
                 self.process_pane_message(message);
@@ -280,6 +342,15 @@ impl iced::Application for TeamworkLauncher {
             Message::Screenshots(message) => {
                 self.process_screenshots_message(message);
             }
+            Message::MapStats(message) => {
+                self.process_map_stats_message(message);
+            }
+            Message::Players(message) => {
+                return self.process_players_message(message);
+            }
+            Message::Rules(message) => {
+                self.process_rules_message(message);
+            }
             Message::Blacklist(message) => {
                 return self.process_blacklist_message(message);
             }
@@ -295,7 +366,7 @@ impl iced::Application for TeamworkLauncher {
 
                 // This is the case where the user has just pasted his API key.
                 // Instead of waiting for the user, we refresh spontaneously.
-                if !self.is_loading_servers && self.servers.is_empty() && self.user_settings.has_teamwork_api_key() {
+                if !self.is_loading_servers && self.servers.is_empty() && self.can_refresh_servers() {
                     self.refresh_servers();
                 }
 
@@ -320,9 +391,40 @@ impl iced::Application for TeamworkLauncher {
                 return self.copy_connection_string(ip_port);
             }
             Message::ShowServer(ip_port, map_name) => {
-                self.views.push(Screens::Server(ServerView::new(ip_port)));
+                self.views.push(Screens::Server(ServerView::new(ip_port.clone())));
+                // The rules of the server displayed are queried first.
+                self.request_rules(ip_port.clone(), Priority::Bookmarked);
                 self.screenshots.set(PromisedValue::Loading);
-                return screenshots::fetch_screenshot(map_name, self.user_settings.teamwork_api_key());
+                return Command::batch([
+                    screenshots::fetch_screenshot(
+                        self.teamwork_client.with_priority(teamwork::Priority::Interactive),
+                        map_name,
+                        self.user_settings.teamwork_api_key(),
+                    ),
+                    players::fetch_players(self.source_query_client.clone(), ip_port),
+                ]);
+            }
+            Message::ShowMap(map_name) => {
+                let thumbnail = self
+                    .servers
+                    .iter()
+                    .filter(|server| server.map == map_name)
+                    .find_map(|server| server.map_thumbnail.get().cloned())
+                    .or_else(|| self.thumbnails_cache.get(&map_name));
+
+                self.views
+                    .push(Screens::Map(MapView::new(map_name.clone(), thumbnail.into())));
+
+                return map_stats::fetch_map_stats(
+                    self.teamwork_client.with_priority(teamwork::Priority::Interactive),
+                    map_name,
+                    self.user_settings.teamwork_api_key(),
+                );
+            }
+            Message::ServerRulesExpanded(expanded) => {
+                if let Some(Screens::Server(view)) = self.views.current_mut() {
+                    view.rules_expanded = expanded;
+                }
             }
             Message::ServerListScroll(viewport) => {
                 self.servers_list.scroll_position = viewport.relative_offset();
@@ -353,6 +455,7 @@ impl iced::Application for TeamworkLauncher {
                         servers: &self.servers,
                         bookmarks: &self.bookmarks,
                         filter: &self.filter,
+                        filter_presets: &self.filter_presets,
                         game_modes: &self.game_modes,
                         counts: &self.servers_counts,
                         servers_list: &self.servers_list,
@@ -362,18 +465,16 @@ impl iced::Application for TeamworkLauncher {
                     })
                 }
                 Screens::Server(view) => {
-                    ui::server_details::view(
-                        &self.servers,
-                        &self.game_modes,
-                        &view.ip_port,
-                        &self.screenshots,
-                        &self.blacklist,
-                    )
+                    ui::server_details::view(&self.servers, &self.game_modes, view, &self.screenshots, &self.blacklist)
+                }
+                Screens::Map(view) => {
+                    ui::map_details::view(view, &self.game_modes)
                 }
                 Screens::Settings => {
                     ui::settings::view(
                         &self.user_settings,
                         &self.servers_sources,
+                        &self.rejected_servers,
                         &self.blacklist,
                         self.paths.get_configuration_directory(),
                     )
@@ -397,20 +498,43 @@ impl iced::Application for TeamworkLauncher {
     fn subscription(&self) -> Subscription<Self::Message> {
         use iced::futures::StreamExt;
 
-        let urls = self.get_sources_urls();
-        let server_stream = fetch_servers(urls).map(Message::from);
+        let server_stream = fetch_servers(
+            self.teamwork_client.clone(),
+            self.source_query_client.clone(),
+            self.user_settings.teamwork_client.name_normalization(),
+            self.get_sources_requests(),
+        )
+        .map(Message::from);
+        let players_subscription = match self.views.current() {
+            Some(Screens::Server(_)) => players::subscription().map(Message::Players),
+            _ => Subscription::none(),
+        };
 
         Subscription::batch([
             subscription::run_with_id(self.fetch_servers_subscription_id, server_stream),
-            geolocation::subscription().map(Message::from),
+            geolocation::subscription(
+                self.user_settings.geolocation_database.clone(),
+                self.user_settings.geolocation_http_fallback,
+            )
+            .map(Message::from),
             ping::subscription().map(Message::from),
-            thumbnail::subscription(self.fetch_servers_subscription_id, &self.user_settings.teamwork_api_key())
-                .map(Message::from),
-            game_mode::subscription(self.fetch_servers_subscription_id, &self.user_settings.teamwork_api_key())
-                .map(Message::from),
+            rules::subscription().map(Message::from),
+            thumbnail::subscription(
+                self.fetch_servers_subscription_id,
+                self.teamwork_client.clone(),
+                &self.user_settings.teamwork_api_key(),
+            )
+            .map(Message::from),
+            game_mode::subscription(
+                self.fetch_servers_subscription_id,
+                self.teamwork_client.clone(),
+                &self.user_settings.teamwork_api_key(),
+            )
+            .map(Message::from),
             keyboard::subscription().map(Message::from),
             window::subscription(),
             self.notifications.subscription().map(Message::from),
+            players_subscription,
         ])
     }
 }
@@ -419,10 +543,34 @@ impl TeamworkLauncher {
     fn new_servers(&mut self, mut new_servers: Vec<Server>) {
         new_servers.retain(|server| self.blacklist.accept(server));
 
+        // The countries found during the previous sessions are used as is,
+        // the latencies are displayed until the new pings are answered.
+        // The servers not listed by Teamwork.tf have no game mode, it's guessed from the map.
+        let now = SystemTime::now();
+
+        for server in new_servers.iter_mut() {
+            if server.game_modes.is_empty() {
+                server.game_modes = self.game_modes.guess_from_map(&server.map);
+            }
+            if let Some(country) = self.network_cache.country(server.ip_port.host(), now) {
+                server.country = PromisedValue::Ready(country.clone());
+            }
+            if let Some(stats) = self.network_cache.ping(&server.ip_port, now) {
+                server.ping = PromisedValue::Ready(stats);
+            }
+        }
+
         let countries = new_servers.iter().filter_map(|server| server.country.get()).unique().cloned();
 
         self.filter.country.dictionary.extend(countries);
+
+        for server in &new_servers {
+            self.servers_counts.add(server, &self.filter, &self.bookmarks);
+        }
+
         self.servers.extend(new_servers.into_iter());
+        self.request_filtered_rules();
+
         self.sort_servers();
     }
 
@@ -437,28 +585,137 @@ impl TeamworkLauncher {
         self.progress.increment_total();
     }
 
-    fn request_ping(&mut self, ip: Ipv4Addr, sort: bool) {
+    fn request_ping(&mut self, ip_port: IpPort, sort: bool, priority: Priority) {
+        let backend = self.user_settings.latency_backend;
         let ping_sender = self.ping_request_sender.as_mut().unwrap();
 
         ping_sender
-            .send(PingRequest { ip, sort })
+            .send(WorkerRequest::Process(PingRequest { ip_port, sort, backend }, priority))
             .unwrap_or_else(|e| error!("ping sender {}", e))
             .now_or_never();
 
         self.progress.increment_total();
     }
 
-    fn request_country(&mut self, ip: Ipv4Addr) {
+    /// Query the rules of a server, the rules are set to loading until the answer is received.
+    fn request_rules(&mut self, ip_port: IpPort, priority: Priority) {
+        let rules_sender = self.rules_request_sender.as_mut().unwrap();
+
+        rules_sender
+            .send(WorkerRequest::Process(ip_port.clone(), priority))
+            .unwrap_or_else(|e| error!("rules sender {}", e))
+            .now_or_never();
+
+        for server in self.servers.iter_mut().filter(|server| server.ip_port == ip_port) {
+            server.rules = PromisedValue::Loading;
+        }
+
+        self.progress.increment_total();
+    }
+
+    /// Query the rules of the servers never queried when the rules filter is enabled, the rules filter rejects
+    /// the servers with unknown rules.
+    ///
+    /// Must be called whenever the rules filter is enabled or servers are added.
+    fn request_filtered_rules(&mut self) {
+        if !self.filter.rules.enabled {
+            return;
+        }
+
+        // The rules filter rejects the servers with unknown rules, only the other filters tell which ones are displayed.
+        let unknown_rules: Vec<(IpPort, Priority)> = self
+            .servers
+            .iter()
+            .filter(|server| server.rules.is_none())
+            .map(|server| {
+                let priority = if self.bookmarks.is_bookmarked(&server.ip_port) {
+                    Priority::Bookmarked
+                } else if self.filter.accept_except_rules(server, &self.bookmarks) {
+                    Priority::Visible
+                } else {
+                    Priority::Hidden
+                };
+
+                (server.ip_port.clone(), priority)
+            })
+            .collect();
+
+        for (ip_port, priority) in unknown_rules {
+            self.request_rules(ip_port, priority);
+        }
+    }
+
+    fn request_country(&mut self, host: Host, priority: Priority) {
         let country_sender = self.country_request_sender.as_mut().unwrap();
 
         country_sender
-            .send(ip)
+            .send(WorkerRequest::Process(host, priority))
             .unwrap_or_else(|e| error!("country sender {}", e))
             .now_or_never();
 
         self.progress.increment_total();
     }
 
+    /// Request again the countries still loading, the requests sent to the previous country service are lost
+    /// when it is restarted.
+    fn request_pending_countries(&mut self) {
+        let mut hosts: BTreeMap<Host, Priority> = BTreeMap::new();
+
+        for server in self.servers.iter().filter(|server| server.country.is_loading()) {
+            let priority = self.server_priority(server);
+
+            hosts
+                .entry(server.ip_port.host().clone())
+                .and_modify(|host_priority| *host_priority = priority.max(*host_priority))
+                .or_insert(priority);
+        }
+
+        let country_sender = self.country_request_sender.as_mut().unwrap();
+
+        // The progress already counts these requests.
+        for (host, priority) in hosts {
+            country_sender
+                .send(WorkerRequest::Process(host, priority))
+                .unwrap_or_else(|e| error!("country sender {}", e))
+                .now_or_never();
+        }
+    }
+
+    /// The priority of the requests about a server: the bookmarked servers first, then the servers displayed.
+    fn server_priority(&self, server: &Server) -> Priority {
+        if self.bookmarks.is_bookmarked(&server.ip_port) {
+            Priority::Bookmarked
+        } else if self.filter.accept(server, &self.bookmarks) {
+            Priority::Visible
+        } else {
+            Priority::Hidden
+        }
+    }
+
+    /// Change the count of requests processed at the same time by the ping, the geolocation and the rules services.
+    fn set_concurrent_requests(&mut self, concurrency: usize) {
+        if let Some(ping_sender) = self.ping_request_sender.as_mut() {
+            ping_sender
+                .send(WorkerRequest::SetConcurrency(concurrency))
+                .unwrap_or_else(|e| error!("ping sender {}", e))
+                .now_or_never();
+        }
+
+        if let Some(country_sender) = self.country_request_sender.as_mut() {
+            country_sender
+                .send(WorkerRequest::SetConcurrency(concurrency))
+                .unwrap_or_else(|e| error!("country sender {}", e))
+                .now_or_never();
+        }
+
+        if let Some(rules_sender) = self.rules_request_sender.as_mut() {
+            rules_sender
+                .send(WorkerRequest::SetConcurrency(concurrency))
+                .unwrap_or_else(|e| error!("rules sender {}", e))
+                .now_or_never();
+        }
+    }
+
     /// Update the information about a specific server, identified by the IP/port.
     ///
     /// Notice I do not update:
@@ -467,6 +724,9 @@ impl TeamworkLauncher {
     /// refreshing only one server.
     fn update_server(&mut self, server: Server) {
         if let Some(index) = self.servers.iter().position(|s| s.ip_port == server.ip_port) {
+            self.servers_counts
+                .remove(&self.servers[index], &self.filter, &self.bookmarks);
+
             if self.servers[index].map != server.map {
                 self.servers[index].map = server.map;
                 self.servers[index].map_thumbnail = PromisedValue::Loading;
@@ -474,7 +734,7 @@ impl TeamworkLauncher {
                 self.request_map_thumbnail(self.servers[index].map.clone());
             }
 
-            self.request_ping(server.ip_port.ip().clone(), false);
+            self.request_ping(server.ip_port.clone(), false, self.server_priority(&self.servers[index]));
 
             self.servers[index].max_players_count = server.max_players_count;
             self.servers[index].current_players_count = server.current_players_count;
@@ -488,6 +748,38 @@ impl TeamworkLauncher {
             self.servers[index].has_all_talk = server.has_all_talk;
             self.servers[index].has_random_crits = server.has_random_crits;
             self.servers[index].need_password = server.need_password;
+            self.servers_counts.add(&self.servers[index], &self.filter, &self.bookmarks);
+        }
+    }
+
+    fn live_info_ready(&mut self, ip_port: IpPort, info: source_query::Info) {
+        let name_normalization = self.user_settings.teamwork_client.name_normalization();
+
+        if let Some(index) = self.servers.iter().position(|s| s.ip_port == ip_port) {
+            let map = MapName::new(&info.map);
+
+            self.servers_counts
+                .remove(&self.servers[index], &self.filter, &self.bookmarks);
+
+            if self.servers[index].map != map {
+                self.servers[index].map = map;
+                self.servers[index].map_thumbnail = PromisedValue::Loading;
+
+                self.request_map_thumbnail(self.servers[index].map.clone());
+            }
+
+            self.request_ping(ip_port.clone(), false, self.server_priority(&self.servers[index]));
+
+            let server = &mut self.servers[index];
+
+            server.name = name_normalization.normalize(&info.name);
+            server.raw_name = info.name;
+            server.current_players_count = info.players;
+            server.max_players_count = info.max_players;
+            server.ping = PromisedValue::Loading;
+            server.vac_secured = info.vac;
+            server.need_password = info.password;
+            self.servers_counts.add(server, &self.filter, &self.bookmarks);
         }
     }
 
@@ -523,35 +815,44 @@ impl TeamworkLauncher {
             unique_map_names.insert(server.map.clone());
         }
 
-        let unique_ips: BTreeSet<Ipv4Addr> =
-            BTreeSet::from_iter(servers_refs.iter().map(|server| server.ip_port.ip()).cloned());
+        // A host is as important as the most important of its servers.
+        let mut hosts: BTreeMap<Host, (IpPort, Priority)> = BTreeMap::new();
+        let mut ping_targets: Vec<(IpPort, Priority)> = Vec::new();
+
+        for server in servers_refs.iter() {
+            let priority = self.server_priority(server);
+
+            hosts
+                .entry(server.ip_port.host().clone())
+                .and_modify(|(_, host_priority)| *host_priority = priority.max(*host_priority))
+                .or_insert_with(|| (server.ip_port.clone(), priority));
+
+            if self.user_settings.latency_backend == LatencyBackend::A2s {
+                ping_targets.push((server.ip_port.clone(), priority));
+            }
+        }
+
+        if self.user_settings.latency_backend == LatencyBackend::Icmp {
+            ping_targets.extend(hosts.values().cloned());
+        }
 
         drop(servers_refs);
 
-        for ip in unique_ips.iter() {
-            self.request_country(*ip);
-            self.request_ping(*ip, true);
+        // The countries found in the cache are not requested again.
+        let now = SystemTime::now();
+
+        for (host, (_, priority)) in hosts.into_iter() {
+            if self.network_cache.country(&host, now).is_none() {
+                self.request_country(host, priority);
+            }
+        }
+        for (ip_port, priority) in ping_targets.into_iter() {
+            self.request_ping(ip_port, true, priority);
         }
         for map in unique_map_names.iter().cloned() {
             self.request_map_thumbnail(map);
         }
 
-        // Update counts
-        self.servers_counts.bookmarks =
-            self.servers
-                .iter()
-                .fold(0usize, |count, server| match self.bookmarks.is_bookmarked(&server.ip_port) {
-                    true => count + 1,
-                    false => count,
-                });
-        self.servers_counts.countries =
-            Self::histogram(self.servers.iter().filter_map(|server| server.country.get()).cloned());
-        self.servers_counts.properties = Self::count_properties(&self.servers);
-        self.servers_counts.game_modes = Self::histogram(self.servers.iter().flat_map(|server| server.game_modes.clone()));
-        self.servers_counts.timeouts = self.servers.iter().filter(|server| server.ping.is_none()).count();
-        self.servers_counts.maps = Self::histogram(self.servers.iter().map(|server| server.map.clone()));
-        self.servers_counts.providers = Self::histogram(self.servers.iter().map(|server| server.provider.clone()));
-
         // Update filters
         self.filter
             .providers
@@ -575,10 +876,22 @@ impl TeamworkLauncher {
         for map_name in self.servers.iter().map(|server| &server.map) {
             self.filter.maps.dictionary.add(map_name.clone());
         }
+
+        // The new maps and providers are checked, the servers are counted again.
+        self.recount_servers();
+    }
+
+    fn recount_servers(&mut self) {
+        self.servers_counts.recount(&self.servers, &self.filter, &self.bookmarks);
+    }
+
+    /// The servers can be refreshed without API key if a source does not use Teamwork.tf.
+    fn can_refresh_servers(&self) -> bool {
+        self.user_settings.has_teamwork_api_key() || !self.get_sources_requests().is_empty()
     }
 
     fn refresh_servers(&mut self) {
-        if !self.user_settings.has_teamwork_api_key() {
+        if !self.can_refresh_servers() {
             self.push_notification(
                 "No Teamwork.tf API key specified.\nSet your API key in the settings.",
                 NotificationKind::Error,
@@ -588,13 +901,37 @@ impl TeamworkLauncher {
             self.progress.reset();
             self.servers_counts.reset();
             self.servers.clear();
+            self.rejected_servers.clear();
+            self.notified_teamwork_errors.clear();
             self.filter.players.maximum_free_slots = 0;
             self.filter.players.maximum_players = 0;
             self.fetch_servers_subscription_id += 1;
         }
     }
 
+    /// Query the server directly, this does not use the Teamwork.tf API quota.
+    ///
+    /// If the server does not answer, Teamwork.tf is used instead.
     fn refresh_server(&mut self, ip_port: IpPort) -> Command<<TeamworkLauncher as iced::Application>::Message> {
+        let client = self.source_query_client.clone();
+
+        Command::perform(
+            async move {
+                let result = client.info(ip_port.to_string()).await;
+
+                (ip_port, result)
+            },
+            |(ip_port, result)| match result {
+                Ok(info) => Message::Servers(FetchServersMessage::LiveInfoReady(ip_port, info)),
+                Err(error) => Message::Servers(FetchServersMessage::LiveInfoError(ip_port, Arc::new(error))),
+            },
+        )
+    }
+
+    fn refresh_server_from_teamwork(
+        &mut self,
+        ip_port: IpPort,
+    ) -> Command<<TeamworkLauncher as iced::Application>::Message> {
         if !self.user_settings.has_teamwork_api_key() {
             self.push_notification(
                 "No Teamwork.tf API key specified.\nSet your API key in the settings.",
@@ -605,9 +942,10 @@ impl TeamworkLauncher {
         }
 
         let api_key = self.user_settings.teamwork_api_key();
+        let client = self.teamwork_client.with_priority(teamwork::Priority::Interactive);
 
         Command::perform(
-            Self::fetch_server(ip_port.ip().clone(), ip_port.port(), api_key),
+            Self::fetch_server(client, ip_port.host().clone(), ip_port.port(), api_key),
             |result| match result {
                 Ok(server) => Message::Servers(FetchServersMessage::ServerInfoReady(server)),
                 Err(error) => Message::Servers(FetchServersMessage::FetchServersError(Arc::new(error))),
@@ -615,24 +953,56 @@ impl TeamworkLauncher {
         )
     }
 
-    async fn fetch_server(ip: Ipv4Addr, port: u16, api_key: String) -> Result<Option<Server>, teamwork::Error> {
-        let client = teamwork::Client::default();
-
+    async fn fetch_server(
+        client: teamwork::Client,
+        host: Host,
+        port: u16,
+        api_key: String,
+    ) -> Result<Option<Server>, teamwork::Error> {
         Ok(client
-            .get_server(ip, port, &api_key)
+            .get_server(&host.to_string(), port, &api_key)
             .await?
-            .map(std::convert::Into::<Server>::into))
+            .and_then(|server| {
+                Server::try_from(server)
+                    .map_err(|error| warn!("Server skipped: {}", error))
+                    .ok()
+            }))
     }
 
-    fn country_found(&mut self, ip: Ipv4Addr, country: Option<Country>) {
-        for server in self.servers.iter_mut().filter(|server| server.ip_port.ip() == &ip) {
-            server.country = country.clone().into();
-        }
+    fn source_name(&self, source_key: &SourceKey) -> Option<String> {
+        self.servers_sources
+            .iter()
+            .find(|source| source.key() == source_key)
+            .map(|source| source.display_name().to_string())
+    }
 
-        if let Some(country) = country {
+    fn servers_rejected(&mut self, source_key: SourceKey, report: teamwork::ValidationReport) {
+        let source_name = self.source_name(&source_key).unwrap_or_else(|| report.address.clone());
+
+        warn!("Invalid servers from source '{}': {}", source_name, report);
+
+        self.push_notification(
+            format!(
+                "{} invalid servers from the source '{}' were ignored.\nSee the sources in the settings for details.",
+                report.rejected.len(),
+                source_name
+            ),
+            NotificationKind::Error,
+        );
+        self.rejected_servers.insert(source_key, report);
+    }
+
+    fn country_found(&mut self, host: Host, country: Option<Country>) {
+        if let Some(country) = country.as_ref() {
+            self.network_cache
+                .insert_country(host.clone(), country.clone(), SystemTime::now());
             self.filter.country.dictionary.add(country.clone());
+        }
 
-            self.servers_counts.add_country(country);
+        for server in self.servers.iter_mut().filter(|server| server.ip_port.host() == &host) {
+            self.servers_counts.modify(server, &self.filter, &self.bookmarks, |server| {
+                server.country = country.clone().into();
+            });
         }
 
         self.sort_servers();
@@ -640,22 +1010,35 @@ impl TeamworkLauncher {
         self.progress.increment_current();
     }
 
-    fn ping_found(&mut self, ip: Ipv4Addr, duration: Option<Duration>, sort_servers: bool) {
-        for server in self.servers.iter_mut().filter(|server| server.ip_port.ip() == &ip) {
-            if duration.is_none() {
-                self.servers_counts.timeouts += 1;
+    fn ping_found(&mut self, request: PingRequest, stats: Option<PingStats>) {
+        for server in self.servers.iter_mut().filter(|server| request.applies_to(&server.ip_port)) {
+            if let Some(stats) = stats {
+                self.network_cache
+                    .insert_ping(server.ip_port.clone(), stats, SystemTime::now());
             }
 
-            server.ping = duration.into();
+            self.servers_counts.modify(server, &self.filter, &self.bookmarks, |server| {
+                server.ping = stats.into();
+            });
         }
 
-        if sort_servers {
+        if request.sort {
             self.sort_servers();
         }
 
         self.progress.increment_current();
     }
 
+    fn rules_found(&mut self, ip_port: IpPort, rules: Option<Arc<source_query::Rules>>) {
+        for server in self.servers.iter_mut().filter(|server| server.ip_port == ip_port) {
+            self.servers_counts.modify(server, &self.filter, &self.bookmarks, |server| {
+                server.rules = rules.clone().into();
+            });
+        }
+
+        self.progress.increment_current();
+    }
+
     fn thumbnail_ready(&mut self, map_name: MapName, thumbnail: Option<image::Handle>) {
         if let Some(image) = thumbnail.as_ref() {
             self.thumbnails_cache.insert(map_name.clone(), image.clone());
@@ -670,15 +1053,9 @@ impl TeamworkLauncher {
         self.progress.increment_current();
     }
 
+    /// The sort is stable, the servers equal for every key keep their order.
     fn sort_servers(&mut self) {
-        match self.filter.sort_direction {
-            SortDirection::Ascending => {
-                self.servers.sort_by(|l, r| sort_servers(self.filter.sort_criterion, l, r));
-            }
-            SortDirection::Descending => {
-                self.servers.sort_by(|l, r| sort_servers(self.filter.sort_criterion, r, l));
-            }
-        }
+        self.servers.sort_by(|l, r| self.filter.sort.compare(&self.bookmarks, l, r));
     }
 
     fn require_compact_mode(&self, ratio: f32) -> ViewMode {
@@ -739,7 +1116,7 @@ impl TeamworkLauncher {
         }
     }
 
-    fn process_settings_message(&mut self, message: SettingsMessage) {
+    fn process_settings_message(&mut self, message: SettingsMessage) -> Command<Message> {
         match message {
             SettingsMessage::TeamworkApiKeyChanged(key) => {
                 self.user_settings.set_teamwork_api_key(key);
@@ -752,6 +1129,20 @@ impl TeamworkLauncher {
                     source.set_enabled(enabled);
                 }
             }
+            SettingsMessage::SourceRegionChanged(source_key, new_region) => {
+                if let Some(source) = self.servers_sources.iter_mut().find(|source| source.key() == &source_key) {
+                    if let SourceKind::MasterServer { region, .. } = source.kind_mut() {
+                        *region = new_region;
+                    }
+                }
+            }
+            SettingsMessage::SourceFilterChanged(source_key, new_filter) => {
+                if let Some(source) = self.servers_sources.iter_mut().find(|source| source.key() == &source_key) {
+                    if let SourceKind::MasterServer { filter, .. } = source.kind_mut() {
+                        *filter = new_filter;
+                    }
+                }
+            }
             SettingsMessage::QuitWhenLaunchChecked(checked) => {
                 self.user_settings.quit_on_launch = checked;
             }
@@ -780,6 +1171,9 @@ impl TeamworkLauncher {
                 self.user_settings.theme = theme;
                 self.theme = theme.into();
             }
+            SettingsMessage::LatencyBackendChanged(backend) => {
+                self.user_settings.latency_backend = backend;
+            }
             SettingsMessage::OpenDirectory(directory) => {
                 if directory.is_dir() {
                     if let Err(error) = open::that(&directory) {
@@ -793,10 +1187,34 @@ impl TeamworkLauncher {
             SettingsMessage::MaxCacheSizeChanged(value) => {
                 self.user_settings.max_thumbnails_cache_size_mb = value;
             }
+            SettingsMessage::ConcurrentRequestsChanged(value) => {
+                self.user_settings.concurrent_requests = value.max(1);
+                self.set_concurrent_requests(self.user_settings.concurrent_requests);
+            }
+            SettingsMessage::PickGeolocationDatabase => {
+                return Command::perform(geolocation_database::pick_database_file(), |path| {
+                    Message::Settings(SettingsMessage::GeolocationDatabasePicked(path))
+                });
+            }
+            SettingsMessage::GeolocationDatabasePicked(path) => {
+                if path.is_some() {
+                    self.user_settings.geolocation_database = path;
+                }
+            }
+            SettingsMessage::RemoveGeolocationDatabase => {
+                self.user_settings.geolocation_database = None;
+            }
+            SettingsMessage::GeolocationHttpFallbackChecked(checked) => {
+                self.user_settings.geolocation_http_fallback = checked;
+            }
         }
+
+        Command::none()
     }
 
     fn process_filter_message(&mut self, message: FilterMessage) {
+        let changes_acceptance = message.changes_acceptance();
+
         match message {
             FilterMessage::CountryChecked(country, checked) => {
                 if self.shift_pressed {
@@ -808,6 +1226,12 @@ impl TeamworkLauncher {
                     self.filter.country.dictionary.set_checked(&country, checked);
                 }
             }
+            FilterMessage::ContinentChecked(continent, checked) => {
+                self.filter.country.set_continent_checked(continent, checked);
+            }
+            FilterMessage::ContinentExpanded(continent, expanded) => {
+                self.filter.country.set_continent_expanded(continent, expanded);
+            }
             FilterMessage::NoCountryChecked(checked) => {
                 self.filter.country.no_countries = checked;
             }
@@ -818,10 +1242,13 @@ impl TeamworkLauncher {
                 self.filter.bookmarked_only = checked;
             }
             FilterMessage::IgnoreCaseChanged(checked) => {
-                self.filter.text.ignore_case = checked;
+                self.filter.text.set_ignore_case(checked);
             }
             FilterMessage::IgnoreAccentChanged(checked) => {
-                self.filter.text.ignore_accents = checked;
+                self.filter.text.set_ignore_accents(checked);
+            }
+            FilterMessage::RegexChanged(checked) => {
+                self.filter.text.set_regex(checked);
             }
             FilterMessage::MaxPingChanged(max_ping) => {
                 self.filter.ping.max_ping = max_ping;
@@ -848,6 +1275,14 @@ impl TeamworkLauncher {
             FilterMessage::GameModeFilterEnabled(checked) => {
                 self.filter.game_modes.enabled = checked;
             }
+            FilterMessage::GameModeOfficialOnly(checked) => {
+                self.filter.game_modes.official_only = checked;
+
+                // One click must be enough to see only the official game modes.
+                if checked {
+                    self.filter.game_modes.enabled = true;
+                }
+            }
             FilterMessage::VacSecuredChanged(checked) => {
                 self.filter.vac_secured = checked;
             }
@@ -866,12 +1301,20 @@ impl TeamworkLauncher {
             FilterMessage::RandomCritsChanged(checked) => {
                 self.filter.random_crits = checked;
             }
-            FilterMessage::SortCriterionChanged(criterion) => {
-                self.filter.sort_criterion = criterion;
+            FilterMessage::SortKeyAdded => {
+                self.filter.sort.add(&AVAILABLE_CRITERION);
+                self.sort_servers();
+            }
+            FilterMessage::SortKeyRemoved(index) => {
+                self.filter.sort.remove(index);
+                self.sort_servers();
+            }
+            FilterMessage::SortCriterionChanged(index, criterion) => {
+                self.filter.sort.set_criterion(index, criterion);
                 self.sort_servers();
             }
-            FilterMessage::SortDirectionChanged(direction) => {
-                self.filter.sort_direction = direction;
+            FilterMessage::SortDirectionChanged(index, direction) => {
+                self.filter.sort.set_direction(index, direction);
                 self.sort_servers();
             }
             FilterMessage::MinimumPlayersChanged(value) => {
@@ -899,13 +1342,43 @@ impl TeamworkLauncher {
             FilterMessage::ProviderFilterEnabled(enabled) => {
                 self.filter.providers.enabled = enabled;
             }
+            FilterMessage::RulesFilterEnabled(enabled) => {
+                self.filter.rules.enabled = enabled;
+                self.request_filtered_rules();
+            }
+            FilterMessage::RuleConditionAdded => {
+                self.filter.rules.conditions.push(RuleCondition::default());
+            }
+            FilterMessage::RuleConditionRemoved(index) => {
+                if index < self.filter.rules.conditions.len() {
+                    self.filter.rules.conditions.remove(index);
+                }
+            }
+            FilterMessage::RuleNameChanged(index, name) => {
+                if let Some(condition) = self.filter.rules.conditions.get_mut(index) {
+                    condition.name = name;
+                }
+            }
+            FilterMessage::RuleValueChanged(index, value) => {
+                if let Some(condition) = self.filter.rules.conditions.get_mut(index) {
+                    condition.value = value;
+                }
+            }
             FilterMessage::MapNameFilterChanged(text) => {
-                self.filter.maps.text = text;
+                self.filter.maps.set_text(&text);
+            }
+            FilterMessage::MapNameRegexChanged(checked) => {
+                self.filter.maps.set_regex(checked);
             }
             FilterMessage::PlayerFilterEnabled(enabled) => {
                 self.filter.players.enabled = enabled;
             }
         }
+
+        // Each count depends on the other filters.
+        if changes_acceptance {
+            self.recount_servers();
+        }
     }
 
     fn process_thumbnail_message(&mut self, message: ThumbnailMessage) {
@@ -924,7 +1397,6 @@ impl TeamworkLauncher {
                     Self::obfuscate_api_key(&self.user_settings.teamwork_api_key(), error)
                 );
             }
-            ThumbnailMessage::Wait => {}
         }
     }
 
@@ -932,14 +1404,32 @@ impl TeamworkLauncher {
         match message {
             PingServiceMessage::Started(sender) => {
                 self.ping_request_sender = Some(sender);
+                self.set_concurrent_requests(self.user_settings.concurrent_requests);
                 debug!("Ping service started");
             }
-            PingServiceMessage::Answer(ip, duration, sort) => {
-                self.ping_found(ip, Some(duration), sort);
+            PingServiceMessage::Answer(request, stats) => {
+                self.ping_found(request, Some(stats));
             }
-            PingServiceMessage::Error(ip, error, sort) => {
+            PingServiceMessage::Error(request, error) => {
                 error!("Ping service error: {}", error);
-                self.ping_found(ip, None, sort);
+                self.ping_found(request, None);
+            }
+        }
+    }
+
+    fn process_rules_message(&mut self, message: RulesServiceMessage) {
+        match message {
+            RulesServiceMessage::Started(sender) => {
+                self.rules_request_sender = Some(sender);
+                self.set_concurrent_requests(self.user_settings.concurrent_requests);
+                debug!("Rules service started");
+            }
+            RulesServiceMessage::Answer(ip_port, rules) => {
+                self.rules_found(ip_port, Some(rules));
+            }
+            RulesServiceMessage::Error(ip_port, error) => {
+                warn!("Failed to query the rules of {}: {}", ip_port, error);
+                self.rules_found(ip_port, None);
             }
         }
     }
@@ -947,20 +1437,33 @@ impl TeamworkLauncher {
     fn process_country_message(&mut self, message: CountryServiceMessage) {
         match message {
             CountryServiceMessage::Started(country_sender) => {
-                self.country_request_sender = Some(country_sender);
+                let restarted = self.country_request_sender.replace(country_sender).is_some();
+
+                self.set_concurrent_requests(self.user_settings.concurrent_requests);
                 debug!("country service started");
+
+                if restarted {
+                    self.request_pending_countries();
+                }
             }
-            CountryServiceMessage::CountryFound(ip, country) => {
-                self.country_found(ip, Some(country));
+            CountryServiceMessage::CountryFound(host, country) => {
+                self.country_found(host, Some(country));
             }
-            CountryServiceMessage::Error(ip, error) => {
+            CountryServiceMessage::Error(host, error) => {
                 error!("Country service error: {}", error);
-                self.country_found(ip, None);
+                self.country_found(host, None);
+            }
+            CountryServiceMessage::DatabaseError(error) => {
+                error!("Geolocation database error: {}", error);
+                self.push_notification(
+                    format!("Failed to open the geolocation database:\n{}", error),
+                    NotificationKind::Error,
+                );
             }
         }
     }
 
-    fn process_server_message(&mut self, message: FetchServersMessage) {
+    fn process_server_message(&mut self, message: FetchServersMessage) -> Command<Message> {
         match message {
             FetchServersMessage::FetchServersStart => {
                 debug!("Start");
@@ -970,30 +1473,62 @@ impl TeamworkLauncher {
                 self.on_finish();
             }
             FetchServersMessage::FetchServersError(error) => {
+                self.notify_teamwork_error(&error);
                 error!(
                     "Error: {}",
                     Self::obfuscate_api_key(&self.user_settings.teamwork_api_key(), error)
                 );
             }
+            FetchServersMessage::MasterServerError(source_key, error) => {
+                let source_name = self.source_name(&source_key).unwrap_or_default();
+
+                warn!("Failed to query the master server of the source '{}': {}", source_name, error);
+
+                self.push_notification(
+                    format!(
+                        "The master server of the source '{}' is not reachable.\nPlease retry later.",
+                        source_name
+                    ),
+                    NotificationKind::Error,
+                );
+            }
             FetchServersMessage::NewServers(new_servers) => self.new_servers(new_servers),
+            FetchServersMessage::Rejected(source_key, report) => self.servers_rejected(source_key, report),
             FetchServersMessage::ServerInfoReady(server) => {
                 if let Some(server) = server {
                     self.update_server(server);
                 }
             }
+            FetchServersMessage::LiveInfoReady(ip_port, info) => self.live_info_ready(ip_port, info),
+            FetchServersMessage::LiveInfoError(ip_port, error) => {
+                warn!("Failed to query the server {}: {}", ip_port, error);
+
+                return self.refresh_server_from_teamwork(ip_port);
+            }
         }
+
+        Command::none()
     }
 
     fn process_game_modes_message(&mut self, message: GameModesMessage) {
         match message {
             GameModesMessage::GameModes(game_modes) => {
                 self.game_modes.reset(&game_modes);
+
+                // The servers received before the game modes have no game mode guessed yet.
+                for server in self.servers.iter_mut().filter(|server| server.game_modes.is_empty()) {
+                    server.game_modes = self.game_modes.guess_from_map(&server.map);
+                }
+
+                self.filter.game_modes.official_modes = self.game_modes.official_ids().cloned().collect();
                 self.filter
                     .game_modes
                     .dictionary
                     .extend(game_modes.into_iter().map(|mode| GameModeId::new(mode.id)));
+                self.recount_servers();
             }
             GameModesMessage::Error(error) => {
+                self.notify_teamwork_error(&error);
                 self.push_notification(
                     "Failed to fetch game modes.\nFiltering by game modes is disabled.",
                     NotificationKind::Error,
@@ -1034,6 +1569,7 @@ impl TeamworkLauncher {
                 self.screenshots.set(PromisedValue::Ready(screenshots));
             }
             ScreenshotsMessage::Error(error) => {
+                self.notify_teamwork_error(&error);
                 error!(
                     "Screenshots fetch failed: {}",
                     Self::obfuscate_api_key(&self.user_settings.teamwork_api_key(), error)
@@ -1048,6 +1584,59 @@ impl TeamworkLauncher {
         }
     }
 
+    fn process_map_stats_message(&mut self, message: MapStatsMessage) {
+        match message {
+            MapStatsMessage::Ready(map_name, stats) => {
+                if let Some(Screens::Map(view)) = self.views.current_mut() {
+                    if view.map_name == map_name {
+                        view.set_stats(stats);
+                    }
+                }
+            }
+            MapStatsMessage::Error(map_name, error) => {
+                let text = Self::obfuscate_api_key(&self.user_settings.teamwork_api_key(), &error);
+
+                self.notify_teamwork_error(&error);
+                error!("Map stats fetch failed for '{}': {}", map_name, text);
+
+                if let Some(Screens::Map(view)) = self.views.current_mut() {
+                    if view.map_name == map_name {
+                        view.error = Some(text);
+                    }
+                }
+            }
+        }
+    }
+
+    fn process_players_message(&mut self, message: PlayersMessage) -> Command<Message> {
+        match message {
+            PlayersMessage::Refresh => {
+                if let Some(Screens::Server(view)) = self.views.current() {
+                    return players::fetch_players(self.source_query_client.clone(), view.ip_port.clone());
+                }
+            }
+            PlayersMessage::Ready(ip_port, players) => {
+                if let Some(Screens::Server(view)) = self.views.current_mut() {
+                    if view.ip_port == ip_port {
+                        view.players = PromisedValue::Ready(players);
+                        view.players_error = None;
+                    }
+                }
+            }
+            PlayersMessage::Error(ip_port, error) => {
+                warn!("Failed to query the players of {}: {}", ip_port, error);
+
+                if let Some(Screens::Server(view)) = self.views.current_mut() {
+                    if view.ip_port == ip_port {
+                        view.players_error = Some(error.to_string());
+                    }
+                }
+            }
+        }
+
+        Command::none()
+    }
+
     fn process_blacklist_message(&mut self, message: BlacklistMessage) -> Command<Message> {
         match message {
             BlacklistMessage::Add(entry) => {
@@ -1078,28 +1667,123 @@ impl TeamworkLauncher {
         Command::none()
     }
 
-    /// Get the list of URLS to get the servers information.
+    fn process_filter_preset_message(&mut self, message: FilterPresetMessage) -> Command<Message> {
+        let result = match message {
+            FilterPresetMessage::Selected(name) => {
+                if let Some(filter) = self.filter_presets.select(&name) {
+                    self.apply_filter(filter);
+                }
+                Ok(())
+            }
+            FilterPresetMessage::NameChanged(name) => {
+                self.filter_presets.name = name;
+                Ok(())
+            }
+            FilterPresetMessage::SaveAs => self.filter_presets.save_as(&self.filter),
+            FilterPresetMessage::Rename => self.filter_presets.rename(),
+            FilterPresetMessage::Delete => self.filter_presets.delete(),
+            FilterPresetMessage::Import => {
+                return Command::perform(import_preset(), |result| match result {
+                    Ok(json) => Message::FilterPreset(FilterPresetMessage::Imported(json)),
+                    Err(error) => Message::FilterPreset(FilterPresetMessage::Error(error)),
+                })
+            }
+            FilterPresetMessage::Imported(None) => Ok(()),
+            FilterPresetMessage::Imported(Some(json)) => self.filter_presets.import(&json).map(|name| {
+                if let Some(filter) = self.filter_presets.select(&name) {
+                    self.apply_filter(filter);
+                }
+            }),
+            FilterPresetMessage::Export => match self.filter_presets.export() {
+                Ok((name, json)) => {
+                    return Command::perform(export_preset(name, json), |result| match result {
+                        Ok(path) => Message::FilterPreset(FilterPresetMessage::Exported(path)),
+                        Err(error) => Message::FilterPreset(FilterPresetMessage::Error(error)),
+                    })
+                }
+                Err(error) => Err(error),
+            },
+            FilterPresetMessage::Exported(path) => {
+                if let Some(path) = path {
+                    self.push_notification(format!("Preset exported to {}", path.display()), NotificationKind::Feedback);
+                }
+                Ok(())
+            }
+            FilterPresetMessage::Error(error) => Err(error),
+        };
+
+        if let Err(error) = result {
+            self.push_notification(error, NotificationKind::Error);
+        }
+
+        Command::none()
+    }
+
+    /// Replace the filter by a preset, the values found in the current servers are kept in the dictionaries.
+    fn apply_filter(&mut self, mut filter: Filter) {
+        filter.players.maximum_players = self.filter.players.maximum_players;
+        filter.players.maximum_free_slots = self.filter.players.maximum_free_slots;
+        filter.game_modes.official_modes = self.filter.game_modes.official_modes.clone();
+        filter
+            .game_modes
+            .dictionary
+            .extend(self.filter.game_modes.dictionary.iter().map(|(id, _)| id.clone()));
+        filter
+            .country
+            .dictionary
+            .extend(self.servers.iter().filter_map(|server| server.country.get()).cloned());
+        filter
+            .maps
+            .dictionary
+            .extend(self.servers.iter().map(|server| server.map.clone()));
+        filter
+            .providers
+            .dictionary
+            .extend(self.servers.iter().map(|server| server.provider.clone()));
+
+        self.filter = filter;
+        self.request_filtered_rules();
+
+        self.sort_servers();
+        self.recount_servers();
+    }
+
+    /// Get the requests to send to get the servers of the enabled sources.
     ///
-    /// The order is specified by the bookmarks. The rule is
-    /// the source with the greater count of servers goes first.
-    fn get_sources_urls(&self) -> Vec<(SourceKey, UrlWithKey)> {
-        let urls = self
-            .servers_sources
+    /// The Teamwork.tf sources are skipped if there is no API key.
+    fn get_sources_requests(&self) -> Vec<(SourceKey, SourceRequest)> {
+        let has_teamwork_api_key = self.user_settings.has_teamwork_api_key();
+
+        self.servers_sources
             .iter()
-            .filter_map(|source| match source.enabled() {
-                true => Some((
-                    source.key().clone(),
-                    UrlWithKey::new(source.url(), &self.user_settings.teamwork_api_key()),
-                )),
-                false => None,
+            .filter(|source| source.enabled())
+            .filter_map(|source| {
+                let request = match source.kind() {
+                    SourceKind::Teamwork if !has_teamwork_api_key => return None,
+                    SourceKind::Teamwork => {
+                        SourceRequest::Teamwork(UrlWithKey::new(source.url(), &self.user_settings.teamwork_api_key()))
+                    }
+                    SourceKind::MasterServer { region, filter } => SourceRequest::MasterServer {
+                        address: source.url().to_string(),
+                        region: *region,
+                        filter: filter.clone(),
+                    },
+                };
+
+                Some((source.key().clone(), request))
             })
-            .collect();
-
-        urls
+            .collect()
     }
 
     #[allow(clippy::map_flatten)]
     fn bookmark(&mut self, ip_port: IpPort, bookmarked: bool) {
+        let index = self.servers.iter().position(|server| server.ip_port == ip_port);
+
+        if let Some(index) = index {
+            self.servers_counts
+                .remove(&self.servers[index], &self.filter, &self.bookmarks);
+        }
+
         match bookmarked {
             true => {
                 if let Some(source_key) = self
@@ -1110,7 +1794,6 @@ impl TeamworkLauncher {
                     .flatten()
                 {
                     self.bookmarks.add(ip_port, source_key);
-                    self.servers_counts.bookmarks += 1;
                 }
             }
             false => {
@@ -1122,10 +1805,17 @@ impl TeamworkLauncher {
                     .flatten()
                 {
                     self.bookmarks.remove(&ip_port, source_key);
-                    self.servers_counts.bookmarks -= 1;
                 }
             }
         }
+
+        if let Some(index) = index {
+            self.servers_counts.add(&self.servers[index], &self.filter, &self.bookmarks);
+        }
+
+        if self.filter.sort.iter().any(|key| key.criterion == SortCriterion::Bookmarked) {
+            self.sort_servers();
+        }
     }
 
     fn launch_game(&mut self, ip_port: &IpPort) -> Command<Message> {
@@ -1159,52 +1849,6 @@ impl TeamworkLauncher {
         }
     }
 
-    /// Count each element.
-    /// For example with this collection `[3, 3, 3, 2, 2, 1]`
-    /// The result will be: `3 -> 3, 2 -> 2, 1 -> 1`
-    fn histogram<T: Ord>(values: impl Iterator<Item = T>) -> BTreeMap<T, usize> {
-        values.fold(BTreeMap::new(), |mut count, value| {
-            Self::increment_count(&mut count, value);
-
-            count
-        })
-    }
-
-    fn increment_count<K: Ord>(count: &mut BTreeMap<K, usize>, key: K) {
-        match count.entry(key) {
-            Vacant(vacant) => {
-                vacant.insert(1usize);
-            }
-            Occupied(mut occupied) => {
-                *occupied.get_mut() += 1;
-            }
-        }
-    }
-
-    /// Count how many servers with each properties.
-    /// I can't use `histogram`.
-    fn count_properties(servers: &[Server]) -> BTreeMap<Property, usize> {
-        let mut count = BTreeMap::new();
-
-        for server in servers {
-            if server.need_password {
-                Self::increment_count(&mut count, Property::Password);
-            } else if server.has_no_respawn_time {
-                Self::increment_count(&mut count, Property::NoRespawnTime);
-            } else if server.has_rtd {
-                Self::increment_count(&mut count, Property::Rtd);
-            } else if server.has_all_talk {
-                Self::increment_count(&mut count, Property::AllTalk);
-            } else if server.vac_secured {
-                Self::increment_count(&mut count, Property::VacSecured);
-            } else if server.has_random_crits {
-                Self::increment_count(&mut count, Property::RandomCrits);
-            }
-        }
-
-        count
-    }
-
     fn push_notification(&mut self, text: impl ToString, kind: NotificationKind) {
         const NOTIFICATION_DURATION_SECS: u64 = 2;
         let text = Self::obfuscate_api_key(&self.user_settings.teamwork_api_key(), text);
@@ -1215,6 +1859,27 @@ impl TeamworkLauncher {
         self.notifications.push(Notification::new(text, duration, kind));
     }
 
+    /// Explain to the user why Teamwork.tf refused a request.
+    /// The other errors are only logged.
+    ///
+    /// The missing API key is already notified at startup.
+    fn notify_teamwork_error(&mut self, error: &teamwork::Error) {
+        let text = match error {
+            teamwork::Error::InvalidApiKey { .. } => {
+                "Teamwork.tf rejected your API key.\nCheck your API key in the settings."
+            }
+            teamwork::Error::RateLimited { .. } => "Too many requests sent to Teamwork.tf.\nPlease wait a moment.",
+            teamwork::Error::ServerError { .. } | teamwork::Error::HttpRequest(_) => {
+                "Teamwork.tf is not reachable.\nPlease retry later."
+            }
+            _ => return,
+        };
+
+        if self.notified_teamwork_errors.insert(text) {
+            self.push_notification(text, NotificationKind::Error);
+        }
+    }
+
     fn obfuscate_api_key(key: &str, text: impl ToString) -> String {
         if key.is_empty() {
             return text.to_string();
@@ -1240,9 +1905,11 @@ impl Drop for TeamworkLauncher {
         let bookmarks_file_path = configuration_directory.join("bookmarks.json");
         let settings_file_path = configuration_directory.join("settings.json");
         let filters_file_path = configuration_directory.join("filters.json");
+        let filter_presets_file_path = configuration_directory.join("filter_presets.json");
         let sources_file_path = configuration_directory.join("sources.json");
         let mods_registry_file_path = configuration_directory.join("mods.registry");
         let blacklist_file_path = configuration_directory.join("blacklist.json");
+        let network_cache_file_path = configuration_directory.join("network.cache");
 
         write_file(&self.bookmarks, &bookmarks_file_path).unwrap_or_else(|error| {
             error!(
@@ -1255,6 +1922,13 @@ impl Drop for TeamworkLauncher {
             .unwrap_or_else(|error| error!("Failed to write settings file '{}': {}", settings_file_path.display(), error));
         write_file(&self.filter, &filters_file_path)
             .unwrap_or_else(|error| error!("Failed to write filters file '{}': {}", filters_file_path.display(), error));
+        write_file(&self.filter_presets, &filter_presets_file_path).unwrap_or_else(|error| {
+            error!(
+                "Failed to write filter presets file '{}': {}",
+                filter_presets_file_path.display(),
+                error
+            )
+        });
         write_file(&self.servers_sources, &sources_file_path)
             .unwrap_or_else(|error| error!("Failed to write sources file '{}': {}", sources_file_path.display(), error));
         write_file(&self.blacklist, &blacklist_file_path).unwrap_or_else(|error| {
@@ -1279,6 +1953,13 @@ impl Drop for TeamworkLauncher {
                 error
             )
         });
+        write_bin_file(&self.network_cache, &network_cache_file_path).unwrap_or_else(|error| {
+            error!(
+                "Failed to write network cache file '{}': {}",
+                network_cache_file_path.display(),
+                error
+            )
+        });
     }
 }
 
@@ -1342,18 +2023,42 @@ mod keyboard {
     }
 }
 
+/// Create a client replaying the responses recorded in the teamwork crate.
+#[cfg(test)]
+pub fn create_replay_client() -> teamwork::Client {
+    let fixtures_directory = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("teamwork")
+        .join("resources")
+        .join("tests")
+        .join("fixtures");
+
+    teamwork::Client::builder()
+        .fixtures(teamwork::FixtureMode::Replay(fixtures_directory))
+        .build()
+        .expect("create replay client")
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::application::TeamworkLauncher;
+    use {
+        crate::application::{user_settings::LatencyBackend, IpPort, PingRequest},
+        std::net::Ipv4Addr,
+        test_case::test_case,
+    };
 
-    #[test]
-    fn test_histogram() {
-        let numbers = vec![3, 3, 3, 2, 2, 1];
-        let h = TeamworkLauncher::histogram(numbers.iter());
+    #[test_case(LatencyBackend::Icmp, 27015, true)]
+    #[test_case(LatencyBackend::Icmp, 27016, true)]
+    #[test_case(LatencyBackend::A2s, 27015, true)]
+    #[test_case(LatencyBackend::A2s, 27016, false)]
+    fn test_ping_request_applies_to(backend: LatencyBackend, port: u16, expected: bool) {
+        let request = PingRequest {
+            ip_port: IpPort::new(Ipv4Addr::new(1, 2, 3, 4), 27015),
+            sort: false,
+            backend,
+        };
 
-        assert_eq!(h.get(&3), Some(&3));
-        assert_eq!(h.get(&2), Some(&2));
-        assert_eq!(h.get(&1), Some(&1));
-        assert_eq!(h.get(&0), None);
+        assert_eq!(expected, request.applies_to(&IpPort::new(Ipv4Addr::new(1, 2, 3, 4), port)));
+        assert!(!request.applies_to(&IpPort::new(Ipv4Addr::new(4, 3, 2, 1), 27015)));
     }
 }
@@ -0,0 +1,146 @@
+use {
+    crate::application::{Country, Host, IpPort, PingStats},
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// The countries rarely change, the cached ones are used instead of requesting them again.
+pub const COUNTRY_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// The latencies are displayed until the new pings are answered.
+pub const PING_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    value: T,
+    /// The count of seconds since the UNIX epoch.
+    timestamp: u64,
+}
+
+impl<T> Entry<T> {
+    fn new(value: T, now: SystemTime) -> Self {
+        Self {
+            value,
+            timestamp: seconds_since_epoch(now),
+        }
+    }
+
+    fn is_expired(&self, now: SystemTime, ttl: Duration) -> bool {
+        seconds_since_epoch(now).saturating_sub(self.timestamp) >= ttl.as_secs()
+    }
+}
+
+fn seconds_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// The countries and the last latencies found, kept between the sessions.
+#[derive(Serialize, Deserialize, Default)]
+pub struct NetworkCache {
+    countries: BTreeMap<Host, Entry<Country>>,
+    pings: BTreeMap<IpPort, Entry<PingStats>>,
+}
+
+impl NetworkCache {
+    pub fn country(&self, host: &Host, now: SystemTime) -> Option<&Country> {
+        self.countries
+            .get(host)
+            .filter(|entry| !entry.is_expired(now, COUNTRY_TTL))
+            .map(|entry| &entry.value)
+    }
+
+    pub fn ping(&self, ip_port: &IpPort, now: SystemTime) -> Option<PingStats> {
+        self.pings
+            .get(ip_port)
+            .filter(|entry| !entry.is_expired(now, PING_TTL))
+            .map(|entry| entry.value)
+    }
+
+    pub fn insert_country(&mut self, host: Host, country: Country, now: SystemTime) {
+        self.countries.insert(host, Entry::new(country, now));
+    }
+
+    pub fn insert_ping(&mut self, ip_port: IpPort, stats: PingStats, now: SystemTime) {
+        self.pings.insert(ip_port, Entry::new(stats, now));
+    }
+
+    /// Remove the expired entries, so the cache does not grow forever.
+    pub fn remove_expired(&mut self, now: SystemTime) {
+        self.countries.retain(|_, entry| !entry.is_expired(now, COUNTRY_TTL));
+        self.pings.retain(|_, entry| !entry.is_expired(now, PING_TTL));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{NetworkCache, COUNTRY_TTL, PING_TTL},
+        crate::application::{Country, Host, IpPort, PingStats},
+        std::{
+            net::Ipv4Addr,
+            time::{Duration, SystemTime},
+        },
+        test_case::test_case,
+    };
+
+    fn host() -> Host {
+        Host::from(Ipv4Addr::new(1, 2, 3, 4))
+    }
+
+    fn stats() -> PingStats {
+        PingStats::from_samples(&[Some(Duration::from_millis(42))]).unwrap()
+    }
+
+    #[test_case(Duration::ZERO, true; "fresh")]
+    #[test_case(COUNTRY_TTL - Duration::from_secs(1), true; "before expiration")]
+    #[test_case(COUNTRY_TTL, false; "expired")]
+    fn test_country(elapsed: Duration, expected: bool) {
+        let now = SystemTime::now();
+        let mut cache = NetworkCache::default();
+
+        cache.insert_country(host(), Country::new(&"FR"), now);
+
+        assert_eq!(expected, cache.country(&host(), now + elapsed).is_some());
+    }
+
+    #[test_case(Duration::ZERO, true; "fresh")]
+    #[test_case(PING_TTL, false; "expired")]
+    fn test_ping(elapsed: Duration, expected: bool) {
+        let now = SystemTime::now();
+        let mut cache = NetworkCache::default();
+
+        cache.insert_ping(IpPort::new(host(), 27015), stats(), now);
+
+        assert_eq!(expected, cache.ping(&IpPort::new(host(), 27015), now + elapsed).is_some());
+        assert_eq!(None, cache.ping(&IpPort::new(host(), 27016), now));
+    }
+
+    #[test]
+    fn test_remove_expired() {
+        let now = SystemTime::now();
+        let mut cache = NetworkCache::default();
+
+        cache.insert_country(host(), Country::new(&"FR"), now);
+        cache.insert_ping(IpPort::new(host(), 27015), stats(), now);
+        cache.remove_expired(now + PING_TTL);
+
+        assert_eq!(1, cache.countries.len());
+        assert!(cache.pings.is_empty());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let now = SystemTime::now();
+        let mut cache = NetworkCache::default();
+
+        cache.insert_country(host(), Country::new(&"FR"), now);
+        cache.insert_ping(IpPort::new(host(), 27015), stats(), now);
+
+        let cache: NetworkCache = bincode::deserialize(&bincode::serialize(&cache).unwrap()).unwrap();
+
+        assert_eq!(Some(&Country::new(&"FR")), cache.country(&host(), now));
+        assert_eq!(Some(stats()), cache.ping(&IpPort::new(host(), 27015), now));
+    }
+}
@@ -15,6 +15,11 @@ pub trait PathsProvider {
         self.get_configuration_directory().join("thumbnails")
     }
 
+    /// The directory where the responses of Teamwork.tf are cached.
+    fn get_http_cache_directory(&self) -> PathBuf {
+        self.get_configuration_directory().join("http_cache")
+    }
+
     fn get_mods_directory(&self) -> Option<PathBuf> {
         self.get_team_fortress_directory()
             .map(|directory| directory.join("tf").join("custom"))
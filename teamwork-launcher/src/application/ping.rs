@@ -1,19 +1,28 @@
 use {
-    crate::application::{message::PingServiceMessage, PingRequest},
+    crate::application::{
+        ip_port::Host,
+        message::PingServiceMessage,
+        ping_stats::PingStats,
+        user_settings::LatencyBackend,
+        worker_pool::{WorkerPool, WorkerRequest, DEFAULT_CONCURRENCY},
+        IpPort, PingRequest,
+    },
     iced::{
-        futures::channel::mpsc::{unbounded, UnboundedReceiver},
+        futures::{
+            channel::mpsc::{unbounded, UnboundedReceiver},
+            FutureExt,
+        },
         subscription, Subscription,
     },
-    std::{
-        net::{IpAddr, Ipv4Addr},
-        time::Duration,
-    },
-    surge_ping::{Client, Config, IcmpPacket, PingIdentifier, PingSequence},
+    std::{net::IpAddr, time::Duration},
+    surge_ping::{Client, Config, IcmpPacket, PingIdentifier, PingSequence, ICMP},
 };
 
 #[derive(Clone)]
 struct PingService {
-    client: Option<Client>,
+    client_v4: Option<Client>,
+    client_v6: Option<Client>,
+    query_client: source_query::Client,
 }
 
 const PAYLOAD: &[u8; 56] = &[
@@ -28,19 +37,84 @@ pub enum Error {
     Timeout,
     #[error("Client is disabled")]
     ClientDisabled,
+    #[error("Failed to resolve host: {0}")]
+    Resolve(String),
+    #[error("Server query failed: {0}")]
+    Query(String),
+}
+
+/// The samples sent to one server.
+#[derive(Default)]
+struct Samples(Vec<Option<Duration>>);
+
+impl Samples {
+    /// Add a sample, `None` if it's lost. Returns true if more samples must be sent.
+    ///
+    /// A server that does not answer the first two samples is very likely unreachable,
+    /// sending the others would only make the user wait for more timeouts.
+    fn push(&mut self, sample: Option<Duration>) -> bool {
+        self.0.push(sample);
+
+        let unreachable = self.0.len() == 2 && self.0.iter().all(Option::is_none);
+
+        !unreachable && self.0.len() < PingStats::SAMPLES
+    }
+
+    fn stats(&self) -> Result<PingStats, Error> {
+        PingStats::from_samples(&self.0).ok_or(Error::Timeout)
+    }
 }
 
 impl PingService {
-    pub async fn ping(&self, ip: &Ipv4Addr) -> Result<Duration, Error> {
-        if let Some(client) = self.client.as_ref() {
-            let mut pinger = client.pinger(IpAddr::from(*ip), PingIdentifier(111)).await;
+    pub async fn ping(&self, request: &PingRequest) -> Result<PingStats, Error> {
+        match request.backend {
+            LatencyBackend::Icmp => self.icmp_ping(request.ip_port.host()).await,
+            LatencyBackend::A2s => self.query_ping(&request.ip_port).await,
+        }
+    }
+
+    async fn query_ping(&self, ip_port: &IpPort) -> Result<PingStats, Error> {
+        let mut samples = Samples::default();
+
+        loop {
+            let sample = match self.query_client.latency(ip_port.to_string()).await {
+                Ok(duration) => Some(duration),
+                Err(source_query::Error::Timeout) => None,
+                Err(error) => return Err(Error::Query(error.to_string())),
+            };
+
+            if !samples.push(sample) {
+                return samples.stats();
+            }
+        }
+    }
+
+    async fn icmp_ping(&self, host: &Host) -> Result<PingStats, Error> {
+        let ip = host.resolve().await.map_err(|error| Error::Resolve(error.to_string()))?;
+        let client = match ip {
+            IpAddr::V4(_) => self.client_v4.as_ref(),
+            IpAddr::V6(_) => self.client_v6.as_ref(),
+        };
+
+        if let Some(client) = client {
+            let mut pinger = client.pinger(ip, PingIdentifier(111)).await;
+            let mut samples = Samples::default();
+            let mut sequence = 0;
 
             pinger.timeout(Duration::from_secs(1));
 
-            match pinger.ping(PingSequence(0), PAYLOAD).await {
-                Ok((IcmpPacket::V4(_reply), dur)) => Ok(dur),
-                Ok((IcmpPacket::V6(_reply), dur)) => Ok(dur),
-                Err(_e) => Err(Error::Timeout),
+            loop {
+                let sample = match pinger.ping(PingSequence(sequence), PAYLOAD).await {
+                    Ok((IcmpPacket::V4(_reply), dur)) => Some(dur),
+                    Ok((IcmpPacket::V6(_reply), dur)) => Some(dur),
+                    Err(_e) => None,
+                };
+
+                if !samples.push(sample) {
+                    return samples.stats();
+                }
+
+                sequence += 1;
             }
         } else {
             Err(Error::ClientDisabled)
@@ -50,17 +124,23 @@ impl PingService {
 
 impl Default for PingService {
     fn default() -> Self {
-        let config = Config::default();
-
         Self {
-            client: Client::new(&config).ok(),
+            client_v4: Client::new(&Config::default()).ok(),
+            client_v6: Client::new(&Config::builder().kind(ICMP::V6).build()).ok(),
+            query_client: source_query::Client::new(Duration::from_secs(1)),
         }
     }
 }
 
+type PingResult = (PingRequest, Result<PingStats, Error>);
+
 enum State {
     Starting,
-    Ready(UnboundedReceiver<PingRequest>, PingService),
+    Ready(
+        UnboundedReceiver<WorkerRequest<PingRequest>>,
+        PingService,
+        WorkerPool<PingRequest, PingResult>,
+    ),
 }
 
 pub fn subscription() -> Subscription<PingServiceMessage> {
@@ -71,22 +151,31 @@ pub fn subscription() -> Subscription<PingServiceMessage> {
 
                 (
                     PingServiceMessage::Started(sender),
-                    State::Ready(receiver, PingService::default()),
+                    State::Ready(receiver, PingService::default(), WorkerPool::new(DEFAULT_CONCURRENCY)),
                 )
             }
-            State::Ready(mut receiver, service) => {
-                use iced::futures::StreamExt;
+            State::Ready(mut receiver, service, mut pool) => {
+                let (request, result) = pool
+                    .next(&mut receiver, |request| {
+                        let service = service.clone();
+
+                        async move {
+                            let result = service.ping(&request).await;
 
-                let request = receiver.select_next_some().await;
+                            (request, result)
+                        }
+                        .boxed()
+                    })
+                    .await;
 
-                match service.ping(&request.ip).await {
-                    Ok(duration) => (
-                        PingServiceMessage::Answer(request.ip, duration, request.sort),
-                        State::Ready(receiver, service),
+                match result {
+                    Ok(stats) => (
+                        PingServiceMessage::Answer(request, stats),
+                        State::Ready(receiver, service, pool),
                     ),
                     Err(error) => (
-                        PingServiceMessage::Error(request.ip, error, request.sort),
-                        State::Ready(receiver, service),
+                        PingServiceMessage::Error(request, error),
+                        State::Ready(receiver, service, pool),
                     ),
                 }
             }
@@ -0,0 +1,117 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::time::Duration,
+};
+
+/// The jitter from which a server is considered unstable.
+const UNSTABLE_JITTER: Duration = Duration::from_millis(15);
+/// The percentage of lost samples from which a server is considered unstable.
+const UNSTABLE_LOSS: u8 = 20;
+
+/// The statistics of several latency samples sent to the same server.
+///
+/// The servers are compared by median first, a single sample is too noisy to be compared.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PingStats {
+    pub median: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    /// The mean of the differences between two consecutive samples.
+    pub jitter: Duration,
+    /// The percentage of samples without answer, from 0 to 100.
+    pub loss: u8,
+}
+
+impl PingStats {
+    /// The count of samples sent for each server.
+    pub const SAMPLES: usize = 5;
+
+    /// Compute the statistics, a lost sample is `None`.
+    ///
+    /// Returns `None` if all the samples are lost.
+    pub fn from_samples(samples: &[Option<Duration>]) -> Option<Self> {
+        let mut received: Vec<Duration> = samples.iter().flatten().copied().collect();
+
+        if received.is_empty() {
+            return None;
+        }
+
+        let jitter = match received.len() {
+            1 => Duration::ZERO,
+            count => received.windows(2).map(|pair| pair[0].abs_diff(pair[1])).sum::<Duration>() / (count - 1) as u32,
+        };
+        let loss = ((samples.len() - received.len()) * 100 / samples.len()) as u8;
+
+        received.sort();
+
+        let middle = received.len() / 2;
+        let median = match received.len() % 2 {
+            0 => (received[middle - 1] + received[middle]) / 2,
+            _ => received[middle],
+        };
+
+        Some(Self {
+            median,
+            min: received[0],
+            max: received[received.len() - 1],
+            jitter,
+            loss,
+        })
+    }
+
+    /// True if the latency varies a lot or if some samples are lost.
+    pub fn is_unstable(&self) -> bool {
+        self.jitter >= UNSTABLE_JITTER || self.loss >= UNSTABLE_LOSS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::PingStats, std::time::Duration, test_case::test_case};
+
+    fn ms(millis: u64) -> Option<Duration> {
+        Some(Duration::from_millis(millis))
+    }
+
+    #[test]
+    fn test_from_samples() {
+        let stats = PingStats::from_samples(&[ms(30), ms(50), None, ms(40), ms(20)]).unwrap();
+
+        assert_eq!(Duration::from_millis(35), stats.median);
+        assert_eq!(Duration::from_millis(20), stats.min);
+        assert_eq!(Duration::from_millis(50), stats.max);
+        // |30 - 50| + |50 - 40| + |40 - 20| = 50, for 3 differences.
+        assert_eq!(Duration::from_nanos(16_666_666), stats.jitter);
+        assert_eq!(20, stats.loss);
+    }
+
+    #[test]
+    fn test_from_single_sample() {
+        let stats = PingStats::from_samples(&[None, ms(42), None, None]).unwrap();
+
+        assert_eq!(Duration::from_millis(42), stats.median);
+        assert_eq!(Duration::ZERO, stats.jitter);
+        assert_eq!(75, stats.loss);
+    }
+
+    #[test_case(&[]; "no samples")]
+    #[test_case(&[None, None, None]; "all lost")]
+    fn test_from_lost_samples(samples: &[Option<Duration>]) {
+        assert_eq!(None, PingStats::from_samples(samples));
+    }
+
+    #[test_case(&[ms(30), ms(31), ms(29), ms(30), ms(30)], false; "stable")]
+    #[test_case(&[ms(30), ms(80), ms(20), ms(90), ms(30)], true; "high jitter")]
+    #[test_case(&[ms(30), None, ms(30), ms(30), ms(30)], true; "loss")]
+    fn test_is_unstable(samples: &[Option<Duration>], expected: bool) {
+        assert_eq!(expected, PingStats::from_samples(samples).unwrap().is_unstable());
+    }
+
+    #[test]
+    fn test_compare_by_median() {
+        let stable = PingStats::from_samples(&[ms(40), ms(40), ms(40)]).unwrap();
+        let noisy = PingStats::from_samples(&[ms(10), ms(50), ms(90)]).unwrap();
+
+        assert!(stable < noisy);
+    }
+}
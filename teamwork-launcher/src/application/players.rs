@@ -0,0 +1,45 @@
+use {
+    crate::application::{message::PlayersMessage, IpPort, Message},
+    iced::{Command, Subscription},
+    std::time::Duration,
+};
+
+/// How often the players are queried while the server details are displayed.
+const REFRESH_RATE: Duration = Duration::from_secs(10);
+
+pub fn fetch_players(client: source_query::Client, ip_port: IpPort) -> Command<Message> {
+    let address = ip_port.to_string();
+
+    Command::perform(async move { client.players(address).await }, move |result| match result {
+        Ok(players) => Message::Players(PlayersMessage::Ready(ip_port.clone(), players)),
+        Err(error) => Message::Players(PlayersMessage::Error(ip_port.clone(), error.into())),
+    })
+}
+
+pub fn subscription() -> Subscription<PlayersMessage> {
+    iced::time::every(REFRESH_RATE).map(|_| PlayersMessage::Refresh)
+}
+
+/// Format the time a player has been connected, like `1:02:03` or `12:34`.
+pub fn format_connected_time(duration: &Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    match hours {
+        0 => format!("{}:{:02}", minutes, seconds),
+        hours => format!("{}:{:02}:{:02}", hours, minutes, seconds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::format_connected_time, std::time::Duration, test_case::test_case};
+
+    #[test_case(0, "0:00")]
+    #[test_case(59, "0:59")]
+    #[test_case(754, "12:34")]
+    #[test_case(3723, "1:02:03")]
+    fn test_format_connected_time(seconds: u64, expected: &str) {
+        assert_eq!(expected, format_connected_time(&Duration::from_secs(seconds)));
+    }
+}
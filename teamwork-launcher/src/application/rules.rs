@@ -0,0 +1,74 @@
+use {
+    crate::application::{
+        message::RulesServiceMessage,
+        worker_pool::{WorkerPool, WorkerRequest, DEFAULT_CONCURRENCY},
+        IpPort,
+    },
+    iced::{
+        futures::{
+            channel::mpsc::{unbounded, UnboundedReceiver},
+            FutureExt,
+        },
+        subscription, Subscription,
+    },
+    std::sync::Arc,
+};
+
+struct RulesService {
+    client: source_query::Client,
+}
+
+type RulesResult = (IpPort, Result<source_query::Rules, source_query::Error>);
+
+enum State {
+    Starting,
+    Ready(
+        UnboundedReceiver<WorkerRequest<IpPort>>,
+        RulesService,
+        WorkerPool<IpPort, RulesResult>,
+    ),
+}
+
+/// Query the rules of the servers, several servers at a time.
+pub fn subscription() -> Subscription<RulesServiceMessage> {
+    subscription::unfold(std::any::TypeId::of::<RulesService>(), State::Starting, |state| async move {
+        match state {
+            State::Starting => {
+                let (sender, receiver) = unbounded();
+                let service = RulesService {
+                    client: source_query::Client::default(),
+                };
+
+                (
+                    RulesServiceMessage::Started(sender),
+                    State::Ready(receiver, service, WorkerPool::new(DEFAULT_CONCURRENCY)),
+                )
+            }
+            State::Ready(mut receiver, service, mut pool) => {
+                let (ip_port, result) = pool
+                    .next(&mut receiver, |ip_port| {
+                        let client = service.client.clone();
+
+                        async move {
+                            let result = client.rules(ip_port.to_string()).await;
+
+                            (ip_port, result)
+                        }
+                        .boxed()
+                    })
+                    .await;
+
+                match result {
+                    Ok(rules) => (
+                        RulesServiceMessage::Answer(ip_port, Arc::new(rules)),
+                        State::Ready(receiver, service, pool),
+                    ),
+                    Err(error) => (
+                        RulesServiceMessage::Error(ip_port, Arc::new(error)),
+                        State::Ready(receiver, service, pool),
+                    ),
+                }
+            }
+        }
+    })
+}
@@ -1,8 +1,13 @@
-use {crate::application::IpPort, iced::widget::text_input};
+use {
+    crate::application::{game_mode::GameModeId, map::MapName, IpPort, PromisedValue},
+    iced::widget::{image, text_input},
+    std::sync::Arc,
+};
 
 pub enum Screens {
     Main,
     Server(ServerView),
+    Map(MapView),
     Mods,
     AddMod(AddModView),
     Settings,
@@ -10,11 +15,45 @@ pub enum Screens {
 
 pub struct ServerView {
     pub ip_port: IpPort,
+    /// The players connected, refreshed while the view is displayed.
+    pub players: PromisedValue<Vec<source_query::Player>>,
+    pub players_error: Option<String>,
+    pub rules_expanded: bool,
 }
 
 impl ServerView {
     pub fn new(ip_port: IpPort) -> Self {
-        Self { ip_port }
+        Self {
+            ip_port,
+            players: PromisedValue::Loading,
+            players_error: None,
+            rules_expanded: false,
+        }
+    }
+}
+
+pub struct MapView {
+    pub map_name: MapName,
+    pub thumbnail: PromisedValue<image::Handle>,
+    pub stats: Option<Arc<teamwork::MapStats>>,
+    pub game_modes: Vec<GameModeId>,
+    pub error: Option<String>,
+}
+
+impl MapView {
+    pub fn new(map_name: MapName, thumbnail: PromisedValue<image::Handle>) -> Self {
+        Self {
+            map_name,
+            thumbnail,
+            stats: None,
+            game_modes: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn set_stats(&mut self, stats: Arc<teamwork::MapStats>) {
+        self.game_modes = stats.game_modes.iter().map(GameModeId::new).collect();
+        self.stats = Some(stats);
     }
 }
 
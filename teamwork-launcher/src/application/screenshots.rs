@@ -70,11 +70,9 @@ impl Screenshots {
     }
 }
 
-pub fn fetch_screenshot(map_name: MapName, api_key: String) -> Command<Message> {
+pub fn fetch_screenshot(client: teamwork::Client, map_name: MapName, api_key: String) -> Command<Message> {
     Command::perform(
         async move {
-            let client = teamwork::Client::default();
-
             client
                 .get_map_screenshots(&api_key, map_name.as_str(), image::Handle::from_memory)
                 .await
@@ -1,16 +1,24 @@
 use {
     crate::application::{
-        country::Country, game_mode::GameModeId, ip_port::IpPort, map::MapName, promised_value::PromisedValue,
+        country::Country,
+        game_mode::GameModeId,
+        ip_port::{Host, InvalidHost, IpPort},
+        map::MapName,
+        ping_stats::PingStats,
+        promised_value::PromisedValue,
         servers_source::SourceKey,
     },
     iced::widget::image,
-    std::{str::FromStr, time::Duration},
+    std::{str::FromStr, sync::Arc},
 };
 
 /// Store information about a server.
 #[derive(Debug, Hash, Clone)]
 pub struct Server {
+    /// The name cleaned by the Teamwork.tf client, used to display, filter and sort the servers.
     pub name: String,
+    /// The name as received.
+    pub raw_name: String,
     pub max_players_count: u8,
     pub current_players_count: u8,
     pub map: MapName,
@@ -18,7 +26,7 @@ pub struct Server {
     pub map_thumbnail: PromisedValue<image::Handle>,
     pub ip_port: IpPort,
     pub country: PromisedValue<Country>,
-    pub ping: PromisedValue<Duration>,
+    pub ping: PromisedValue<PingStats>,
     pub source_key: Option<SourceKey>,
     pub game_modes: Vec<GameModeId>,
     pub provider: String,
@@ -28,21 +36,66 @@ pub struct Server {
     pub has_all_talk: bool,
     pub has_random_crits: bool,
     pub need_password: bool,
+    /// The rules (console variables) queried directly to the server, only when they are needed.
+    pub rules: PromisedValue<Arc<source_query::Rules>>,
 }
 
 impl Server {
+    /// Create a server from the information queried directly to the server, for the servers not listed by Teamwork.tf.
+    ///
+    /// The properties are read from the tags of the server, the game modes are guessed later
+    /// from the map name, see [`GameModes::guess_from_map`](crate::application::game_mode::GameModes::guess_from_map).
+    pub fn from_info(ip_port: IpPort, info: source_query::Info, name_normalization: &teamwork::NameNormalization) -> Self {
+        let tags: Vec<&str> = info.keywords.as_deref().unwrap_or_default().split(',').collect();
+
+        Server {
+            name: name_normalization.normalize(&info.name),
+            map: MapName::new(&info.map),
+            next_map: None,
+            map_thumbnail: PromisedValue::Loading,
+            current_players_count: info.players,
+            max_players_count: info.max_players,
+            ip_port,
+            country: PromisedValue::Loading,
+            ping: PromisedValue::Loading,
+            source_key: None,
+            provider: String::from("Unspecified"),
+            game_modes: Vec::new(),
+            vac_secured: info.vac,
+            has_all_talk: tags.contains(&"alltalk"),
+            has_rtd: tags.contains(&"rtd"),
+            has_no_respawn_time: tags.contains(&"norespawntime"),
+            has_random_crits: !tags.contains(&"nocrits"),
+            need_password: info.password,
+            rules: PromisedValue::None,
+            raw_name: info.name,
+        }
+    }
+
     pub fn free_slots(&self) -> u8 {
         if self.max_players_count < self.current_players_count {
             return 0;
         }
         self.max_players_count - self.current_players_count
     }
+
+    pub fn has_property(&self, property: Property) -> bool {
+        match property {
+            Property::Rtd => self.has_rtd,
+            Property::AllTalk => self.has_all_talk,
+            Property::NoRespawnTime => self.has_no_respawn_time,
+            Property::Password => self.need_password,
+            Property::VacSecured => self.vac_secured,
+            Property::RandomCrits => self.has_random_crits,
+        }
+    }
 }
 
 impl Default for Server {
     fn default() -> Self {
         Self {
             name: Default::default(),
+            raw_name: Default::default(),
             max_players_count: Default::default(),
             current_players_count: Default::default(),
             map: Default::default(),
@@ -60,20 +113,24 @@ impl Default for Server {
             has_all_talk: false,
             has_random_crits: false,
             need_password: false,
+            rules: PromisedValue::None,
         }
     }
 }
 
-impl From<teamwork::Server> for Server {
-    fn from(server: teamwork::Server) -> Self {
-        Server {
-            name: server.name,
+impl TryFrom<teamwork::Server> for Server {
+    type Error = InvalidHost;
+
+    fn try_from(server: teamwork::Server) -> Result<Self, Self::Error> {
+        Ok(Server {
+            name: server.normalized_name,
+            raw_name: server.name,
             map: MapName::new(server.map_name),
             next_map: server.map_name_next.map(MapName::new),
             map_thumbnail: PromisedValue::Loading,
             current_players_count: server.players,
             max_players_count: server.max_players,
-            ip_port: IpPort::new(std::net::Ipv4Addr::from_str(&server.ip).expect("parse IP"), server.port),
+            ip_port: IpPort::new(Host::from_str(&server.ip)?, server.port),
             country: PromisedValue::Loading,
             ping: PromisedValue::Loading,
             source_key: None,
@@ -85,11 +142,12 @@ impl From<teamwork::Server> for Server {
             has_no_respawn_time: server.has_norespawntime.unwrap_or_default(),
             has_random_crits: server.has_randomcrits.unwrap_or_default(),
             need_password: server.has_password.unwrap_or_default(),
-        }
+            rules: PromisedValue::None,
+        })
     }
 }
 
-#[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
 pub enum Property {
     Rtd,
     AllTalk,
@@ -98,3 +156,77 @@ pub enum Property {
     VacSecured,
     RandomCrits,
 }
+
+impl Property {
+    pub const ALL: [Property; 6] = [
+        Property::Rtd,
+        Property::AllTalk,
+        Property::NoRespawnTime,
+        Property::Password,
+        Property::VacSecured,
+        Property::RandomCrits,
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::application::{
+            ip_port::{Host, InvalidHost, IpPort},
+            map::MapName,
+            Server,
+        },
+        std::str::FromStr,
+        test_case::test_case,
+    };
+
+    #[test_case("1.2.3.4", Ok(Host::from_str("1.2.3.4").unwrap()))]
+    #[test_case("2001:db8::1", Ok(Host::from_str("2001:db8::1").unwrap()))]
+    #[test_case("tf2.example.com", Ok(Host::Name("tf2.example.com".to_string())))]
+    #[test_case("1.2.3", Err(InvalidHost("1.2.3".to_string())))]
+    #[test_case("", Err(InvalidHost(String::new())))]
+    fn test_server_from_teamwork(ip: &str, expected: Result<Host, InvalidHost>) {
+        let server = teamwork::Server {
+            ip: ip.to_string(),
+            port: 27015,
+            ..Default::default()
+        };
+
+        assert_eq!(expected, Server::try_from(server).map(|server| server.ip_port.host().clone()));
+    }
+
+    #[test_case("pl_upward", "nocrits,alltalk", false, true)]
+    #[test_case("plr_hightower", "", true, false)]
+    #[test_case("itemtest", "alltalk,rtd", true, true)]
+    fn test_server_from_info(map: &str, tags: &str, random_crits: bool, all_talk: bool) {
+        let info = source_query::Info {
+            protocol: 17,
+            name: "  Uncletopia | Paris  ".to_string(),
+            map: map.to_string(),
+            folder: "tf".to_string(),
+            game: "Team Fortress".to_string(),
+            app_id: 440,
+            players: 22,
+            max_players: 24,
+            bots: 0,
+            server_type: source_query::ServerType::Dedicated,
+            password: false,
+            vac: true,
+            version: "8604597".to_string(),
+            port: None,
+            steam_id: None,
+            keywords: Some(tags.to_string()),
+            game_id: None,
+        };
+        let ip_port = IpPort::new(Host::from_str("1.2.3.4").unwrap(), 27015);
+        let server = Server::from_info(ip_port.clone(), info, &teamwork::NameNormalization::default());
+
+        assert_eq!(ip_port, server.ip_port);
+        assert_eq!("Uncletopia | Paris", server.name);
+        assert_eq!("  Uncletopia | Paris  ", server.raw_name);
+        assert_eq!(MapName::new(map), server.map);
+        assert!(server.game_modes.is_empty());
+        assert_eq!(random_crits, server.has_random_crits);
+        assert_eq!(all_talk, server.has_all_talk);
+    }
+}
@@ -1,11 +1,21 @@
 use {
-    crate::application::{game_mode::GameModeId, map::MapName, server::Property, Country},
+    crate::application::{
+        filter::filter_servers::{Acceptance, Facet, Filter},
+        game_mode::GameModeId,
+        map::MapName,
+        server::Property,
+        Bookmarks, Country, Server,
+    },
     std::collections::{
         btree_map::Entry::{Occupied, Vacant},
         BTreeMap,
     },
 };
 
+/// The counts displayed next to the values of the filters.
+///
+/// The count of a value is the count of servers having this value and accepted by every other filter,
+/// so it's the count of servers displayed if only this value is checked.
 #[derive(Default)]
 pub struct ServersCounts {
     pub bookmarks: usize,
@@ -22,14 +32,212 @@ impl ServersCounts {
         *self = ServersCounts::default();
     }
 
-    pub fn add_country(&mut self, country: Country) {
-        match self.countries.entry(country) {
-            Vacant(vacant) => {
+    /// Count every server again, needed when the filter changes.
+    pub fn recount(&mut self, servers: &[Server], filter: &Filter, bookmarks: &Bookmarks) {
+        self.reset();
+
+        for server in servers {
+            self.add(server, filter, bookmarks);
+        }
+    }
+
+    pub fn add(&mut self, server: &Server, filter: &Filter, bookmarks: &Bookmarks) {
+        self.count(server, filter, bookmarks, true);
+    }
+
+    pub fn remove(&mut self, server: &Server, filter: &Filter, bookmarks: &Bookmarks) {
+        self.count(server, filter, bookmarks, false);
+    }
+
+    /// Modify a server, only the counts of this server are updated.
+    pub fn modify(&mut self, server: &mut Server, filter: &Filter, bookmarks: &Bookmarks, modify: impl FnOnce(&mut Server)) {
+        self.remove(server, filter, bookmarks);
+        modify(server);
+        self.add(server, filter, bookmarks);
+    }
+
+    fn count(&mut self, server: &Server, filter: &Filter, bookmarks: &Bookmarks, add: bool) {
+        if bookmarks.is_bookmarked(&server.ip_port) {
+            Self::change(&mut self.bookmarks, add);
+        }
+
+        if server.ping.is_none() {
+            Self::change(&mut self.timeouts, add);
+        }
+
+        let acceptance = filter.acceptance(server, bookmarks);
+
+        if acceptance == Acceptance::Rejected {
+            return;
+        }
+
+        if acceptance.is_accepted_by_others(Facet::Country) {
+            if let Some(country) = server.country.get() {
+                Self::change_entry(&mut self.countries, country.clone(), add);
+            }
+        }
+
+        if acceptance.is_accepted_by_others(Facet::GameMode) {
+            for game_mode in &server.game_modes {
+                Self::change_entry(&mut self.game_modes, game_mode.clone(), add);
+            }
+        }
+
+        if acceptance.is_accepted_by_others(Facet::Map) {
+            Self::change_entry(&mut self.maps, server.map.clone(), add);
+        }
+
+        if acceptance.is_accepted_by_others(Facet::Provider) {
+            Self::change_entry(&mut self.providers, server.provider.clone(), add);
+        }
+
+        // A server counts for each of its properties.
+        for property in Property::ALL {
+            if server.has_property(property) && acceptance.is_accepted_by_others(Facet::Property(property)) {
+                Self::change_entry(&mut self.properties, property, add);
+            }
+        }
+    }
+
+    fn change(count: &mut usize, add: bool) {
+        match add {
+            true => *count += 1,
+            false => *count = count.saturating_sub(1),
+        }
+    }
+
+    fn change_entry<K: Ord>(counts: &mut BTreeMap<K, usize>, key: K, add: bool) {
+        match (counts.entry(key), add) {
+            (Vacant(vacant), true) => {
                 vacant.insert(1);
             }
-            Occupied(mut occupied) => {
+            (Vacant(_), false) => {}
+            (Occupied(mut occupied), true) => {
                 *occupied.get_mut() += 1;
             }
+            (Occupied(occupied), false) if *occupied.get() <= 1 => {
+                occupied.remove();
+            }
+            (Occupied(mut occupied), false) => {
+                *occupied.get_mut() -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::ServersCounts,
+        crate::application::{
+            filter::{filter_servers::Filter, properties_filter::PropertyFilterSwitch},
+            map::MapName,
+            server::Property,
+            Bookmarks, Country, PromisedValue, Server,
+        },
+    };
+
+    fn create_server(map: &str, country: &str, vac_secured: bool, has_rtd: bool) -> Server {
+        Server {
+            map: MapName::new(map),
+            country: PromisedValue::Ready(Country::new(&country)),
+            vac_secured,
+            has_rtd,
+            ..Default::default()
+        }
+    }
+
+    fn create_servers() -> Vec<Server> {
+        vec![
+            create_server("pl_upward", "FR", true, true),
+            create_server("pl_upward", "US", true, false),
+            create_server("koth_viaduct", "FR", false, true),
+        ]
+    }
+
+    #[test]
+    fn test_every_property_is_counted() {
+        let mut counts = ServersCounts::default();
+        let filter = Filter {
+            vac_secured: PropertyFilterSwitch::Ignore,
+            ..Default::default()
         };
+
+        counts.recount(&create_servers(), &filter, &Bookmarks::default());
+
+        assert_eq!(Some(&2), counts.properties.get(&Property::VacSecured));
+        assert_eq!(Some(&2), counts.properties.get(&Property::Rtd));
+    }
+
+    #[test]
+    fn test_counts_ignore_their_own_filter() {
+        let mut counts = ServersCounts::default();
+        let mut filter = Filter {
+            vac_secured: PropertyFilterSwitch::Ignore,
+            ..Default::default()
+        };
+
+        filter.maps.enabled = true;
+        filter
+            .maps
+            .dictionary
+            .extend(create_servers().into_iter().map(|server| server.map));
+        filter.maps.dictionary.set_checked(&MapName::new("koth_viaduct"), false);
+        counts.recount(&create_servers(), &filter, &Bookmarks::default());
+
+        // The unchecked map is counted, as if it was checked.
+        assert_eq!(Some(&2), counts.maps.get(&MapName::new("pl_upward")));
+        assert_eq!(Some(&1), counts.maps.get(&MapName::new("koth_viaduct")));
+        // The other facets count only the servers of the checked maps.
+        assert_eq!(Some(&1), counts.countries.get(&Country::new(&"FR")));
+        assert_eq!(Some(&1), counts.properties.get(&Property::Rtd));
+    }
+
+    #[test]
+    fn test_rejected_by_another_filter() {
+        let mut counts = ServersCounts::default();
+
+        // The default filter requires VAC.
+        counts.recount(&create_servers(), &Filter::default(), &Bookmarks::default());
+
+        assert_eq!(Some(&2), counts.maps.get(&MapName::new("pl_upward")));
+        assert_eq!(None, counts.maps.get(&MapName::new("koth_viaduct")));
+        assert_eq!(Some(&2), counts.properties.get(&Property::VacSecured));
+        assert_eq!(Some(&1), counts.properties.get(&Property::Rtd));
+    }
+
+    #[test]
+    fn test_rejected_by_several_filters() {
+        let mut counts = ServersCounts::default();
+        let mut filter = Filter::default();
+
+        filter.maps.enabled = true;
+        filter
+            .maps
+            .dictionary
+            .extend(create_servers().into_iter().map(|server| server.map));
+        filter.maps.dictionary.set_checked(&MapName::new("koth_viaduct"), false);
+        counts.recount(&create_servers(), &filter, &Bookmarks::default());
+
+        assert_eq!(None, counts.maps.get(&MapName::new("koth_viaduct")));
+        assert_eq!(Some(&1), counts.countries.get(&Country::new(&"FR")));
+    }
+
+    #[test]
+    fn test_modify() {
+        let mut counts = ServersCounts::default();
+        let filter = Filter::default();
+        let bookmarks = Bookmarks::default();
+        let mut servers = create_servers();
+
+        counts.recount(&servers, &filter, &bookmarks);
+        counts.modify(&mut servers[0], &filter, &bookmarks, |server| {
+            server.country = PromisedValue::Ready(Country::new(&"DE"))
+        });
+
+        assert_eq!(None, counts.countries.get(&Country::new(&"FR")));
+        assert_eq!(Some(&1), counts.countries.get(&Country::new(&"DE")));
+        assert_eq!(Some(&1), counts.countries.get(&Country::new(&"US")));
+        assert_eq!(Some(&2), counts.maps.get(&MapName::new("pl_upward")));
     }
 }
@@ -12,12 +12,29 @@ impl SourceKey {
     }
 }
 
+/// The filter of the servers listed by the master server: Team Fortress 2 servers that are not empty.
+pub const DEFAULT_MASTER_SERVER_FILTER: &str = "\\appid\\440\\empty\\1";
+
+/// The service listing the servers of a source.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SourceKind {
+    /// A list of Teamwork.tf, the URL is the address of the list.
+    #[default]
+    Teamwork,
+    /// The Steam master server, the URL is the address of the master server.
+    ///
+    /// It does not need a Teamwork.tf API key, the information is queried to each server.
+    MasterServer { region: source_query::Region, filter: String },
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ServersSource {
     display_name: String,
     url: String,
     key: SourceKey,
     enabled: bool,
+    #[serde(default)]
+    kind: SourceKind,
 }
 
 impl ServersSource {
@@ -27,6 +44,22 @@ impl ServersSource {
             url: url.to_string(),
             key: SourceKey(Arc::new(url.to_string())),
             enabled: true,
+            kind: SourceKind::Teamwork,
+        }
+    }
+
+    pub fn master_server(display_name: impl ToString, region: source_query::Region, filter: impl ToString) -> Self {
+        let url = source_query::STEAM_MASTER_SERVER;
+
+        Self {
+            display_name: display_name.to_string(),
+            url: url.to_string(),
+            key: SourceKey(Arc::new(format!("{}#{}", url, display_name.to_string()))),
+            enabled: true,
+            kind: SourceKind::MasterServer {
+                region,
+                filter: filter.to_string(),
+            },
         }
     }
 
@@ -49,4 +82,12 @@ impl ServersSource {
     pub fn enabled(&self) -> bool {
         self.enabled
     }
+
+    pub fn kind(&self) -> &SourceKind {
+        &self.kind
+    }
+
+    pub fn kind_mut(&mut self) -> &mut SourceKind {
+        &mut self.kind
+    }
 }
@@ -1,10 +1,7 @@
 use {
     crate::application::{map::MapName, message::ThumbnailMessage},
     iced::{
-        futures::{
-            channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
-            SinkExt,
-        },
+        futures::channel::mpsc::{unbounded, UnboundedReceiver},
         subscription,
         widget::image,
         Subscription,
@@ -14,45 +11,37 @@ use {
         collections::BTreeMap,
         path::{Path, PathBuf},
         sync::Arc,
-        time::Duration,
     },
 };
 
 enum State {
-    Starting { api_key: String },
+    Starting { client: teamwork::Client, api_key: String },
     Ready(Context),
-    Wait(Duration, Context),
 }
 
 struct Context {
-    requests_sender: UnboundedSender<MapName>,
     requests_receiver: UnboundedReceiver<MapName>,
     client: teamwork::Client,
     teamwork_api_key: String,
 }
 
-pub fn subscription(id: u64, api_key: &str) -> Subscription<ThumbnailMessage> {
-    const SECONDS_TO_WAIT_IF_TOO_MANY_ATTEMPTS: u64 = 60;
-    const SECONDS_TO_WAIT_ON_ERROR: u64 = 5;
-
+/// The thumbnails are fetched in background, the teamwork client sends them
+/// after the requests the user is waiting for.
+pub fn subscription(id: u64, client: teamwork::Client, api_key: &str) -> Subscription<ThumbnailMessage> {
     subscription::unfold(
         id,
         State::Starting {
+            client: client.with_priority(teamwork::Priority::Background),
             api_key: api_key.to_string(),
         },
         |state| async move {
             match state {
-                State::Wait(duration, context) => {
-                    tokio::time::sleep(duration).await;
-                    (ThumbnailMessage::Wait, State::Ready(context))
-                }
-                State::Starting { api_key } => {
+                State::Starting { client, api_key } => {
                     let (sender, receiver) = unbounded();
                     let context = Context {
-                        requests_sender: sender.clone(),
                         requests_receiver: receiver,
                         teamwork_api_key: api_key,
-                        client: teamwork::Client::default(),
+                        client,
                     };
 
                     (ThumbnailMessage::Started(sender), State::Ready(context))
@@ -62,28 +51,9 @@ pub fn subscription(id: u64, api_key: &str) -> Subscription<ThumbnailMessage> {
 
                     let map_name = context.requests_receiver.select_next_some().await;
 
-                    match context
-                        .client
-                        .get_map_thumbnail(&context.teamwork_api_key, map_name.as_str(), image::Handle::from_memory)
-                        .await
-                    {
+                    match fetch_thumbnail(&context, &map_name).await {
                         Ok(thumbnail) => (ThumbnailMessage::Thumbnail(map_name, thumbnail), State::Ready(context)),
-                        Err(teamwork::Error::TooManyAttempts) => {
-                            context.requests_sender.send(map_name.clone()).await.unwrap();
-
-                            (
-                                ThumbnailMessage::Error(map_name, Arc::new(teamwork::Error::TooManyAttempts)),
-                                State::Wait(Duration::from_secs(SECONDS_TO_WAIT_IF_TOO_MANY_ATTEMPTS), context),
-                            )
-                        }
-                        Err(error) => {
-                            context.requests_sender.send(map_name.clone()).await.unwrap();
-
-                            (
-                                ThumbnailMessage::Error(map_name, Arc::new(error)),
-                                State::Wait(Duration::from_secs(SECONDS_TO_WAIT_ON_ERROR), context),
-                            )
-                        }
+                        Err(error) => (ThumbnailMessage::Error(map_name, Arc::new(error)), State::Ready(context)),
                     }
                 }
             }
@@ -91,6 +61,14 @@ pub fn subscription(id: u64, api_key: &str) -> Subscription<ThumbnailMessage> {
     )
 }
 
+/// Fetch a thumbnail exactly as the subscription does.
+async fn fetch_thumbnail(context: &Context, map_name: &MapName) -> Result<Option<image::Handle>, teamwork::Error> {
+    context
+        .client
+        .get_map_thumbnail(&context.teamwork_api_key, map_name.as_str(), image::Handle::from_memory)
+        .await
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ThumbnailCacheError {
     #[error("Invalid directory path '{0}'")]
@@ -183,3 +161,30 @@ impl ThumbnailCache {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{fetch_thumbnail, Context},
+        crate::application::{create_replay_client, map::MapName},
+        iced::futures::{channel::mpsc::unbounded, executor::block_on},
+    };
+
+    #[test]
+    fn test_fetch_thumbnail() {
+        let (_sender, receiver) = unbounded();
+        let context = Context {
+            requests_receiver: receiver,
+            client: create_replay_client().with_priority(teamwork::Priority::Background),
+            teamwork_api_key: "key".to_string(),
+        };
+
+        assert!(block_on(fetch_thumbnail(&context, &MapName::new("pl_upward")))
+            .unwrap()
+            .is_some());
+        assert!(block_on(fetch_thumbnail(&context, &MapName::new("cp_unknown")))
+            .unwrap()
+            .is_none());
+        assert!(block_on(fetch_thumbnail(&context, &MapName::new("ctf_2fort"))).is_err());
+    }
+}
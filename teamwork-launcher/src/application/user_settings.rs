@@ -1,8 +1,15 @@
 use {
-    crate::application::{palettes, paths::get_default_steam_executable},
+    crate::{
+        application::{palettes, paths::get_default_steam_executable, worker_pool},
+        application_version, APPLICATION_NAME,
+    },
     iced::Theme,
     serde::{Deserialize, Serialize},
-    std::fmt::{Display, Formatter},
+    std::{
+        fmt::{Display, Formatter},
+        path::PathBuf,
+        time::Duration,
+    },
 };
 
 #[derive(Serialize, Deserialize, Default, Clone)]
@@ -37,6 +44,88 @@ impl Display for LauncherTheme {
     }
 }
 
+/// How the latency of the servers is measured.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum LatencyBackend {
+    /// ICMP echo, it requires the privilege to open raw sockets and some hosts drop it.
+    #[default]
+    Icmp,
+    /// A2S_INFO query sent to the game port, it works without any privilege.
+    A2s,
+}
+
+impl Display for LatencyBackend {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            LatencyBackend::Icmp => write!(f, "ICMP ping"),
+            LatencyBackend::A2s => write!(f, "Game server query"),
+        }
+    }
+}
+
+/// Settings of the client used to send requests to Teamwork.tf.
+/// They are not exposed in the UI, but they can be edited in the settings file.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TeamworkClientSettings {
+    /// The address of the API, change it to use a mirror or a mock server.
+    pub base_url: String,
+    pub timeout_secs: u64,
+    pub proxy: Option<String>,
+    pub max_retries: u32,
+    /// Replace the styled letters used by some servers to stand out, like `𝐓𝐅𝟐`, by ASCII letters.
+    pub fold_homoglyphs: bool,
+}
+
+impl Default for TeamworkClientSettings {
+    fn default() -> Self {
+        Self {
+            base_url: teamwork::DEFAULT_BASE_URL.to_string(),
+            timeout_secs: teamwork::DEFAULT_TIMEOUT.as_secs(),
+            proxy: None,
+            max_retries: teamwork::RetryPolicy::default().max_retries,
+            fold_homoglyphs: false,
+        }
+    }
+}
+
+impl TeamworkClientSettings {
+    /// How the server names are cleaned, also used for the names queried directly to the servers.
+    pub fn name_normalization(&self) -> teamwork::NameNormalization {
+        teamwork::NameNormalization {
+            fold_homoglyphs: self.fold_homoglyphs,
+            ..Default::default()
+        }
+    }
+
+    pub fn create_client(
+        &self,
+        cache_directory: impl Into<PathBuf>,
+        fixtures: Option<teamwork::FixtureMode>,
+    ) -> Result<teamwork::Client, teamwork::Error> {
+        let mut builder = teamwork::Client::builder()
+            .base_url(&self.base_url)
+            .cache_directory(cache_directory)
+            .timeout(Duration::from_secs(self.timeout_secs))
+            .user_agent(format!("{}/{}", APPLICATION_NAME, application_version()))
+            .retry_policy(teamwork::RetryPolicy {
+                max_retries: self.max_retries,
+                ..Default::default()
+            })
+            .name_normalization(self.name_normalization());
+
+        if let Some(proxy) = self.proxy.as_ref().filter(|proxy| !proxy.trim().is_empty()) {
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(fixtures) = fixtures {
+            builder = builder.fixtures(fixtures);
+        }
+
+        builder.build()
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct UserSettings {
     pub steam_executable_path: String,
@@ -48,6 +137,20 @@ pub struct UserSettings {
     pub max_thumbnails_cache_size_mb: u64,
     #[serde(default)]
     pub window: Option<WindowSettings>,
+    #[serde(default)]
+    pub teamwork_client: TeamworkClientSettings,
+    #[serde(default)]
+    pub latency_backend: LatencyBackend,
+    /// The maximum count of pings and geolocation requests processed at the same time.
+    #[serde(default = "default_concurrent_requests")]
+    pub concurrent_requests: usize,
+    /// A MaxMind database or a CSV file of IP ranges, used to find the countries without sending the addresses
+    /// to a web service.
+    #[serde(default)]
+    pub geolocation_database: Option<PathBuf>,
+    /// Use the web service for the addresses not found in the geolocation database.
+    #[serde(default = "default_geolocation_http_fallback")]
+    pub geolocation_http_fallback: bool,
     teamwork_api_key: String,
 }
 
@@ -62,6 +165,11 @@ impl Default for UserSettings {
             quit_on_launch: false,
             quit_on_copy: false,
             window: None,
+            teamwork_client: TeamworkClientSettings::default(),
+            latency_backend: LatencyBackend::default(),
+            concurrent_requests: default_concurrent_requests(),
+            geolocation_database: None,
+            geolocation_http_fallback: default_geolocation_http_fallback(),
             theme: LauncherTheme::Red,
             /// The maximum size for the thumbnails cache.
             /// 20Mb by default, I observe a usage of ~10MB.
@@ -78,6 +186,14 @@ const fn default_max_thumbnails_cache_size() -> u64 {
     20
 }
 
+const fn default_concurrent_requests() -> usize {
+    worker_pool::DEFAULT_CONCURRENCY
+}
+
+const fn default_geolocation_http_fallback() -> bool {
+    true
+}
+
 impl UserSettings {
     const TEAMWORK_API_KEY_ENV: &'static str = "TEAMWORK_API_KEY";
 
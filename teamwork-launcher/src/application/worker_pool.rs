@@ -0,0 +1,175 @@
+use {
+    iced::futures::{
+        channel::mpsc::UnboundedReceiver,
+        future::{self, BoxFuture, Either},
+        stream::{FuturesUnordered, StreamExt},
+    },
+    std::{
+        cmp::{Ordering, Reverse},
+        collections::BinaryHeap,
+    },
+};
+
+pub const DEFAULT_CONCURRENCY: usize = 16;
+
+/// The priority of a request, the requests with the highest priority are processed first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// The server is not displayed because of the filter.
+    Hidden,
+    Visible,
+    Bookmarked,
+}
+
+/// A message sent to a worker pool.
+#[derive(Debug, Clone)]
+pub enum WorkerRequest<T> {
+    Process(T, Priority),
+    /// Change the maximum count of requests processed at the same time.
+    SetConcurrency(usize),
+}
+
+struct Entry<T> {
+    priority: Priority,
+    /// Keep the order of arrival between the requests with the same priority.
+    order: Reverse<u64>,
+    request: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.priority, self.order).cmp(&(other.priority, other.order))
+    }
+}
+
+/// The requests waiting for a worker, by priority then by order of arrival.
+pub struct PriorityQueue<T> {
+    entries: BinaryHeap<Entry<T>>,
+    next_order: u64,
+}
+
+impl<T> Default for PriorityQueue<T> {
+    fn default() -> Self {
+        Self {
+            entries: BinaryHeap::new(),
+            next_order: 0,
+        }
+    }
+}
+
+impl<T> PriorityQueue<T> {
+    pub fn push(&mut self, request: T, priority: Priority) {
+        self.entries.push(Entry {
+            priority,
+            order: Reverse(self.next_order),
+            request,
+        });
+        self.next_order += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.entries.pop().map(|entry| entry.request)
+    }
+}
+
+/// Process the requests received concurrently, without processing more than `concurrency` requests
+/// at the same time.
+///
+/// The pool is meant to be stored in the state of a subscription: each call to [`WorkerPool::next`]
+/// returns the next result, whatever the order the requests were started.
+pub struct WorkerPool<T, O> {
+    queue: PriorityQueue<T>,
+    running: FuturesUnordered<BoxFuture<'static, O>>,
+    concurrency: usize,
+}
+
+impl<T, O> WorkerPool<T, O> {
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            queue: PriorityQueue::default(),
+            running: FuturesUnordered::new(),
+            concurrency: concurrency.max(1),
+        }
+    }
+
+    /// Wait for the next result.
+    ///
+    /// Meanwhile, the requests received are queued and started by priority as soon as a worker is available.
+    pub async fn next(
+        &mut self,
+        receiver: &mut UnboundedReceiver<WorkerRequest<T>>,
+        mut start: impl FnMut(T) -> BoxFuture<'static, O>,
+    ) -> O {
+        loop {
+            while self.running.len() < self.concurrency {
+                match self.queue.pop() {
+                    Some(request) => self.running.push(start(request)),
+                    None => break,
+                }
+            }
+
+            let request = match self.running.is_empty() {
+                true => receiver.select_next_some().await,
+                false => match future::select(receiver.select_next_some(), self.running.select_next_some()).await {
+                    Either::Left((request, _)) => request,
+                    Either::Right((output, _)) => return output,
+                },
+            };
+
+            match request {
+                WorkerRequest::Process(request, priority) => self.queue.push(request, priority),
+                WorkerRequest::SetConcurrency(concurrency) => self.concurrency = concurrency.max(1),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Priority, PriorityQueue};
+
+    #[test]
+    fn test_pop_by_priority() {
+        let mut queue = PriorityQueue::default();
+
+        queue.push("hidden", Priority::Hidden);
+        queue.push("visible", Priority::Visible);
+        queue.push("bookmarked", Priority::Bookmarked);
+
+        assert_eq!(Some("bookmarked"), queue.pop());
+        assert_eq!(Some("visible"), queue.pop());
+        assert_eq!(Some("hidden"), queue.pop());
+        assert_eq!(None, queue.pop());
+    }
+
+    #[test]
+    fn test_pop_same_priority_in_order() {
+        let mut queue = PriorityQueue::default();
+
+        for request in 0..10 {
+            queue.push(request, Priority::Visible);
+        }
+        queue.push(42, Priority::Bookmarked);
+
+        assert_eq!(Some(42), queue.pop());
+        assert_eq!(
+            (0..10).collect::<Vec<_>>(),
+            std::iter::from_fn(|| queue.pop()).collect::<Vec<_>>()
+        );
+        assert_eq!(None, queue.pop());
+    }
+}
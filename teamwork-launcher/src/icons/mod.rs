@@ -31,6 +31,10 @@ lazy_static! {
     pub static ref FOLDER2_OPEN: SvgHandle = SvgHandle::from_memory(include_bytes!("folder2-open.svg").as_slice());
     pub static ref ARROW_LEFT_SHORT: SvgHandle = SvgHandle::from_memory(include_bytes!("arrow-left-short.svg").as_slice());
     pub static ref ARROW_RIGHT_SHORT: SvgHandle = SvgHandle::from_memory(include_bytes!("arrow-right-short.svg").as_slice());
+    pub static ref ARROW_DOWN_SHORT: SvgHandle = SvgHandle::from_memory(include_bytes!("arrow-down-short.svg").as_slice());
+    pub static ref SQUARE: SvgHandle = SvgHandle::from_memory(include_bytes!("square.svg").as_slice());
+    pub static ref CHECK_SQUARE: SvgHandle = SvgHandle::from_memory(include_bytes!("check-square.svg").as_slice());
+    pub static ref DASH_SQUARE: SvgHandle = SvgHandle::from_memory(include_bytes!("dash-square.svg").as_slice());
     pub static ref PLUGIN: SvgHandle = SvgHandle::from_memory(include_bytes!("plugin.svg").as_slice());
     pub static ref PLUS: SvgHandle = SvgHandle::from_memory(include_bytes!("plus.svg").as_slice());
     pub static ref FLAGS: BTreeMap<String, SvgHandle> = FLAGS_SVG_ICONS
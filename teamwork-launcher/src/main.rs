@@ -5,9 +5,10 @@ use {
     crate::{
         application::{
             blacklist::Blacklist,
-            filter::filter_servers::Filter,
+            filter::{filter_presets::FilterPresets, filter_servers::Filter},
+            network_cache::NetworkCache,
             paths::{DefaultPathsProvider, PathsProvider, TestPathsProvider},
-            servers_source::ServersSource,
+            servers_source::{ServersSource, SourceKind, DEFAULT_MASTER_SERVER_FILTER},
             user_settings::WindowSettings,
             Bookmarks, UserSettings,
         },
@@ -16,7 +17,8 @@ use {
     iced::{window::Position, Application, Settings},
     log::{error, info},
     mods_manager::Registry,
-    std::{fs::OpenOptions, path::Path},
+    source_query::Region,
+    std::{fs::OpenOptions, path::Path, time::SystemTime},
 };
 
 mod application;
@@ -40,7 +42,8 @@ pub const fn application_version() -> &'static str {
 
 fn main() -> iced::Result {
     let testing_mode = std::env::args().any(|arg| arg == "--testing-mode");
-    let settings = load_settings(testing_mode);
+    let teamwork_fixtures = parse_fixture_mode(std::env::args());
+    let settings = load_settings(testing_mode, teamwork_fixtures);
     let configuration_directory = settings.flags.paths.get_configuration_directory();
 
     std::fs::create_dir_all(&configuration_directory).expect("create configuration directory");
@@ -54,10 +57,14 @@ pub struct ApplicationFlags {
     pub blacklist: Blacklist,
     pub user_settings: UserSettings,
     pub filter: Filter,
+    pub filter_presets: FilterPresets,
     pub servers_sources: Vec<ServersSource>,
     pub paths: Box<dyn PathsProvider>,
     pub mods: Registry,
+    pub network_cache: NetworkCache,
     pub testing_mode_enabled: bool,
+    /// Record or replay the responses of Teamwork.tf, see [`parse_fixture_mode`].
+    pub teamwork_fixtures: Option<teamwork::FixtureMode>,
 }
 
 impl Default for ApplicationFlags {
@@ -67,15 +74,38 @@ impl Default for ApplicationFlags {
             blacklist: Blacklist::default(),
             user_settings: UserSettings::default(),
             filter: Filter::default(),
+            filter_presets: FilterPresets::default(),
             servers_sources: Vec::new(),
             testing_mode_enabled: false,
+            teamwork_fixtures: None,
             paths: Box::new(DefaultPathsProvider::new()),
             mods: Registry::new(),
+            network_cache: NetworkCache::default(),
         }
     }
 }
 
-fn load_settings(testing_mode_enabled: bool) -> Settings<ApplicationFlags> {
+/// Parse `--record-fixtures=<directory>` or `--replay-fixtures=<directory>`.
+/// Combined with `--testing-mode`, replaying fixtures allows to run the launcher without network access.
+fn parse_fixture_mode(args: impl Iterator<Item = String>) -> Option<teamwork::FixtureMode> {
+    const RECORD_FIXTURES_ARG: &str = "--record-fixtures=";
+    const REPLAY_FIXTURES_ARG: &str = "--replay-fixtures=";
+
+    args.filter_map(|arg| {
+        if let Some(directory) = arg.strip_prefix(RECORD_FIXTURES_ARG) {
+            Some(teamwork::FixtureMode::Record(directory.into()))
+        } else {
+            arg.strip_prefix(REPLAY_FIXTURES_ARG)
+                .map(|directory| teamwork::FixtureMode::Replay(directory.into()))
+        }
+    })
+    .last()
+}
+
+fn load_settings(
+    testing_mode_enabled: bool,
+    teamwork_fixtures: Option<teamwork::FixtureMode>,
+) -> Settings<ApplicationFlags> {
     let paths: Box<dyn PathsProvider> = match testing_mode_enabled {
         true => Box::new(TestPathsProvider::new()),
         false => Box::new(DefaultPathsProvider::new()),
@@ -85,7 +115,8 @@ fn load_settings(testing_mode_enabled: bool) -> Settings<ApplicationFlags> {
     let bookmarks: Bookmarks = read_file(configuration_directory.join("bookmarks.json")).unwrap_or_default();
     let mut user_settings: UserSettings = read_file(configuration_directory.join("settings.json")).unwrap_or_default();
     let filter: Filter = read_file(configuration_directory.join("filters.json")).unwrap_or_default();
-    let servers_sources: Vec<ServersSource> =
+    let filter_presets: FilterPresets = read_file(configuration_directory.join("filter_presets.json")).unwrap_or_default();
+    let mut servers_sources: Vec<ServersSource> =
         read_file(configuration_directory.join("sources.json")).unwrap_or_else(|error| {
             error!("Failed to read sources.json: {}", error);
 
@@ -99,19 +130,36 @@ fn load_settings(testing_mode_enabled: bool) -> Settings<ApplicationFlags> {
                 ServersSource::new("Medieval Mode", "https://teamwork.tf/api/v1/quickplay/medieval-mode/servers"),
             ]
         });
+
+    // The master server source was added later, it's enabled by default only for the users without API key.
+    if !servers_sources
+        .iter()
+        .any(|source| matches!(source.kind(), SourceKind::MasterServer { .. }))
+    {
+        let mut source = ServersSource::master_server("Steam master server", Region::World, DEFAULT_MASTER_SERVER_FILTER);
+
+        source.set_enabled(!user_settings.has_teamwork_api_key());
+        servers_sources.push(source);
+    }
     let blacklist = read_file(configuration_directory.join("blacklist.json")).unwrap_or_default();
     let mods = read_bin_file(configuration_directory.join("mods.registry")).unwrap_or_default();
+    let mut network_cache: NetworkCache = read_bin_file(configuration_directory.join("network.cache")).unwrap_or_default();
+
+    network_cache.remove_expired(SystemTime::now());
 
     let mut settings = if let Some(window_settings) = user_settings.window.clone() {
         let mut settings = Settings::with_flags(ApplicationFlags {
             bookmarks,
             user_settings,
             filter,
+            filter_presets,
             servers_sources,
             paths,
             testing_mode_enabled,
+            teamwork_fixtures,
             mods,
             blacklist,
+            network_cache,
         });
 
         settings.window.position = Position::Specific(
@@ -129,11 +177,14 @@ fn load_settings(testing_mode_enabled: bool) -> Settings<ApplicationFlags> {
             bookmarks,
             user_settings,
             filter,
+            filter_presets,
             servers_sources,
             paths,
             testing_mode_enabled,
+            teamwork_fixtures,
             mods,
             blacklist,
+            network_cache,
         })
     };
 
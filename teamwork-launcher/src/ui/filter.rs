@@ -1,11 +1,16 @@
 use {
     crate::{
         application::{
-            filter::{filter_servers::Filter, properties_filter::PropertyFilterSwitch},
+            continent::Continent,
+            filter::{
+                country_filter::GroupState, filter_presets::FilterPresets, filter_servers::Filter,
+                properties_filter::PropertyFilterSwitch,
+            },
             game_mode::GameModes,
+            palettes,
             server::Property,
             servers_counts::ServersCounts,
-            FilterMessage, Message,
+            Country, FilterMessage, FilterPresetMessage, Message,
         },
         icons,
         ui::{
@@ -15,47 +20,150 @@ use {
     },
     iced::{
         widget::{
-            checkbox, column, horizontal_space, pick_list, row, slider, text, text_input, tooltip::Position, vertical_space,
+            button, checkbox, column, horizontal_space, pick_list, row, slider, text, text_input, tooltip::Position,
+            vertical_space,
         },
-        Element, Length,
+        Alignment, Element, Length,
     },
     itertools::Itertools,
 };
 
 pub fn text_filter(filter: &Filter) -> Element<Message> {
-    row![
-        text_input("Filter", filter.text.text()).on_input(|text| { Message::Filter(FilterMessage::TextChanged(text)) }),
+    let input = row![
+        text_input("Filter, like: upward map:pl_ players>12 -trade", filter.text.text())
+            .on_input(|text| { Message::Filter(FilterMessage::TextChanged(text)) }),
         svg_button(icons::CLEAR_ICON.clone(), 20).on_press(Message::Filter(FilterMessage::TextChanged(String::new()))),
     ]
+    .spacing(4);
+
+    match filter.text.error() {
+        Some(error) => column![input, text(error).size(14).style(*palettes::RED)].spacing(2).into(),
+        None => input.into(),
+    }
+}
+
+pub fn filter_presets(presets: &FilterPresets) -> Element<Message> {
+    let names: Vec<String> = presets.names().cloned().collect();
+
+    column![
+        pick_list(names, presets.selected().cloned(), |name| {
+            Message::FilterPreset(FilterPresetMessage::Selected(name))
+        })
+        .placeholder("Select a preset")
+        .text_size(16)
+        .padding([2, 4])
+        .width(Length::Fill),
+        text_input("Preset name", &presets.name)
+            .on_input(|name| Message::FilterPreset(FilterPresetMessage::NameChanged(name)))
+            .on_submit(Message::FilterPreset(FilterPresetMessage::SaveAs)),
+        row![
+            button("Save as").on_press(Message::FilterPreset(FilterPresetMessage::SaveAs)),
+            button("Rename").on_press(Message::FilterPreset(FilterPresetMessage::Rename)),
+            button("Delete").on_press(Message::FilterPreset(FilterPresetMessage::Delete)),
+        ]
+        .spacing(4),
+        row![
+            button("Import").on_press(Message::FilterPreset(FilterPresetMessage::Import)),
+            button("Export").on_press(Message::FilterPreset(FilterPresetMessage::Export)),
+        ]
+        .spacing(4),
+    ]
     .spacing(4)
     .into()
 }
 
 pub fn text_filter_options(filter: &Filter) -> Element<Message> {
     column![
-        checkbox("Ignore case", filter.text.ignore_case, |checked| {
+        checkbox("Ignore case", filter.text.ignore_case(), |checked| {
             Message::Filter(FilterMessage::IgnoreCaseChanged(checked))
         }),
-        checkbox("Ignore accents", filter.text.ignore_accents, |checked| {
+        checkbox("Ignore accents", filter.text.ignore_accents(), |checked| {
             Message::Filter(FilterMessage::IgnoreAccentChanged(checked))
+        }),
+        checkbox("Regular expression", filter.text.regex(), |checked| {
+            Message::Filter(FilterMessage::RegexChanged(checked))
         })
     ]
     .spacing(4)
     .into()
 }
 
+fn country_checkbox<'l>(country: &'l Country, checked: bool, counts: &'l ServersCounts) -> Element<'l, Message> {
+    let label = format!("{} ({})", country.name(), counts.countries.get(country).unwrap_or(&0));
+
+    checkbox(label, checked, |checked| {
+        Message::Filter(FilterMessage::CountryChecked(country.clone(), checked))
+    })
+    .into()
+}
+
+/// A tri-state checkbox to check all the countries of the continent, followed by the countries if expanded.
+fn continent_group<'l>(
+    filter: &'l Filter,
+    counts: &'l ServersCounts,
+    continent: Continent,
+    state: GroupState,
+) -> Element<'l, Message> {
+    let expanded = filter.country.is_continent_expanded(continent);
+    let countries: Vec<(&Country, bool)> = filter
+        .country
+        .dictionary
+        .iter()
+        .filter(|(country, _)| country.continent() == Some(continent))
+        .collect();
+    let count: usize = countries
+        .iter()
+        .map(|(country, _)| *counts.countries.get(*country).unwrap_or(&0))
+        .sum();
+    let (state_icon, check) = match state {
+        GroupState::Checked => (icons::CHECK_SQUARE.clone(), false),
+        GroupState::Unchecked => (icons::SQUARE.clone(), true),
+        GroupState::Mixed => (icons::DASH_SQUARE.clone(), true),
+    };
+    let expand_icon = match expanded {
+        true => icons::ARROW_DOWN_SHORT.clone(),
+        false => icons::ARROW_RIGHT_SHORT.clone(),
+    };
+    let header = row![
+        svg_button(expand_icon, 12).on_press(Message::Filter(FilterMessage::ContinentExpanded(continent, !expanded))),
+        svg_button(state_icon, 12).on_press(Message::Filter(FilterMessage::ContinentChecked(continent, check))),
+        text(format!("{} ({})", continent, count)),
+    ]
+    .spacing(4)
+    .align_items(Alignment::Center);
+
+    if !expanded {
+        return header.into();
+    }
+
+    column![
+        header,
+        countries
+            .into_iter()
+            .fold(column![].spacing(4).padding([0, 0, 0, 24]), |column, (country, checked)| {
+                column.push(country_checkbox(country, checked, counts))
+            })
+    ]
+    .spacing(4)
+    .into()
+}
+
 pub fn country_filter<'l>(filter: &'l Filter, counts: &'l ServersCounts) -> Element<'l, Message> {
+    let groups = Continent::ALL
+        .into_iter()
+        .filter_map(|continent| filter.country.continent_state(continent).map(|state| (continent, state)))
+        .fold(column![].spacing(4), |column, (continent, state)| {
+            column.push(continent_group(filter, counts, continent, state))
+        });
+
+    // The countries with an unknown code are not grouped.
     filter
         .country
         .dictionary
         .iter()
-        .map(|(country, checked)| (country, checked, *counts.countries.get(country).unwrap_or(&0)))
-        .fold(column![].spacing(4), |column, (country, checked, count)| {
-            let label = format!("{} ({})", country.name(), count);
-
-            column.push(checkbox(label, checked, |checked| {
-                Message::Filter(FilterMessage::CountryChecked(country.clone(), checked))
-            }))
+        .filter(|(country, _)| country.continent().is_none())
+        .fold(groups, |column, (country, checked)| {
+            column.push(country_checkbox(country, checked, counts))
         })
         .push(vertical_space(Length::Fixed(8.0)))
         .push(checkbox("No country", filter.country.no_countries, |checked| {
@@ -96,16 +204,19 @@ pub fn ping_filter<'l>(filter: &'l Filter, counts: &'l ServersCounts) -> Element
     .into()
 }
 
-pub fn game_modes_filter<'l>(
+fn game_modes_group<'l>(
+    title: &'l str,
     filter: &'l Filter,
     game_modes: &'l GameModes,
     counts: &'l ServersCounts,
-) -> Element<'l, Message> {
-    filter
+    official: bool,
+) -> Option<Element<'l, Message>> {
+    let modes: Vec<_> = filter
         .game_modes
         .dictionary
         .iter()
         .filter_map(|(id, enabled)| game_modes.get(id).map(|mode| (id, mode, enabled)))
+        .filter(|(_, mode, _)| mode.official == official)
         .sorted_by(|(_, l, _), (_, r, _)| l.title.cmp(&r.title))
         .filter_map(|(id, mode, enabled)| {
             let count = *counts.game_modes.get(id).unwrap_or(&0);
@@ -116,14 +227,46 @@ pub fn game_modes_filter<'l>(
 
             Some((id, mode, enabled, count))
         })
-        .fold(column![].spacing(4), |column, (id, mode, enabled, count)| {
+        .collect();
+
+    if modes.is_empty() {
+        return None;
+    }
+
+    let group = modes.into_iter().fold(
+        column![].spacing(4).padding([0, 0, 0, 8]),
+        |column, (id, mode, enabled, count)| {
             let label = format!("{} ({})", mode.title, count);
             let check_box = checkbox(&label, enabled, |checked| {
                 Message::Filter(FilterMessage::GameModeChecked(id.clone(), checked))
             });
 
             column.push(tooltip(check_box, &mode.description, Position::Bottom))
-        })
+        },
+    );
+
+    Some(column![text(title), group].spacing(4).into())
+}
+
+/// The game modes, the official ones first then the ones made by the community.
+pub fn game_modes_filter<'l>(
+    filter: &'l Filter,
+    game_modes: &'l GameModes,
+    counts: &'l ServersCounts,
+) -> Element<'l, Message> {
+    let official_only = checkbox("Official game modes only", filter.game_modes.official_only, |checked| {
+        Message::Filter(FilterMessage::GameModeOfficialOnly(checked))
+    });
+    let mut groups = vec![game_modes_group("Official", filter, game_modes, counts, true)];
+
+    if !filter.game_modes.official_only {
+        groups.push(game_modes_group("Community", filter, game_modes, counts, false));
+    }
+
+    groups
+        .into_iter()
+        .flatten()
+        .fold(column![official_only].spacing(8), |column, group| column.push(group))
         .into()
 }
 
@@ -212,30 +355,41 @@ pub fn players_filter(filter: &Filter) -> Element<Message> {
 }
 
 pub fn maps_filter<'l>(filter: &'l Filter, counts: &'l ServersCounts) -> Element<'l, Message> {
-    column![
-        text_input("Filter", &filter.maps.text).on_input(|text| Message::Filter(FilterMessage::MapNameFilterChanged(text))),
-        filter
-            .maps
-            .dictionary
-            .iter()
-            .filter(|(name, _enabled)| { name.as_str().contains(&filter.maps.text) })
-            .filter_map(|(name, enabled)| {
-                let count = *counts.maps.get(name).unwrap_or(&0);
-
-                if count == 0 {
-                    return None;
-                }
-
-                Some((name, enabled, count))
-            })
-            .fold(column![].spacing(4), |column, (name, enabled, count)| {
-                let label = format!("{} ({})", name.as_str(), count);
-
-                column.push(checkbox(label, enabled, move |checked| {
-                    Message::Filter(FilterMessage::MapChecked(name.clone(), checked))
-                }))
-            })
+    let input = row![
+        text_input("Filter", filter.maps.text()).on_input(|text| Message::Filter(FilterMessage::MapNameFilterChanged(text))),
+        checkbox("Regex", filter.maps.regex(), |checked| {
+            Message::Filter(FilterMessage::MapNameRegexChanged(checked))
+        }),
     ]
+    .spacing(8)
+    .align_items(Alignment::Center);
+
+    let maps = filter
+        .maps
+        .dictionary
+        .iter()
+        .filter(|(name, _enabled)| filter.maps.matches_name(name))
+        .filter_map(|(name, enabled)| {
+            let count = *counts.maps.get(name).unwrap_or(&0);
+
+            if count == 0 {
+                return None;
+            }
+
+            Some((name, enabled, count))
+        })
+        .fold(column![].spacing(4), |column, (name, enabled, count)| {
+            let label = format!("{} ({})", name.as_str(), count);
+
+            column.push(checkbox(label, enabled, move |checked| {
+                Message::Filter(FilterMessage::MapChecked(name.clone(), checked))
+            }))
+        });
+
+    match filter.maps.error() {
+        Some(error) => column![input, text(error).size(14).style(*palettes::RED), maps],
+        None => column![input, maps],
+    }
     .spacing(4)
     .into()
 }
@@ -264,30 +418,57 @@ pub fn providers_filter<'l>(filter: &'l Filter, counts: &'l ServersCounts) -> El
         .into()
 }
 
+/// The conditions on the rules, a condition without value only requires the rule to be defined.
+pub fn rules_filter(filter: &Filter) -> Element<Message> {
+    filter
+        .rules
+        .conditions
+        .iter()
+        .enumerate()
+        .fold(column![].spacing(4), |column, (index, condition)| {
+            column.push(
+                row![
+                    text_input("Rule", &condition.name)
+                        .on_input(move |name| Message::Filter(FilterMessage::RuleNameChanged(index, name))),
+                    text_input("Any value", &condition.value)
+                        .on_input(move |value| Message::Filter(FilterMessage::RuleValueChanged(index, value))),
+                    svg_button(icons::CLEAR_ICON.clone(), 16)
+                        .on_press(Message::Filter(FilterMessage::RuleConditionRemoved(index))),
+                ]
+                .spacing(4),
+            )
+        })
+        .push(svg_button(icons::PLUS.clone(), 16).on_press(Message::Filter(FilterMessage::RuleConditionAdded)))
+        .into()
+}
+
 pub fn server_sort(filter: &Filter) -> Element<Message> {
-    column![
-        row![
-            text("Criterion:"),
-            horizontal_space(Length::Fill),
-            pick_list(&AVAILABLE_CRITERION[..], Some(filter.sort_criterion), |value| {
-                Message::Filter(FilterMessage::SortCriterionChanged(value))
-            })
-            .text_size(16)
-            .padding([2, 4])
-            .width(PICK_LIST_WIDTH),
-        ]
-        .spacing(4),
-        row![
-            text("Direction:"),
-            horizontal_space(Length::Fill),
-            pick_list(&AVAILABLE_DIRECTIONS[..], Some(filter.sort_direction), |value| {
-                Message::Filter(FilterMessage::SortDirectionChanged(value))
-            })
-            .text_size(16)
-            .padding([2, 4])
-            .width(PICK_LIST_WIDTH),
-        ]
-    ]
-    .spacing(4)
-    .into()
+    filter
+        .sort
+        .iter()
+        .enumerate()
+        .fold(column![].spacing(4), |column, (index, key)| {
+            column.push(
+                row![
+                    pick_list(&AVAILABLE_CRITERION[..], Some(key.criterion), move |value| {
+                        Message::Filter(FilterMessage::SortCriterionChanged(index, value))
+                    })
+                    .text_size(16)
+                    .padding([2, 4])
+                    .width(Length::Fill),
+                    pick_list(&AVAILABLE_DIRECTIONS[..], Some(key.direction), move |value| {
+                        Message::Filter(FilterMessage::SortDirectionChanged(index, value))
+                    })
+                    .text_size(16)
+                    .padding([2, 4])
+                    .width(PICK_LIST_WIDTH),
+                    svg_button(icons::CLEAR_ICON.clone(), 16)
+                        .on_press(Message::Filter(FilterMessage::SortKeyRemoved(index))),
+                ]
+                .spacing(4)
+                .align_items(Alignment::Center),
+            )
+        })
+        .push(svg_button(icons::PLUS.clone(), 16).on_press(Message::Filter(FilterMessage::SortKeyAdded)))
+        .into()
 }
@@ -87,6 +87,9 @@ pub fn header_view<'a>(title: &str, view: &Screens, notifications: &'a Notificat
         Screens::Server(_) => {
             row![title_widget, horizontal_space(iced::Length::Fill), back_button(),]
         }
+        Screens::Map(_) => {
+            row![title_widget, horizontal_space(iced::Length::Fill), back_button(),]
+        }
         Screens::Settings => {
             row![title_widget, horizontal_space(iced::Length::Fill), back_button(),]
         }
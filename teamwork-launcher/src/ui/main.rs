@@ -1,7 +1,7 @@
 use {
     crate::{
         application::{
-            filter::filter_servers::Filter,
+            filter::{filter_presets::FilterPresets, filter_servers::Filter},
             game_mode::GameModes,
             progress::Progress,
             screens::{PaneId, PaneView},
@@ -35,6 +35,7 @@ pub struct ViewContext<'l> {
     pub servers: &'l [Server],
     pub bookmarks: &'l Bookmarks,
     pub filter: &'l Filter,
+    pub filter_presets: &'l FilterPresets,
     pub game_modes: &'l GameModes,
     pub counts: &'l ServersCounts,
     pub servers_list: &'l ServersList,
@@ -63,7 +64,7 @@ pub fn view(context: ViewContext) -> Element<Message> {
                     .center_y()
                     .into(),
             },
-            PaneId::Filters => filter_view(context.filter, context.game_modes, context.counts),
+            PaneId::Filters => filter_view(context.filter, context.filter_presets, context.game_modes, context.counts),
         }))
     })
     .on_resize(10, |e| Message::Pane(PaneMessage::Resized(e)));
@@ -82,7 +83,7 @@ pub fn view(context: ViewContext) -> Element<Message> {
 /// The wide view that displays server information.
 fn server_view<'l>(server: &'l Server, bookmarks: &'l Bookmarks, game_modes: &'l GameModes) -> Element<'l, Message> {
     let is_bookmarked = bookmarks.is_bookmarked(&server.ip_port);
-    let ip_port_text = format!("{}:{}", server.ip_port.ip(), server.ip_port.port());
+    let ip_port_text = server.ip_port.to_string();
     let game_modes = widgets::game_modes(game_modes, &server.game_modes);
 
     const BUTTON_SIZE: u16 = 20;
@@ -138,7 +139,7 @@ fn server_view<'l>(server: &'l Server, bookmarks: &'l Bookmarks, game_modes: &'l
 /// This is the compact view that displays server information.
 fn compact_server_view<'l>(server: &'l Server, bookmarks: &'l Bookmarks, game_modes: &'l GameModes) -> Element<'l, Message> {
     let is_bookmarked = bookmarks.is_bookmarked(&server.ip_port);
-    let ip_port_text = format!("{}:{}", server.ip_port.ip(), server.ip_port.port());
+    let ip_port_text = server.ip_port.to_string();
     let game_modes = widgets::game_modes(game_modes, &server.game_modes);
 
     const BUTTON_SIZE: u16 = 20;
@@ -237,9 +238,15 @@ fn servers_view<'l>(
     servers_list.into()
 }
 
-fn filter_view<'l>(filter: &'l Filter, game_modes: &'l GameModes, counts: &'l ServersCounts) -> Element<'l, Message> {
+fn filter_view<'l>(
+    filter: &'l Filter,
+    presets: &'l FilterPresets,
+    game_modes: &'l GameModes,
+    counts: &'l ServersCounts,
+) -> Element<'l, Message> {
     let filter_panel = container(widget::scrollable(
         column![
+            filter_section(Some("Presets"), ui::filter::filter_presets(presets)),
             filter_section(Some("Sort"), ui::filter::server_sort(filter)),
             filter_section(None, ui::filter::bookmark_filter(filter, counts)),
             filter_section_with_switch(
@@ -280,6 +287,12 @@ fn filter_view<'l>(filter: &'l Filter, game_modes: &'l GameModes, counts: &'l Se
                 filter.providers.enabled,
                 |checked| Message::Filter(FilterMessage::ProviderFilterEnabled(checked))
             ),
+            filter_section_with_switch(
+                Some("Rules filter"),
+                ui::filter::rules_filter(filter),
+                filter.rules.enabled,
+                |checked| Message::Filter(FilterMessage::RulesFilterEnabled(checked))
+            ),
         ]
         .padding([0, 14, 0, 0])
         .spacing(4),
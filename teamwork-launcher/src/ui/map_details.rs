@@ -0,0 +1,92 @@
+use {
+    crate::{
+        application::{game_mode::GameModes, map::MapName, screens::MapView, Message},
+        ui::{
+            form::Form,
+            styles::BoxContainerStyle,
+            widgets::{self, spinner},
+        },
+    },
+    iced::{
+        theme,
+        widget::{button, column, container, row, scrollable, text},
+        Element, Length,
+    },
+    teamwork::MapStats,
+};
+
+fn optional_number<'l>(value: Option<u32>) -> Element<'l, Message> {
+    match value {
+        Some(value) => text(value),
+        None => text("Unknown"),
+    }
+    .into()
+}
+
+fn related_maps(stats: &MapStats) -> Element<Message> {
+    stats
+        .related_maps
+        .iter()
+        .fold(row![].spacing(4), |row, map_name| {
+            row.push(button(text(map_name)).on_press(Message::ShowMap(MapName::new(map_name))))
+        })
+        .into()
+}
+
+fn map_stats_form<'l>(view: &'l MapView, stats: &'l MapStats, game_modes: &'l GameModes) -> Element<'l, Message> {
+    Form::new()
+        .spacing(4)
+        .padding(4.into())
+        .push("Official:", |stats: &MapStats| {
+            text(if stats.is_official { "Yes" } else { "No" }).into()
+        })
+        .push_if(!view.game_modes.is_empty(), "Game modes:", |_: &MapStats| {
+            widgets::game_modes(game_modes, &view.game_modes)
+        })
+        .push("Players:", |stats: &MapStats| optional_number(stats.current_players))
+        .push("Servers:", |stats: &MapStats| optional_number(stats.current_servers))
+        .push("Most players:", |stats: &MapStats| optional_number(stats.highest_players))
+        .push("Most servers:", |stats: &MapStats| optional_number(stats.highest_servers))
+        .push_if(stats.popularity_rank.is_some(), "Popularity rank:", |stats: &MapStats| {
+            optional_number(stats.popularity_rank)
+        })
+        .push_if(stats.first_seen.is_some(), "First seen:", |stats: &MapStats| {
+            text(stats.first_seen.as_deref().unwrap_or_default()).into()
+        })
+        .push_if(!stats.related_maps.is_empty(), "Related maps:", related_maps)
+        .view(stats)
+}
+
+fn content<'l>(view: &'l MapView, game_modes: &'l GameModes) -> Element<'l, Message> {
+    let details: Element<'l, Message> = match (&view.stats, &view.error) {
+        (Some(stats), _) => column![
+            map_stats_form(view, stats, game_modes),
+            text(stats.context.description.as_deref().unwrap_or_default()),
+        ]
+        .spacing(8)
+        .into(),
+        (None, Some(error)) => text(error).into(),
+        (None, None) => spinner(Length::Fixed(64.0), 2.0),
+    };
+
+    row![
+        container(widgets::image_thumbnail_content(&view.thumbnail))
+            .width(Length::FillPortion(2))
+            .center_x(),
+        column![
+            text(view.map_name.as_str()).size(28),
+            scrollable(details).height(Length::Fill)
+        ]
+        .spacing(4)
+        .width(Length::Fill),
+    ]
+    .spacing(4)
+    .padding(4)
+    .into()
+}
+
+pub fn view<'l>(view: &'l MapView, game_modes: &'l GameModes) -> Element<'l, Message> {
+    let content = container(content(view, game_modes)).style(theme::Container::Custom(Box::new(BoxContainerStyle)));
+
+    container(content).width(Length::Fill).height(Length::Fill).padding(16).into()
+}
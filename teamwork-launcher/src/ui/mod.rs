@@ -17,6 +17,7 @@ pub mod filter;
 mod form;
 pub mod header;
 pub mod main;
+pub mod map_details;
 pub mod mods_view;
 pub mod server_details;
 pub mod settings;
@@ -34,15 +35,20 @@ const PROPERTY_FILTER_VALUES: [PropertyFilterSwitch; 3] = [
 ];
 
 /// List of criterion exposed by the UI
-pub(crate) const AVAILABLE_CRITERION: [SortCriterion; 8] = [
+pub(crate) const AVAILABLE_CRITERION: [SortCriterion; 13] = [
     SortCriterion::Ip,
     SortCriterion::Name,
     SortCriterion::Country,
+    SortCriterion::Region,
     SortCriterion::Ping,
     SortCriterion::Players,
     SortCriterion::PlayerSlots,
     SortCriterion::FreePlayerSlots,
     SortCriterion::Map,
+    SortCriterion::NextMap,
+    SortCriterion::GameMode,
+    SortCriterion::Provider,
+    SortCriterion::Bookmarked,
 ];
 
 /// List of criterion exposed by the UI
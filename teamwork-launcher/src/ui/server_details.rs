@@ -7,18 +7,21 @@ use {
             map::MapName,
             message::{BlacklistMessage, ScreenshotsMessage},
             palettes,
+            players::format_connected_time,
+            screens::ServerView,
             screenshots::Screenshots,
-            IpPort, Message, PromisedValue, Server,
+            Message, PromisedValue, Server,
         },
         fonts, icons,
         ui::{buttons::svg_button, form::Form, styles::BoxContainerStyle, widgets, THUMBNAIL_CONTENT_FIT},
     },
     iced::{
         theme,
-        widget::{button, column, container, image, row, text, vertical_space, Image},
+        widget::{button, column, container, image, row, scrollable, text, vertical_space, Image},
         Alignment, Element, Length,
     },
     iced_aw::{floating_element::Anchor, FloatingElement},
+    itertools::Itertools,
 };
 use crate::ui::widgets::spinner;
 
@@ -69,6 +72,9 @@ fn server_details_form<'l>(server: &'l Server, game_modes: &'l GameModes) -> Ele
         .push("Players:", |server: &Server| {
             text(&format!("{} / {}", server.current_players_count, server.max_players_count)).into()
         })
+        .push_if(server.raw_name != server.name, "Original name:", |server: &Server| {
+            text(&server.raw_name).into()
+        })
         .push("Map:", |server: &Server| text(server.map.as_str()).into())
         .push_if(server.next_map.is_some(), "Next map:", |server: &Server| {
             text(server.next_map.as_ref().unwrap().as_str()).into()
@@ -85,6 +91,81 @@ fn server_details_form<'l>(server: &'l Server, game_modes: &'l GameModes) -> Ele
         .view(server)
 }
 
+fn players_list(players: &[source_query::Player]) -> Element<Message> {
+    players
+        .iter()
+        .sorted_by(|l, r| r.score.cmp(&l.score))
+        .fold(column![].spacing(2), |column, player| {
+            let name = match player.name.is_empty() {
+                true => text("Connecting..."),
+                false => text(&player.name),
+            };
+
+            column.push(
+                row![
+                    name.width(Length::Fill),
+                    text(player.score).width(Length::Fixed(60.0)),
+                    text(format_connected_time(&player.duration)).width(Length::Fixed(80.0)),
+                ]
+                .spacing(4),
+            )
+        })
+        .into()
+}
+
+/// The players connected to the server, queried directly to the server.
+fn players_view(view: &ServerView) -> Element<Message> {
+    let content: Element<Message> = match (&view.players, &view.players_error) {
+        (PromisedValue::Ready(players), _) if players.is_empty() => text("No players").into(),
+        (PromisedValue::Ready(players), _) => scrollable(players_list(players)).height(Length::Fill).into(),
+        (_, Some(error)) => text(format!("Failed to get the players: {}", error))
+            .style(*palettes::RED)
+            .into(),
+        _ => spinner(Length::Fixed(20.0), 2.0),
+    };
+    let title = match view.players.get() {
+        Some(players) => format!("Players ({})", players.len()),
+        None => String::from("Players"),
+    };
+
+    column![text(title).size(20), content].spacing(4).into()
+}
+
+/// The rules (console variables) of the server, hidden by default as there are a lot of them.
+fn rules_view<'l>(server: &'l Server, view: &'l ServerView) -> Element<'l, Message> {
+    let title = match server.rules.get() {
+        Some(rules) => format!("Rules ({})", rules.len()),
+        None => String::from("Rules"),
+    };
+    let toggle = match view.rules_expanded {
+        true => button("Hide").on_press(Message::ServerRulesExpanded(false)),
+        false => button("Show").on_press(Message::ServerRulesExpanded(true)),
+    };
+    let header = row![text(title).size(20), toggle].spacing(8).align_items(Alignment::Center);
+
+    if !view.rules_expanded {
+        return header.into();
+    }
+
+    let content: Element<Message> = match &server.rules {
+        PromisedValue::Ready(rules) => scrollable(rules.iter().fold(column![].spacing(2), |column, (name, value)| {
+            column.push(
+                row![
+                    text(name).width(Length::FillPortion(1)),
+                    text(value).width(Length::FillPortion(1))
+                ]
+                .spacing(4),
+            )
+        }))
+        .height(Length::Fill)
+        .into(),
+        PromisedValue::Loading => spinner(Length::Fixed(20.0), 2.0),
+        PromisedValue::None => text("The server did not answer").style(*palettes::RED).into(),
+    };
+
+    column![header, content].spacing(4).into()
+}
+
 fn previous_button(screenshots: &Screenshots) -> Element<Message> {
     let mut button = svg_button(icons::ARROW_LEFT_SHORT.clone(), 20);
 
@@ -155,6 +236,7 @@ fn blacklist_button<'l>(server: &'l Server, blacklist: &'l Blacklist) -> Element
 
 fn content<'l>(
     server: &'l Server,
+    view: &'l ServerView,
     game_modes: &'l GameModes,
     screenshots: &'l Screenshots,
     blacklist: &'l Blacklist,
@@ -164,7 +246,13 @@ fn content<'l>(
         column![
             text(&server.name).size(28),
             server_details_form(server, game_modes),
-            blacklist_button(server, blacklist),
+            row![
+                button("Map details").on_press(Message::ShowMap(server.map.clone())),
+                blacklist_button(server, blacklist),
+            ]
+            .spacing(4),
+            players_view(view),
+            rules_view(server, view),
         ]
         .spacing(4)
         .width(Length::Fill),
@@ -177,12 +265,12 @@ fn content<'l>(
 pub fn view<'l>(
     servers: &'l [Server],
     game_modes: &'l GameModes,
-    ip_port: &'l IpPort,
+    view: &'l ServerView,
     screenshots: &'l Screenshots,
     blacklist: &'l Blacklist,
 ) -> Element<'l, Message> {
-    let server = servers.iter().find(|s| &s.ip_port == ip_port).expect("find server");
-    let content = container(content(server, game_modes, screenshots, blacklist))
+    let server = servers.iter().find(|s| s.ip_port == view.ip_port).expect("find server");
+    let content = container(content(server, view, game_modes, screenshots, blacklist))
         .style(theme::Container::Custom(Box::new(BoxContainerStyle)));
 
     container(content).width(Length::Fill).height(Length::Fill).padding(16).into()
@@ -1,7 +1,10 @@
 use {
     crate::{
         application::{
-            servers_source::ServersSource, user_settings::LauncherTheme, BlacklistMessage, Message, UserSettings,
+            palettes,
+            servers_source::{ServersSource, SourceKey, SourceKind},
+            user_settings::{LatencyBackend, LauncherTheme},
+            BlacklistMessage, Message, UserSettings,
         },
         icons,
         ui::{self, blacklist::Blacklist, buttons::svg_button, styles::BoxContainerStyle, SettingsMessage},
@@ -12,14 +15,17 @@ use {
         Element, Length,
     },
     iced_aw::NumberInput,
-    std::path::PathBuf,
+    std::{collections::BTreeMap, path::PathBuf},
 };
 
 const THEMES: [LauncherTheme; 2] = [LauncherTheme::Blue, LauncherTheme::Red];
+const LATENCY_BACKENDS: [LatencyBackend; 2] = [LatencyBackend::Icmp, LatencyBackend::A2s];
+const MAX_CONCURRENT_REQUESTS: usize = 64;
 
 pub fn view<'l>(
     settings: &'l UserSettings,
     sources: &'l [ServersSource],
+    rejected_servers: &'l BTreeMap<SourceKey, teamwork::ValidationReport>,
     blacklist: &'l crate::Blacklist,
     configuration_directory_path: PathBuf,
 ) -> Element<'l, Message> {
@@ -47,13 +53,39 @@ pub fn view<'l>(
                     SettingsMessage::ThemeChanged(value)
                 )),
             ),
+            field(
+                Some("Latency measurement"),
+                Some(
+                    "ICMP ping requires the privilege to open raw sockets, and some hosts do not answer it.\n\
+                The game server query measures the time taken by the server to answer on the game port."
+                ),
+                pick_list(LATENCY_BACKENDS.as_slice(), Some(settings.latency_backend), |value| {
+                    Message::Settings(SettingsMessage::LatencyBackendChanged(value))
+                }),
+            ),
+            field(
+                Some("Concurrent requests"),
+                Some(
+                    "The count of pings and geolocation requests sent at the same time.\n\
+                The bookmarked servers are processed first, then the servers displayed."
+                ),
+                NumberInput::new(settings.concurrent_requests, MAX_CONCURRENT_REQUESTS, |value| {
+                    Message::Settings(SettingsMessage::ConcurrentRequestsChanged(value))
+                }),
+            ),
+            field(
+                Some("Geolocation database"),
+                Some(
+                    "A MaxMind database (.mmdb) or a CSV file of IP ranges, like the free databases of DB-IP.\n\
+                The countries are found locally, without sending the addresses of the servers to a web service."
+                ),
+                geolocation_database_view(settings),
+            ),
             field(
                 Some("Sources"),
                 None,
                 sources.iter().fold(column![].spacing(4), |column, source| {
-                    column.push(checkbox(source.display_name(), source.enabled(), |checked| {
-                        Message::Settings(SettingsMessage::SourceEnabled(source.key().clone(), checked))
-                    }))
+                    column.push(source_view(source, rejected_servers.get(source.key())))
                 })
             ),
             field(
@@ -115,6 +147,88 @@ pub fn view<'l>(
     .into()
 }
 
+fn geolocation_database_view(settings: &UserSettings) -> Element<Message> {
+    let path: Element<Message> = match &settings.geolocation_database {
+        Some(path) => text(path.display()).into(),
+        None => text("No database, the countries are requested to api.country.is").into(),
+    };
+    let mut buttons = row![button("Select file").on_press(Message::Settings(SettingsMessage::PickGeolocationDatabase))]
+        .spacing(ui::DEFAULT_SPACING);
+
+    if settings.geolocation_database.is_some() {
+        buttons = buttons.push(button("Remove").on_press(Message::Settings(SettingsMessage::RemoveGeolocationDatabase)));
+    }
+
+    column![
+        path,
+        buttons,
+        checkbox(
+            "Request the countries not found in the database to api.country.is",
+            settings.geolocation_http_fallback,
+            |checked| Message::Settings(SettingsMessage::GeolocationHttpFallbackChecked(checked))
+        ),
+    ]
+    .spacing(ui::DEFAULT_SPACING)
+    .into()
+}
+
+/// A source with the servers rejected during the last refresh, if any.
+fn source_view<'l>(source: &'l ServersSource, report: Option<&'l teamwork::ValidationReport>) -> Element<'l, Message> {
+    const MAX_REJECTED_SERVERS: usize = 10;
+
+    let checkbox: Element<'l, Message> = match source.kind() {
+        SourceKind::Teamwork => checkbox(source.display_name(), source.enabled(), |checked| {
+            Message::Settings(SettingsMessage::SourceEnabled(source.key().clone(), checked))
+        })
+        .into(),
+        SourceKind::MasterServer { region, filter } => column![
+            checkbox(source.display_name(), source.enabled(), |checked| {
+                Message::Settings(SettingsMessage::SourceEnabled(source.key().clone(), checked))
+            }),
+            row![
+                pick_list(source_query::Region::ALL.as_slice(), Some(*region), |region| {
+                    Message::Settings(SettingsMessage::SourceRegionChanged(source.key().clone(), region))
+                }),
+                text_input("Filter, like \\appid\\440", filter).on_input(|filter| {
+                    Message::Settings(SettingsMessage::SourceFilterChanged(source.key().clone(), filter))
+                }),
+            ]
+            .spacing(4)
+            .padding([0, 0, 0, 24]),
+        ]
+        .spacing(4)
+        .into(),
+    };
+
+    match report.filter(|report| !report.is_empty()) {
+        None => checkbox,
+        Some(report) => {
+            let mut rejected_servers = report
+                .rejected
+                .iter()
+                .take(MAX_REJECTED_SERVERS)
+                .fold(column![].padding([0, 0, 0, 24]), |column, rejected| {
+                    column.push(text(format!("#{}: {}", rejected.index, rejected.reason)).size(14))
+                });
+
+            if report.rejected.len() > MAX_REJECTED_SERVERS {
+                rejected_servers = rejected_servers
+                    .push(text(format!("And {} more", report.rejected.len() - MAX_REJECTED_SERVERS)).size(14));
+            }
+
+            column![
+                checkbox,
+                text(format!("{} servers ignored out of {}:", report.rejected.len(), report.total))
+                    .style(*palettes::RED)
+                    .size(14),
+                rejected_servers,
+            ]
+            .spacing(2)
+            .into()
+        }
+    }
+}
+
 fn section_title(label: &str) -> Element<Message> {
     container(text(label).size(25)).padding(8).into()
 }
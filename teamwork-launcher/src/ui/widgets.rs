@@ -2,7 +2,7 @@ use {
     crate::{
         application::{
             game_mode::{GameMode, GameModeId, GameModes},
-            Country, Message, PromisedValue, Server,
+            palettes, Country, Message, PingStats, PromisedValue, Server,
         },
         icons,
         ui::{styles, styles::ColoredPingIndicatorStyle, THUMBNAIL_CONTENT_FIT},
@@ -60,17 +60,36 @@ pub fn tooltip<'a>(
         .into()
 }
 
-pub fn ping<'a>(value: &PromisedValue<Duration>) -> Element<'a, Message> {
+/// The median ping, flagged if the latency is unstable.
+pub fn ping<'a>(value: &PromisedValue<PingStats>) -> Element<'a, Message> {
     match value {
-        PromisedValue::Ready(duration) => ping_icon(duration, 20),
+        PromisedValue::Ready(stats) if stats.is_unstable() => row![
+            ping_icon(&stats.median, 20),
+            tooltip(
+                text("!").style(*palettes::RED),
+                format!("Unstable: {}ms of jitter, {}% lost", stats.jitter.as_millis(), stats.loss),
+                iced_tooltip::Position::Bottom,
+            ),
+        ]
+        .spacing(2)
+        .into(),
+        PromisedValue::Ready(stats) => ping_icon(&stats.median, 20),
         PromisedValue::Loading => spinner(Length::Fixed(20.0), 2.0),
         PromisedValue::None => text("Timeout").into(),
     }
 }
 
-pub fn ping_time<'a>(value: &PromisedValue<Duration>) -> Element<'a, Message> {
+pub fn ping_time<'a>(value: &PromisedValue<PingStats>) -> Element<'a, Message> {
     match value {
-        PromisedValue::Ready(duration) => text(format!("{}ms", duration.as_millis())).into(),
+        PromisedValue::Ready(stats) => text(format!(
+            "{}ms (min {}ms, max {}ms, jitter {}ms, {}% lost)",
+            stats.median.as_millis(),
+            stats.min.as_millis(),
+            stats.max.as_millis(),
+            stats.jitter.as_millis(),
+            stats.loss
+        ))
+        .into(),
         PromisedValue::Loading => spinner(Length::Fixed(20.0), 2.0),
         PromisedValue::None => horizontal_space(Length::Shrink).into(),
     }
@@ -0,0 +1,211 @@
+use {
+    crate::{
+        cache::ResponseCache, fixtures::Fixtures, CachePolicy, Client, Error, FixtureMode, NameNormalization, RateLimit,
+    },
+    std::{path::PathBuf, time::Duration},
+};
+
+/// The address of the official Teamwork.tf API.
+pub const DEFAULT_BASE_URL: &str = "https://teamwork.tf/api/v1";
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Describe how many times a request is sent again when it fails because of
+/// a network problem (timeout, connection refused...).
+/// The delay between two attempts is doubled after each failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub const fn none() -> Self {
+        Self {
+            max_retries: 0,
+            initial_delay: Duration::ZERO,
+        }
+    }
+
+    /// The delay to wait before the attempt number `attempt` (starting at 1 for the first retry).
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Create a configured [`Client`].
+///
+/// ```no_run
+/// let client = teamwork::Client::builder()
+///     .base_url("http://localhost:8080/api/v1")
+///     .timeout(std::time::Duration::from_secs(20))
+///     .build()
+///     .expect("build client");
+/// ```
+pub struct ClientBuilder {
+    base_url: String,
+    timeout: Duration,
+    user_agent: String,
+    proxy: Option<String>,
+    retry_policy: RetryPolicy,
+    rate_limit: RateLimit,
+    cache_directory: Option<PathBuf>,
+    cache_policy: CachePolicy,
+    fixtures: Option<FixtureMode>,
+    name_normalization: NameNormalization,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limit: RateLimit::default(),
+            cache_directory: None,
+            cache_policy: CachePolicy::default(),
+            fixtures: None,
+            name_normalization: NameNormalization::default(),
+        }
+    }
+
+    /// The address of the API, for example `https://teamwork.tf/api/v1`.
+    /// Every request sent to the official API is redirected to this address.
+    pub fn base_url(mut self, base_url: impl ToString) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl ToString) -> Self {
+        self.user_agent = user_agent.to_string();
+        self
+    }
+
+    /// Send all the requests through a proxy, for example `socks5://127.0.0.1:9050`.
+    pub fn proxy(mut self, proxy_url: impl ToString) -> Self {
+        self.proxy = Some(proxy_url.to_string());
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// The request budget shared by all the clones of the client.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Store the responses of Teamwork.tf in this directory.
+    /// Without a cache directory, every request is sent to Teamwork.tf.
+    pub fn cache_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.cache_directory = Some(directory.into());
+        self
+    }
+
+    pub fn cache_policy(mut self, cache_policy: CachePolicy) -> Self {
+        self.cache_policy = cache_policy;
+        self
+    }
+
+    /// Record the responses in a directory, or replay them without network access.
+    /// The cache is disabled when the fixtures are used, so every request is recorded.
+    pub fn fixtures(mut self, mode: FixtureMode) -> Self {
+        self.fixtures = Some(mode);
+        self
+    }
+
+    /// How the names of the servers are cleaned.
+    pub fn name_normalization(mut self, name_normalization: NameNormalization) -> Self {
+        self.name_normalization = name_normalization;
+        self
+    }
+
+    pub fn build(self) -> Result<Client, Error> {
+        let base_url = self.base_url.trim().trim_end_matches('/').to_string();
+
+        url::Url::parse(&base_url).map_err(|error| Error::InvalidBaseUrl(base_url.clone(), error))?;
+
+        let mut builder = reqwest::Client::builder().timeout(self.timeout).user_agent(self.user_agent);
+
+        if let Some(proxy_url) = self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy_url)?);
+        }
+
+        let mut client = Client::new(
+            builder.build()?,
+            base_url,
+            self.retry_policy,
+            self.rate_limit,
+            self.cache_directory
+                .filter(|_| self.fixtures.is_none())
+                .map(ResponseCache::new),
+            self.cache_policy,
+            self.fixtures.map(Fixtures::new),
+        );
+
+        client.name_normalization = self.name_normalization;
+
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{ClientBuilder, RetryPolicy},
+        std::time::Duration,
+        test_case::test_case,
+    };
+
+    #[test_case("http://localhost:8080/api/v1", "http://localhost:8080/api/v1"; "unchanged")]
+    #[test_case("http://localhost:8080/api/v1/", "http://localhost:8080/api/v1"; "trailing slash")]
+    #[test_case(" http://localhost/ ", "http://localhost"; "trimmed")]
+    fn test_base_url(input: &str, expected: &str) {
+        let client = ClientBuilder::new().base_url(input).build().unwrap();
+
+        assert_eq!(expected, client.base_url());
+    }
+
+    #[test]
+    fn test_invalid_base_url() {
+        assert!(ClientBuilder::new().base_url("not an url").build().is_err());
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            initial_delay: Duration::from_millis(100),
+        };
+
+        assert_eq!(Duration::from_millis(100), policy.delay(1));
+        assert_eq!(Duration::from_millis(200), policy.delay(2));
+        assert_eq!(Duration::from_millis(400), policy.delay(3));
+    }
+}
@@ -0,0 +1,180 @@
+use {
+    crate::Error,
+    log::warn,
+    serde::{Deserialize, Serialize},
+    std::{
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// How long the responses of each endpoint are used without asking Teamwork.tf.
+///
+/// When a response is too old, it is revalidated with a conditional request (ETag / If-Modified-Since):
+/// Teamwork.tf answers with an empty body if the response did not change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CachePolicy {
+    pub game_modes: Duration,
+    pub map_stats: Duration,
+    pub map_thumbnails: Duration,
+    pub servers: Duration,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        const DAY: Duration = Duration::from_secs(60 * 60 * 24);
+
+        Self {
+            game_modes: DAY,
+            map_stats: DAY,
+            map_thumbnails: DAY * 7,
+            // The servers change all the time, they are always revalidated.
+            servers: Duration::ZERO,
+        }
+    }
+}
+
+/// A response stored on the disk.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CacheEntry {
+    /// The address of the request, without the API key.
+    pub key: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// When the response was received or revalidated, in seconds since UNIX epoch.
+    pub stored_at: u64,
+    pub body: String,
+}
+
+impl CacheEntry {
+    pub fn new(key: impl ToString, etag: Option<String>, last_modified: Option<String>, body: String) -> Self {
+        Self {
+            key: key.to_string(),
+            etag,
+            last_modified,
+            stored_at: now_secs(),
+            body,
+        }
+    }
+
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        now_secs().saturating_sub(self.stored_at) < max_age.as_secs()
+    }
+
+    pub fn touch(&mut self) {
+        self.stored_at = now_secs();
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Store the responses in a directory, one file per request.
+/// Failing to read or write the cache is not an error, the request is just sent to Teamwork.tf.
+pub(crate) struct ResponseCache {
+    directory: PathBuf,
+}
+
+impl ResponseCache {
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn load(&self, key: &str) -> Option<CacheEntry> {
+        let path = self.entry_path(key);
+        let text = std::fs::read_to_string(&path).ok()?;
+
+        match serde_json::from_str::<CacheEntry>(&text) {
+            // Two keys can have the same hash, in this case the entry is just replaced.
+            Ok(entry) if entry.key == key => Some(entry),
+            Ok(_) => None,
+            Err(error) => {
+                warn!("Invalid cache entry '{}': {}", path.display(), error);
+                None
+            }
+        }
+    }
+
+    pub fn store(&self, entry: &CacheEntry) {
+        let path = self.entry_path(&entry.key);
+
+        if let Err(error) = self.write_entry(&path, entry) {
+            warn!("Failed to write cache entry '{}': {}", path.display(), error);
+        }
+    }
+
+    fn write_entry(&self, path: &Path, entry: &CacheEntry) -> Result<(), Error> {
+        std::fs::create_dir_all(&self.directory)?;
+        std::fs::write(path, serde_json::to_string(entry)?)?;
+
+        Ok(())
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{:016x}.json", fnv1a(key.as_bytes())))
+    }
+}
+
+/// The hash must be stable between two executions, so the hashers of the standard library can't be used.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes
+        .iter()
+        .fold(OFFSET_BASIS, |hash, byte| (hash ^ *byte as u64).wrapping_mul(PRIME))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{fnv1a, CacheEntry, ResponseCache},
+        std::time::Duration,
+        tempdir::TempDir,
+    };
+
+    #[test]
+    fn test_store_and_load() {
+        let directory = TempDir::new("test_store_and_load").unwrap();
+        let cache = ResponseCache::new(directory.path().join("cache"));
+        let entry = CacheEntry::new(
+            "https://teamwork.tf/api/v1/quickplay?key=***",
+            Some("\"abc\"".to_string()),
+            None,
+            "{}".to_string(),
+        );
+
+        assert_eq!(None, cache.load(&entry.key));
+
+        cache.store(&entry);
+
+        assert_eq!(Some(entry.clone()), cache.load(&entry.key));
+        assert_eq!(None, cache.load("https://teamwork.tf/api/v1/map-stats/map/pl_upward?key=***"));
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let mut entry = CacheEntry::new("key", None, None, String::new());
+
+        assert!(entry.is_fresh(Duration::from_secs(60)));
+        assert!(!entry.is_fresh(Duration::ZERO));
+
+        entry.stored_at -= 120;
+        assert!(!entry.is_fresh(Duration::from_secs(60)));
+
+        entry.touch();
+        assert!(entry.is_fresh(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_fnv1a_is_stable() {
+        assert_eq!(0xcbf29ce484222325, fnv1a(b""));
+        assert_eq!(0xaf63dc4c8601ec8c, fnv1a(b"a"));
+    }
+}
@@ -0,0 +1,129 @@
+use {
+    crate::{Error, RawResponse},
+    reqwest::StatusCode,
+    serde::{Deserialize, Serialize},
+    std::{
+        path::{Path, PathBuf},
+        time::Duration,
+    },
+};
+
+/// Record the responses of Teamwork.tf in a directory, or replay them without network access.
+///
+/// Each response is stored in two files named after the request: a `.json` file with the status and the headers,
+/// and a `.body` file with the body as received.
+/// The API key is never written, so the fixtures can be shared.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Send the requests to Teamwork.tf and write the responses in the directory.
+    Record(PathBuf),
+    /// Read the responses from the directory, no request is sent.
+    /// A request without fixture fails with [`Error::MissingFixture`].
+    Replay(PathBuf),
+}
+
+#[derive(Serialize, Deserialize)]
+struct FixtureHeader {
+    /// The address of the request, without the API key.
+    address: String,
+    status: u16,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    retry_after_secs: Option<u64>,
+}
+
+pub(crate) struct Fixtures {
+    mode: FixtureMode,
+}
+
+impl Fixtures {
+    pub fn new(mode: FixtureMode) -> Self {
+        Self { mode }
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self.mode, FixtureMode::Replay(_))
+    }
+
+    pub fn is_record(&self) -> bool {
+        matches!(self.mode, FixtureMode::Record(_))
+    }
+
+    /// `key` is the address of the request relative to the base URL, without the API key.
+    pub fn replay(&self, key: &str) -> Result<RawResponse, Error> {
+        let (header_path, body_path) = self.paths(key);
+        let header: FixtureHeader = match std::fs::read_to_string(header_path) {
+            Ok(text) => serde_json::from_str(&text)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::MissingFixture(key.to_string()));
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        Ok(RawResponse {
+            status: StatusCode::from_u16(header.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            body: std::fs::read(body_path)?,
+            etag: header.etag,
+            last_modified: header.last_modified,
+            retry_after: header.retry_after_secs.map(Duration::from_secs),
+        })
+    }
+
+    pub fn record(&self, key: &str, response: &RawResponse) -> Result<(), Error> {
+        let (header_path, body_path) = self.paths(key);
+        let header = FixtureHeader {
+            address: key.to_string(),
+            status: response.status.as_u16(),
+            etag: response.etag.clone(),
+            last_modified: response.last_modified.clone(),
+            retry_after_secs: response.retry_after.map(|retry_after| retry_after.as_secs()),
+        };
+
+        std::fs::create_dir_all(self.directory())?;
+        std::fs::write(header_path, serde_json::to_string_pretty(&header)?)?;
+        std::fs::write(body_path, &response.body)?;
+
+        Ok(())
+    }
+
+    fn directory(&self) -> &Path {
+        match &self.mode {
+            FixtureMode::Record(directory) | FixtureMode::Replay(directory) => directory,
+        }
+    }
+
+    fn paths(&self, key: &str) -> (PathBuf, PathBuf) {
+        let name = file_name(key);
+
+        (
+            self.directory().join(format!("{}.json", name)),
+            self.directory().join(format!("{}.body", name)),
+        )
+    }
+}
+
+/// Make a readable file name from the address of a request.
+/// For example `/map-stats/map/pl_upward` becomes `map-stats_map_pl_upward`.
+fn file_name(key: &str) -> String {
+    key.chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+            true => c,
+            false => '_',
+        })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::file_name, test_case::test_case};
+
+    #[test_case("/quickplay", "quickplay")]
+    #[test_case("/map-stats/map/pl_upward", "map-stats_map_pl_upward")]
+    #[test_case("/quickplay/server?ip=1.2.3.4&port=27015", "quickplay_server_ip_1.2.3.4_port_27015")]
+    #[test_case("https://teamwork.tf/images/thumbnail.jpg", "https___teamwork.tf_images_thumbnail.jpg")]
+    fn test_file_name(key: &str, expected: &str) {
+        assert_eq!(expected, file_name(key));
+    }
+}
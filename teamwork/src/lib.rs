@@ -1,17 +1,36 @@
 use {
     self::models::GameModes,
-    async_mutex::Mutex,
-    log::{error, trace},
+    cache::{CacheEntry, ResponseCache},
+    fixtures::Fixtures,
+    log::{trace, warn},
+    reqwest::{
+        header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER},
+        StatusCode,
+    },
+    scheduler::Scheduler,
     serde::{de::DeserializeOwned, Deserialize},
-    std::{collections::BTreeMap, net::Ipv4Addr, sync::Arc, time::Duration},
+    std::{sync::Arc, time::Duration},
+    url_with_key::HIDDEN_API_KEY,
 };
 pub use {
-    models::{GameMode, Server},
+    builder::{ClientBuilder, RetryPolicy, DEFAULT_BASE_URL, DEFAULT_TIMEOUT},
+    cache::CachePolicy,
+    fixtures::FixtureMode,
+    models::{GameMode, MapContext, MapStats, Server},
+    normalization::NameNormalization,
+    scheduler::{Priority, RateLimit},
     url_with_key::UrlWithKey,
+    validation::{RejectedEntry, ValidationReport},
 };
 
+mod builder;
+mod cache;
+mod fixtures;
+mod normalization;
 mod parsing;
+mod scheduler;
 mod url_with_key;
+mod validation;
 
 #[derive(thiserror::Error, Debug, enum_as_inner::EnumAsInner)]
 pub enum Error {
@@ -25,28 +44,85 @@ pub enum Error {
     HttpRequest(#[from] reqwest::Error),
     #[error("Failed to get {address} with error: {error}")]
     TeamworkError { address: String, error: String },
-    #[error("Too Many Attempts")]
-    TooManyAttempts,
+    #[error("The Teamwork.tf API key is invalid or expired (HTTP {status})")]
+    InvalidApiKey { status: StatusCode },
+    #[error("Too many requests sent to Teamwork.tf{}", format_retry_after(.retry_after))]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("Not found: {address}")]
+    NotFound { address: String },
+    #[error("Teamwork.tf failed to answer {address} (HTTP {status})")]
+    ServerError { address: String, status: StatusCode },
+    #[error("Unexpected HTTP status {status} for {address}")]
+    HttpStatus { address: String, status: StatusCode },
+    #[error("Invalid base URL '{0}': {1}")]
+    InvalidBaseUrl(String, url::ParseError),
+    #[error("No fixture recorded for '{0}'")]
+    MissingFixture(String),
+}
+
+impl Error {
+    /// The HTTP status code of the response that caused this error, if any.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::InvalidApiKey { status } => Some(*status),
+            Error::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            Error::NotFound { .. } => Some(StatusCode::NOT_FOUND),
+            Error::ServerError { status, .. } | Error::HttpStatus { status, .. } => Some(*status),
+            Error::HttpRequest(error) => error.status(),
+            _ => None,
+        }
+    }
+
+    /// Make the error matching a HTTP status, returns None if the status is a success.
+    fn from_status(status: StatusCode, retry_after: Option<Duration>, address: String) -> Option<Error> {
+        if status.is_success() {
+            return None;
+        }
+
+        Some(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::InvalidApiKey { status },
+            StatusCode::TOO_MANY_REQUESTS => Error::RateLimited { retry_after },
+            StatusCode::NOT_FOUND => Error::NotFound { address },
+            status if status.is_server_error() => Error::ServerError { address, status },
+            status => Error::HttpStatus { address, status },
+        })
+    }
+}
+
+fn format_retry_after(retry_after: &Option<Duration>) -> String {
+    match retry_after {
+        Some(retry_after) => format!(", retry after {} seconds", retry_after.as_secs()),
+        None => String::new(),
+    }
+}
+
+/// Parse the value of the header Retry-After.
+/// Only the delay in seconds is supported, dates are ignored.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
 }
 
 #[derive(Clone)]
 /// Notice the client is Send + Sync and it must stay as is (a unit test checks that).
+///
+/// The client is cheap to clone, all the clones share the same connection pool and the same request budget
+/// (see [`RateLimit`]).
+/// Use [`Client::builder`] to configure it.
 pub struct Client {
     reqwest: reqwest::Client,
-    thumbnail_urls_cache: Arc<Mutex<BTreeMap<String, String>>>,
+    base_url: String,
+    retry_policy: RetryPolicy,
+    scheduler: Arc<Scheduler>,
+    priority: Priority,
+    cache: Option<Arc<ResponseCache>>,
+    cache_policy: CachePolicy,
+    fixtures: Option<Arc<Fixtures>>,
+    name_normalization: NameNormalization,
 }
 
 impl Default for Client {
     fn default() -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(5))
-            .build()
-            .expect("build reqwest client");
-
-        Self {
-            reqwest: client,
-            thumbnail_urls_cache: Arc::new(Mutex::new(BTreeMap::new())),
-        }
+        ClientBuilder::new().build().expect("build default client")
     }
 }
 
@@ -62,42 +138,131 @@ struct ThumbnailResponse {
     pub url: Option<String>,
 }
 
-const TEAMWORK_TF_QUICKPLAY_API: &str = "https://teamwork.tf/api/v1/quickplay";
-const TEAMWORK_TF_MAP_THUMBNAIL_API: &str = "https://teamwork.tf/api/v1/map-stats/mapthumbnail";
-const TEAMWORK_TF_MAP_STATS_API: &str = "https://teamwork.tf/api/v1/map-stats/map";
-
-const TEAMWORK_TOO_MANY_ATTEMPTS: &str = "Too Many Attempts.";
+/// The body of a response, with the headers needed to revalidate it later.
+pub(crate) struct RawResponse {
+    status: StatusCode,
+    body: Vec<u8>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    retry_after: Option<Duration>,
+}
 
-mod map_details_response_json {
-    use serde::Deserialize;
+impl RawResponse {
+    async fn read(response: reqwest::Response) -> Result<Self, Error> {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let retry_after = header(RETRY_AFTER).as_deref().and_then(parse_retry_after);
+
+        Ok(Self {
+            status: response.status(),
+            body: response.bytes().await?.to_vec(),
+            etag,
+            last_modified,
+            retry_after,
+        })
+    }
 
-    #[derive(Deserialize, Debug, Clone)]
-    pub struct MapContext {
-        pub screenshots: Vec<String>,
+    /// True if the response stored in the cache is still valid, in this case the body is empty.
+    fn is_not_modified(&self) -> bool {
+        self.status == StatusCode::NOT_MODIFIED
     }
 
-    #[derive(Deserialize, Debug, Clone)]
-    pub struct Response {
-        #[serde(rename = "map")]
-        pub name: String,
-        #[serde(rename = "thumbnail")]
-        pub thumbnail_url: String,
-        pub context: Option<MapContext>,
-        #[serde(rename = "official_map")]
-        pub is_official: bool,
+    /// Turn a response with an error status into an [`Error`].
+    fn check(self, address: String) -> Result<Self, Error> {
+        if self.is_not_modified() {
+            return Ok(self);
+        }
+
+        if let Some(error) = Error::from_status(self.status, self.retry_after, address) {
+            return Err(error);
+        }
+
+        // Teamwork.tf may also answer with this text instead of a status 429.
+        if self.body == TEAMWORK_TOO_MANY_ATTEMPTS.as_bytes() {
+            return Err(Error::RateLimited { retry_after: None });
+        }
+
+        Ok(self)
     }
 }
 
+const TEAMWORK_TF_QUICKPLAY_API: &str = "quickplay";
+const TEAMWORK_TF_QUICKPLAY_SERVER_API: &str = "quickplay/server";
+const TEAMWORK_TF_MAP_THUMBNAIL_API: &str = "map-stats/mapthumbnail";
+const TEAMWORK_TF_MAP_STATS_API: &str = "map-stats/map";
+
+const TEAMWORK_TOO_MANY_ATTEMPTS: &str = "Too Many Attempts.";
+
 impl Client {
-    async fn get_map_details(&self, api_key: &str, map_name: &str) -> Result<map_details_response_json::Response, Error> {
-        let query_url = UrlWithKey::new(format!("{}/{}", TEAMWORK_TF_MAP_STATS_API, map_name), api_key);
-        let raw_text = self.get_raw_text(&query_url).await?;
+    pub(crate) fn new(
+        reqwest: reqwest::Client,
+        base_url: String,
+        retry_policy: RetryPolicy,
+        rate_limit: RateLimit,
+        cache: Option<ResponseCache>,
+        cache_policy: CachePolicy,
+        fixtures: Option<Fixtures>,
+    ) -> Self {
+        Self {
+            reqwest,
+            base_url,
+            retry_policy,
+            scheduler: Arc::new(Scheduler::new(rate_limit)),
+            priority: Priority::default(),
+            cache: cache.map(Arc::new),
+            cache_policy,
+            fixtures: fixtures.map(Arc::new),
+            name_normalization: NameNormalization::default(),
+        }
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
 
-        if raw_text == TEAMWORK_TOO_MANY_ATTEMPTS {
-            return Err(Error::TooManyAttempts);
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Make a clone of this client that sends its requests with another priority.
+    /// The clone still shares the request budget with this client.
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        Self {
+            priority,
+            ..self.clone()
         }
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Make the address of an endpoint of the API.
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    /// Redirect an address of the official API to the configured base URL.
+    /// Other addresses are returned unchanged.
+    fn rebase_url(&self, url: String) -> String {
+        match url.strip_prefix(DEFAULT_BASE_URL) {
+            Some(path) if self.base_url != DEFAULT_BASE_URL => format!("{}{}", self.base_url, path),
+            _ => url,
+        }
+    }
 
-        Self::try_parse_response::<map_details_response_json::Response>(&raw_text, &query_url)
+    /// Get the statistics of a map.
+    pub async fn get_map_stats(&self, api_key: &str, map_name: &str) -> Result<MapStats, Error> {
+        let query_url = UrlWithKey::new(self.endpoint(&format!("{}/{}", TEAMWORK_TF_MAP_STATS_API, map_name)), api_key);
+
+        self.get(&query_url, self.cache_policy.map_stats).await
     }
 
     pub async fn get_map_screenshots<I: Send + Sync, F: Fn(Vec<u8>) -> I>(
@@ -106,13 +271,11 @@ impl Client {
         map_name: &str,
         convert_to_image: F,
     ) -> Result<Vec<I>, Error> {
-        let details = self.get_map_details(api_key, map_name).await?;
+        let stats = self.get_map_stats(api_key, map_name).await?;
         let mut images = Vec::new();
 
-        if let Some(context) = details.context {
-            for screenshot_url in context.screenshots {
-                images.push(self.get_image(&screenshot_url, &convert_to_image).await?);
-            }
+        for screenshot_url in stats.context.screenshots {
+            images.push(self.get_image(&screenshot_url, &convert_to_image).await?);
         }
 
         Ok(images)
@@ -138,96 +301,200 @@ impl Client {
     }
 
     async fn get_image<I: Send + Sync, F: Fn(Vec<u8>) -> I>(&self, url: &str, convert_to_image: &F) -> Result<I, Error> {
-        let bytes = self.reqwest.get(url).send().await?.bytes().await?;
+        let response = self.fetch(self.rebase_url(url.to_string()), url.to_string(), None).await?;
 
-        Ok(convert_to_image(bytes.as_ref().to_vec()))
+        Ok(convert_to_image(response.body))
     }
 
     pub async fn get_map_thumbnail_url(&self, api_key: &str, map_name: &str) -> Result<Option<String>, Error> {
         if api_key.is_empty() {
             return Err(Error::NoTeamworkApiKey);
         }
-        let map_name = map_name.to_string();
-        let image_url = match self.thumbnail_urls_cache.lock().await.get(&map_name) {
-            Some(thumbnail_url) => Some(thumbnail_url.clone()),
-            None => {
-                let query_url = UrlWithKey::new(format!("{}/{}", TEAMWORK_TF_MAP_THUMBNAIL_API, map_name), api_key);
-
-                self.get_thumbnail_response(&query_url).await?.url
-            }
-        };
+        let query_url = UrlWithKey::new(
+            self.endpoint(&format!("{}/{}", TEAMWORK_TF_MAP_THUMBNAIL_API, map_name)),
+            api_key,
+        );
+        let response: ThumbnailResponse = self.get(&query_url, self.cache_policy.map_thumbnails).await?;
 
-        Ok(image_url)
+        Ok(response.url)
     }
 
     pub async fn get_game_modes(&self, api_key: &str) -> Result<Vec<GameMode>, Error> {
-        let url = UrlWithKey::new(TEAMWORK_TF_QUICKPLAY_API, api_key);
-        let modes: GameModes = self.get(&url).await?;
+        let url = UrlWithKey::new(self.endpoint(TEAMWORK_TF_QUICKPLAY_API), api_key);
+        let modes: GameModes = self.get(&url, self.cache_policy.game_modes).await?;
         let mut game_modes: Vec<GameMode> = Vec::new();
 
-        game_modes.extend(modes.official);
+        game_modes.extend(modes.official.into_iter().map(|mode| GameMode { official: true, ..mode }));
         game_modes.extend(modes.community);
 
         Ok(game_modes)
     }
 
-    pub async fn get_server(&self, ip: Ipv4Addr, port: u16, api_key: &str) -> Result<Option<Server>, Error> {
-        let url = UrlWithKey::new(
-            format!("https://teamwork.tf/api/v1/quickplay/server?ip={0}&port={1}", ip, port),
-            api_key,
-        );
-        let mut servers: Vec<Server> = self.get(&url).await?;
+    /// `host` is the address of the server as returned by Teamwork.tf, an IPv4, an IPv6 or a host name.
+    pub async fn get_server(&self, host: &str, port: u16, api_key: &str) -> Result<Option<Server>, Error> {
+        let mut address = url::Url::parse(&self.endpoint(TEAMWORK_TF_QUICKPLAY_SERVER_API))
+            .map_err(|error| Error::InvalidBaseUrl(self.base_url.clone(), error))?;
+
+        // The host is encoded, an IPv6 or a host name can't change the query.
+        address
+            .query_pairs_mut()
+            .append_pair("ip", host)
+            .append_pair("port", &port.to_string());
+
+        let url = UrlWithKey::new(address, api_key);
+        let (mut servers, report) = self.get_server_list(&url).await?;
+
+        if !report.is_empty() {
+            warn!("{}", report);
+        }
 
         Ok(servers.pop())
     }
 
-    pub async fn get_servers(&self, url: UrlWithKey) -> Result<Vec<Server>, Error> {
-        let mut servers: Vec<Server> = self.get(&url).await?;
+    /// Returns the valid servers and a report about the servers rejected.
+    /// An invalid server does not prevent the other servers from being returned.
+    pub async fn get_servers(&self, url: UrlWithKey) -> Result<(Vec<Server>, ValidationReport), Error> {
+        self.get_server_list(&url).await
+    }
+
+    async fn get_server_list(&self, url: &UrlWithKey) -> Result<(Vec<Server>, ValidationReport), Error> {
+        let values: Vec<serde_json::Value> = self.get(url, self.cache_policy.servers).await?;
+        let (mut servers, report) = validation::parse_elements(values, url, |server: &Server| {
+            (!server.is_valid()).then(|| "The IP address is missing".to_string())
+        });
 
         for server in &mut servers {
-            server.name = server
-                .name
-                .chars()
-                .filter(Self::filter_char)
-                .collect::<String>()
-                .trim()
-                .to_string();
+            server.normalized_name = self.name_normalization.normalize(&server.name);
         }
 
-        Ok(servers.into_iter().filter(Server::is_valid).collect())
+        Ok((servers, report))
     }
 
-    fn filter_char(c: &char) -> bool {
-        c.is_alphanumeric() || c.is_ascii_punctuation() || c.is_ascii_punctuation() || c.is_ascii_whitespace()
-    }
+    /// Send a GET request and read the body of the response.
+    ///
+    /// The request waits for its turn in the scheduler before being sent.
+    /// It is sent again if it fails because of the network (see [`RetryPolicy`]) or if Teamwork.tf
+    /// receives too many requests (see [`RateLimit`]).
+    /// If a cached response is specified, the request is conditional.
+    /// When replaying fixtures, the response is read from the disk instead.
+    async fn fetch(&self, url: String, address: String, cached: Option<&CacheEntry>) -> Result<RawResponse, Error> {
+        let fixtures = self.fixtures.as_ref();
+        let fixture_key = self.fixture_key(&address);
+
+        if let Some(fixtures) = fixtures.filter(|fixtures| fixtures.is_replay()) {
+            trace!("Replay '{}'", fixture_key);
+            return fixtures.replay(&fixture_key)?.check(address);
+        }
 
-    async fn get_raw_text(&self, url: &UrlWithKey) -> Result<String, Error> {
-        trace!("GET '{}'", url);
+        let mut network_attempt = 0u32;
+        let mut rate_limited_attempt = 0u32;
 
-        Ok(self
-            .reqwest
-            .get(url.make_url())
-            .send()
-            .await
-            .map_err(Error::HttpRequest)?
-            .text()
-            .await?)
+        loop {
+            self.scheduler.acquire(self.priority).await;
+
+            let mut request = self.reqwest.get(&url);
+
+            if let Some(etag) = cached.and_then(|entry| entry.etag.as_ref()) {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+
+            if let Some(last_modified) = cached.and_then(|entry| entry.last_modified.as_ref()) {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+
+            let result = match request.send().await {
+                Ok(response) => RawResponse::read(response).await,
+                Err(error) => Err(Error::HttpRequest(error)),
+            };
+
+            if let (Ok(response), Some(fixtures)) = (&result, fixtures.filter(|fixtures| fixtures.is_record())) {
+                if let Err(error) = fixtures.record(&fixture_key, response) {
+                    warn!("Failed to record fixture '{}': {}", fixture_key, error);
+                }
+            }
+
+            let result = result.and_then(|response| response.check(address.clone()));
+
+            match result {
+                Err(Error::HttpRequest(error))
+                    if network_attempt < self.retry_policy.max_retries && (error.is_timeout() || error.is_connect()) =>
+                {
+                    network_attempt += 1;
+                    warn!(
+                        "Request failed, retrying ({}/{}): {}",
+                        network_attempt,
+                        self.retry_policy.max_retries,
+                        error.without_url()
+                    );
+                    tokio::time::sleep(self.retry_policy.delay(network_attempt)).await;
+                }
+                Err(Error::RateLimited { retry_after }) if rate_limited_attempt < self.scheduler.limit().max_retries => {
+                    rate_limited_attempt += 1;
+
+                    let delay = retry_after.unwrap_or_else(|| self.scheduler.limit().backoff(rate_limited_attempt));
+
+                    warn!(
+                        "Too many requests, retrying {} in {} seconds ({}/{})",
+                        address,
+                        delay.as_secs(),
+                        rate_limited_attempt,
+                        self.scheduler.limit().max_retries
+                    );
+                    self.scheduler.back_off(delay);
+                }
+                result => return result,
+            }
+        }
     }
 
-    async fn get<'a, T: DeserializeOwned + Send + Sync + Sized>(&self, url: &UrlWithKey) -> Result<T, Error> {
-        let raw_text = self.get_raw_text(url).await?;
+    /// The name of the fixture of a request: its address relative to the base URL, without the API key.
+    fn fixture_key(&self, address: &str) -> String {
+        let path = address
+            .strip_prefix(self.base_url.as_str())
+            .or_else(|| address.strip_prefix(DEFAULT_BASE_URL))
+            .unwrap_or(address);
 
-        Self::try_parse_response::<T>(&raw_text, url)
+        path.strip_suffix(HIDDEN_API_KEY).unwrap_or(path).to_string()
     }
 
-    async fn get_thumbnail_response(&self, url: &UrlWithKey) -> Result<ThumbnailResponse, Error> {
-        let raw_text = self.get_raw_text(url).await?;
+    /// Get a JSON value.
+    /// The response is taken from the cache if it is younger than `max_age`, otherwise it is revalidated.
+    async fn get<T: DeserializeOwned + Send + Sync + Sized>(&self, url: &UrlWithKey, max_age: Duration) -> Result<T, Error> {
+        let key = url.to_string();
+        let cached = self.cache.as_ref().and_then(|cache| cache.load(&key));
 
-        if raw_text == TEAMWORK_TOO_MANY_ATTEMPTS {
-            return Err(Error::TooManyAttempts);
+        if let Some(entry) = cached.as_ref().filter(|entry| entry.is_fresh(max_age)) {
+            if let Ok(value) = serde_json::from_str::<T>(&entry.body) {
+                trace!("GET '{}' (cached)", url);
+                return Ok(value);
+            }
         }
 
-        Self::try_parse_response::<ThumbnailResponse>(&raw_text, url)
+        trace!("GET '{}'", url);
+
+        let response = self
+            .fetch(self.rebase_url(url.make_url()), key.clone(), cached.as_ref())
+            .await?;
+
+        if let (true, Some(mut entry)) = (response.is_not_modified(), cached) {
+            entry.touch();
+            self.store_in_cache(&entry);
+
+            return Self::try_parse_response::<T>(&entry.body, url);
+        }
+
+        let raw_text = String::from_utf8_lossy(&response.body).into_owned();
+        let value = Self::try_parse_response::<T>(&raw_text, url)?;
+
+        self.store_in_cache(&CacheEntry::new(key, response.etag, response.last_modified, raw_text));
+
+        Ok(value)
+    }
+
+    fn store_in_cache(&self, entry: &CacheEntry) {
+        if let Some(cache) = self.cache.as_ref() {
+            cache.store(entry);
+        }
     }
 
     /// Try to parse the value T from JSON.
@@ -256,8 +523,8 @@ impl Client {
 
 pub mod models {
     use {
-        serde::{de, Deserialize, Deserializer, Serialize},
-        std::{fmt::Display, str::FromStr},
+        serde::{de, de::DeserializeOwned, Deserialize, Deserializer, Serialize},
+        std::{collections::BTreeMap, fmt::Display, str::FromStr},
     };
 
     #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -271,9 +538,13 @@ pub mod models {
     pub struct Server {
         #[serde(default)]
         pub ip: String,
-        #[serde(deserialize_with = "from_str")]
+        #[serde(deserialize_with = "number_or_str")]
         pub port: u16,
+        /// The name as received from Teamwork.tf.
         pub name: String,
+        /// The name cleaned by the client, see [`NameNormalization`](crate::NameNormalization).
+        #[serde(skip)]
+        pub normalized_name: String,
         pub reachable: bool,
         #[serde(deserialize_with = "empty_str_is_none")]
         pub provider: Option<String>,
@@ -295,14 +566,18 @@ pub mod models {
         pub has_alltalk: Option<bool>,
     }
 
-    fn from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    /// Deserialize a number written as a JSON number or as a string.
+    fn number_or_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
     where
-        T: FromStr,
+        T: FromStr + DeserializeOwned,
         T::Err: Display,
         D: Deserializer<'de>,
     {
-        let s = String::deserialize(deserializer)?;
-        T::from_str(&s).map_err(de::Error::custom)
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(text) => T::from_str(text.trim()).map_err(de::Error::custom),
+            value @ serde_json::Value::Number(_) => serde_json::from_value(value).map_err(de::Error::custom),
+            value => Err(de::Error::custom(format!("expected a number, found {}", value))),
+        }
     }
 
     fn color_from_str<'de, D>(deserializer: D) -> Result<Option<Color>, D::Error>
@@ -330,6 +605,17 @@ pub mod models {
         })
     }
 
+    /// Deserialize a value, or use the default value if the JSON value has an unexpected type.
+    fn lenient<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: DeserializeOwned + Default,
+        D: Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+
+        Ok(serde_json::from_value(value).unwrap_or_default())
+    }
+
     impl Server {
         pub fn is_valid(&self) -> bool {
             !self.ip.is_empty()
@@ -344,6 +630,59 @@ pub mod models {
         pub description: String,
         #[serde(deserialize_with = "color_from_str")]
         pub color: Option<Color>,
+        /// True if the game mode is one of the official game modes of TF2, false if it's a community game mode.
+        #[serde(skip)]
+        pub official: bool,
+    }
+
+    /// The statistics of a map returned by `/map-stats/map/{name}`.
+    ///
+    /// Teamwork.tf does not document this endpoint, so each field is optional: a field missing
+    /// or with an unexpected type keeps its default value instead of failing the whole request.
+    #[derive(Deserialize, Debug, Clone, Default)]
+    #[serde(default)]
+    pub struct MapStats {
+        #[serde(rename = "map", deserialize_with = "lenient")]
+        pub name: String,
+        #[serde(rename = "thumbnail", deserialize_with = "lenient")]
+        pub thumbnail_url: Option<String>,
+        #[serde(rename = "official_map", deserialize_with = "lenient")]
+        pub is_official: bool,
+        #[serde(rename = "all_gamemodes", deserialize_with = "lenient")]
+        pub game_modes: Vec<String>,
+        /// When Teamwork.tf saw this map for the first time, as formatted by Teamwork.tf.
+        #[serde(deserialize_with = "lenient")]
+        pub first_seen: Option<String>,
+        #[serde(deserialize_with = "lenient")]
+        pub current_servers: Option<u32>,
+        #[serde(deserialize_with = "lenient")]
+        pub current_players: Option<u32>,
+        #[serde(deserialize_with = "lenient")]
+        pub highest_servers: Option<u32>,
+        #[serde(deserialize_with = "lenient")]
+        pub highest_players: Option<u32>,
+        /// The rank of this map among all the maps, 1 is the most played.
+        #[serde(deserialize_with = "lenient")]
+        pub popularity_rank: Option<u32>,
+        #[serde(deserialize_with = "lenient")]
+        pub related_maps: Vec<String>,
+        #[serde(deserialize_with = "lenient")]
+        pub context: MapContext,
+        /// The fields not described above.
+        #[serde(flatten)]
+        pub others: BTreeMap<String, serde_json::Value>,
+    }
+
+    #[derive(Deserialize, Debug, Clone, Default)]
+    #[serde(default)]
+    pub struct MapContext {
+        #[serde(deserialize_with = "lenient")]
+        pub description: Option<String>,
+        #[serde(deserialize_with = "lenient")]
+        pub screenshots: Vec<String>,
+        /// The fields not described above.
+        #[serde(flatten)]
+        pub others: BTreeMap<String, serde_json::Value>,
     }
 
     #[derive(Deserialize, Debug)]
@@ -357,7 +696,7 @@ pub mod models {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {super::*, test_case::test_case};
 
     #[test]
     fn test_client_is_send_and_sync() {
@@ -367,4 +706,224 @@ mod tests {
         assert_send::<Client>();
         assert_sync::<Client>();
     }
+
+    #[test_case(StatusCode::OK, None; "success")]
+    #[test_case(StatusCode::UNAUTHORIZED, Some(StatusCode::UNAUTHORIZED); "unauthorized")]
+    #[test_case(StatusCode::FORBIDDEN, Some(StatusCode::FORBIDDEN); "forbidden")]
+    #[test_case(StatusCode::TOO_MANY_REQUESTS, Some(StatusCode::TOO_MANY_REQUESTS); "too many requests")]
+    #[test_case(StatusCode::NOT_FOUND, Some(StatusCode::NOT_FOUND); "not found")]
+    #[test_case(StatusCode::BAD_GATEWAY, Some(StatusCode::BAD_GATEWAY); "server error")]
+    #[test_case(StatusCode::BAD_REQUEST, Some(StatusCode::BAD_REQUEST); "other")]
+    fn test_error_from_status(status: StatusCode, expected: Option<StatusCode>) {
+        let error = Error::from_status(status, None, String::new());
+
+        assert_eq!(expected, error.and_then(|error| error.status()));
+    }
+
+    #[test]
+    fn test_error_from_status_variants() {
+        let error = |status| Error::from_status(status, Some(Duration::from_secs(30)), String::new()).unwrap();
+
+        assert!(error(StatusCode::UNAUTHORIZED).is_invalid_api_key());
+        assert!(error(StatusCode::FORBIDDEN).is_invalid_api_key());
+        assert_eq!(
+            Some(&Some(Duration::from_secs(30))),
+            error(StatusCode::TOO_MANY_REQUESTS).as_rate_limited()
+        );
+        assert!(error(StatusCode::NOT_FOUND).is_not_found());
+        assert!(error(StatusCode::SERVICE_UNAVAILABLE).is_server_error());
+        assert!(error(StatusCode::IM_A_TEAPOT).is_http_status());
+    }
+
+    #[test_case("120", Some(Duration::from_secs(120)))]
+    #[test_case(" 5 ", Some(Duration::from_secs(5)))]
+    #[test_case("Wed, 21 Oct 2015 07:28:00 GMT", None)]
+    fn test_parse_retry_after(input: &str, expected: Option<Duration>) {
+        assert_eq!(expected, parse_retry_after(input));
+    }
+
+    #[test]
+    fn test_rebase_url() {
+        let client = Client::builder().base_url("http://localhost:8080/api").build().unwrap();
+
+        assert_eq!(
+            "http://localhost:8080/api/quickplay/payload/servers",
+            client.rebase_url(format!("{}/quickplay/payload/servers", DEFAULT_BASE_URL))
+        );
+        assert_eq!(
+            "https://teamwork.tf/images/map.jpg",
+            client.rebase_url("https://teamwork.tf/images/map.jpg".to_string())
+        );
+    }
+
+    fn get_fixtures_path() -> std::path::PathBuf {
+        std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("resources")
+            .join("tests")
+            .join("fixtures")
+    }
+
+    fn replay_client() -> Client {
+        Client::builder()
+            .fixtures(FixtureMode::Replay(get_fixtures_path()))
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replay_game_modes() {
+        let game_modes = replay_client().get_game_modes("").await.unwrap();
+        let ids: Vec<&str> = game_modes.iter().map(|mode| mode.id.as_str()).collect();
+        let official: Vec<bool> = game_modes.iter().map(|mode| mode.official).collect();
+
+        assert_eq!(vec!["payload", "koth", "vsh"], ids);
+        assert_eq!(vec![true, true, false], official);
+    }
+
+    #[tokio::test]
+    async fn test_replay_servers() {
+        let url = UrlWithKey::new(format!("{}/quickplay/payload/servers", DEFAULT_BASE_URL), "secret");
+        let (servers, report) = replay_client().get_servers(url).await.unwrap();
+
+        assert_eq!(2, servers.len());
+        assert_eq!("Fixture Payload #1", servers[0].name);
+        assert_eq!("Fixture Payload #1", servers[0].normalized_name);
+        assert_eq!(27016, servers[1].port);
+        assert_eq!(None, servers[1].provider);
+        assert!(report.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_servers_with_invalid_entries() {
+        let url = UrlWithKey::new(format!("{}/quickplay/koth/servers", DEFAULT_BASE_URL), "secret");
+        let (servers, report) = replay_client().get_servers(url).await.unwrap();
+        let names: Vec<&str> = servers.iter().map(|server| server.name.as_str()).collect();
+        let rejected: Vec<usize> = report.rejected.iter().map(|rejected| rejected.index).collect();
+
+        assert_eq!(vec!["Fixture KOTH #1", "Fixture KOTH #2", "Fixture KOTH #6"], names);
+        assert_eq!(27016, servers[1].port);
+        assert_eq!(3, report.accepted());
+        assert_eq!(vec![2, 3, 4], rejected);
+        assert_eq!("The IP address is missing", report.rejected[1].reason);
+        assert!(report.rejected[2].entry.contains("Fixture KOTH #5"));
+        assert!(!report.address.contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_map_thumbnail() {
+        let client = replay_client();
+        let thumbnail = client.get_map_thumbnail("key", "pl_upward", |bytes| bytes).await.unwrap();
+
+        assert!(thumbnail.unwrap().starts_with(&[0xff, 0xd8]));
+        assert!(client
+            .get_map_thumbnail("key", "cp_unknown", |bytes| bytes)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_map_stats() {
+        let stats = replay_client().get_map_stats("key", "pl_upward").await.unwrap();
+
+        assert_eq!("pl_upward", stats.name);
+        assert!(stats.is_official);
+        assert_eq!(vec!["payload"], stats.game_modes);
+        assert_eq!(Some(412), stats.current_players);
+        assert_eq!(Some(3), stats.popularity_rank);
+        assert_eq!(2, stats.context.screenshots.len());
+        assert!(stats.context.description.is_some());
+        assert!(stats.others.contains_key("normalized_map_name"));
+    }
+
+    #[test]
+    fn test_map_stats_unexpected_types() {
+        let stats: MapStats = serde_json::from_str(
+            r#"{"map": "cp_custom", "official_map": "no", "current_players": "many", "related_maps": [{"map": "cp_other"}], "context": null}"#,
+        )
+        .unwrap();
+
+        assert_eq!("cp_custom", stats.name);
+        assert!(!stats.is_official);
+        assert_eq!(None, stats.current_players);
+        assert!(stats.related_maps.is_empty());
+        assert!(stats.context.screenshots.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_missing_fixture() {
+        let error = replay_client().get_map_thumbnail_url("key", "ctf_2fort").await.unwrap_err();
+
+        assert_eq!(
+            Some(&"/map-stats/mapthumbnail/ctf_2fort".to_string()),
+            error.as_missing_fixture()
+        );
+    }
+
+    #[test_case("1.2.3.4", "1.2.3.4"; "ipv4")]
+    #[test_case("2001:db8::1", "2001%3Adb8%3A%3A1"; "ipv6")]
+    #[test_case("tf2.example.com&port=1", "tf2.example.com%26port%3D1"; "reserved characters")]
+    #[tokio::test]
+    async fn test_get_server_encodes_host(host: &str, expected: &str) {
+        let error = replay_client().get_server(host, 27015, "key").await.unwrap_err();
+
+        assert_eq!(
+            Some(&format!("/quickplay/server?ip={}&port=27015", expected)),
+            error.as_missing_fixture()
+        );
+    }
+
+    /// Answer one HTTP request with the specified body.
+    fn serve_once(body: &'static str) -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 4096];
+            let _ = stream.read(&mut buffer).unwrap();
+
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        format!("http://{}/api/v1", address)
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        const BODY: &str =
+            r#"{"gamemodes_official":[{"id":"arena","title":"Arena","desc":"","color":"ffffff"}],"gamemodes_community":[]}"#;
+
+        let directory = tempdir::TempDir::new("test_record_then_replay").unwrap();
+        let recorder = Client::builder()
+            .base_url(serve_once(BODY))
+            .fixtures(FixtureMode::Record(directory.path().to_path_buf()))
+            .build()
+            .unwrap();
+
+        assert_eq!("arena", recorder.get_game_modes("secret").await.unwrap()[0].id);
+
+        let header = std::fs::read_to_string(directory.path().join("quickplay.json")).unwrap();
+
+        assert!(!header.contains("secret"));
+        assert_eq!(
+            BODY,
+            std::fs::read_to_string(directory.path().join("quickplay.body")).unwrap()
+        );
+
+        let player = Client::builder()
+            .fixtures(FixtureMode::Replay(directory.path().to_path_buf()))
+            .build()
+            .unwrap();
+
+        assert_eq!("arena", player.get_game_modes("").await.unwrap()[0].id);
+    }
 }
@@ -0,0 +1,195 @@
+/// Describe how the server names are cleaned.
+///
+/// The names are chosen by the server owners and they often contain emoji or non-Latin letters, those are kept.
+/// What is removed is what can't be displayed or what is used to stand out in the server list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NameNormalization {
+    /// Remove the control characters and the invisible characters (zero width spaces, bidirectional marks...).
+    pub strip_invisible: bool,
+    /// Replace each run of whitespaces by a single space, and remove the leading and trailing whitespaces.
+    pub collapse_whitespace: bool,
+    /// Replace the letters and digits that imitate ASCII ones, like `𝐓𝐅𝟐` or `ＴＦ２`, by ASCII letters and digits.
+    ///
+    /// Only the styled variants are replaced: Cyrillic or Greek letters are legitimate even if they look
+    /// like Latin letters.
+    pub fold_homoglyphs: bool,
+}
+
+impl Default for NameNormalization {
+    fn default() -> Self {
+        Self {
+            strip_invisible: true,
+            collapse_whitespace: true,
+            fold_homoglyphs: false,
+        }
+    }
+}
+
+impl NameNormalization {
+    /// Keep the name as it is.
+    pub const fn none() -> Self {
+        Self {
+            strip_invisible: false,
+            collapse_whitespace: false,
+            fold_homoglyphs: false,
+        }
+    }
+
+    pub fn normalize(&self, name: &str) -> String {
+        let mut normalized = String::with_capacity(name.len());
+        let mut previous_is_whitespace = true;
+
+        for c in name.chars() {
+            if self.collapse_whitespace && is_whitespace(c) {
+                if !previous_is_whitespace {
+                    normalized.push(' ');
+                }
+
+                previous_is_whitespace = true;
+                continue;
+            }
+
+            if self.strip_invisible && is_invisible(c) {
+                continue;
+            }
+
+            match self.fold_homoglyphs {
+                true => normalized.push(fold_homoglyph(c)),
+                false => normalized.push(c),
+            }
+
+            previous_is_whitespace = false;
+        }
+
+        if self.collapse_whitespace && normalized.ends_with(' ') {
+            normalized.pop();
+        }
+
+        normalized
+    }
+}
+
+/// The whitespaces, including the blank characters that are displayed like a space.
+fn is_whitespace(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '\u{2800}' | '\u{3164}' | '\u{115F}' | '\u{1160}' | '\u{FFA0}')
+}
+
+/// The characters that are not displayed.
+///
+/// The zero width joiner (U+200D) and the variation selectors are kept because they are part of emoji.
+fn is_invisible(c: char) -> bool {
+    c.is_control()
+        || matches!(c,
+            '\u{00AD}' // Soft hyphen
+            | '\u{034F}' // Combining grapheme joiner
+            | '\u{061C}' // Arabic letter mark
+            | '\u{180E}' // Mongolian vowel separator
+            | '\u{200B}' | '\u{200C}' // Zero width space and non-joiner
+            | '\u{200E}' | '\u{200F}' // Left-to-right and right-to-left marks
+            | '\u{202A}'..='\u{202E}' // Bidirectional embeddings and overrides
+            | '\u{2060}'..='\u{2064}' // Word joiner and invisible operators
+            | '\u{2066}'..='\u{206F}' // Bidirectional isolates and deprecated format characters
+            | '\u{FEFF}' // Zero width no-break space
+            | '\u{FFF9}'..='\u{FFFB}' // Interlinear annotations
+            | '\u{E0000}'..='\u{E007F}' // Tags
+        )
+}
+
+fn offset(c: char, first: u32) -> u32 {
+    c as u32 - first
+}
+
+fn ascii(first: u8, offset: u32) -> char {
+    char::from(first + offset as u8)
+}
+
+fn fold_homoglyph(c: char) -> char {
+    match c {
+        // Fullwidth forms
+        '\u{FF01}'..='\u{FF5E}' => ascii(b'!', offset(c, 0xFF01)),
+        '\u{3000}' => ' ',
+        // Mathematical alphanumeric symbols: 13 styles of 52 letters, and 5 styles of 10 digits.
+        '\u{1D400}'..='\u{1D6A3}' => match offset(c, 0x1D400) % 52 {
+            offset @ 0..=25 => ascii(b'A', offset),
+            offset => ascii(b'a', offset - 26),
+        },
+        '\u{1D7CE}'..='\u{1D7FF}' => ascii(b'0', offset(c, 0x1D7CE) % 10),
+        // Enclosed alphanumerics
+        '\u{249C}'..='\u{24B5}' => ascii(b'a', offset(c, 0x249C)),
+        '\u{24B6}'..='\u{24CF}' => ascii(b'A', offset(c, 0x24B6)),
+        '\u{24D0}'..='\u{24E9}' => ascii(b'a', offset(c, 0x24D0)),
+        '\u{2460}'..='\u{2468}' => ascii(b'1', offset(c, 0x2460)),
+        '\u{24EA}' => '0',
+        '\u{1F130}'..='\u{1F149}' => ascii(b'A', offset(c, 0x1F130)),
+        '\u{1F150}'..='\u{1F169}' => ascii(b'A', offset(c, 0x1F150)),
+        '\u{1F170}'..='\u{1F189}' => ascii(b'A', offset(c, 0x1F170)),
+        // Small capitals
+        'ᴀ' => 'A',
+        'ʙ' => 'B',
+        'ᴄ' => 'C',
+        'ᴅ' => 'D',
+        'ᴇ' => 'E',
+        'ғ' => 'F',
+        'ɢ' => 'G',
+        'ʜ' => 'H',
+        'ɪ' => 'I',
+        'ᴊ' => 'J',
+        'ᴋ' => 'K',
+        'ʟ' => 'L',
+        'ᴍ' => 'M',
+        'ɴ' => 'N',
+        'ᴏ' => 'O',
+        'ᴘ' => 'P',
+        'ǫ' => 'Q',
+        'ʀ' => 'R',
+        'ꜱ' => 'S',
+        'ᴛ' => 'T',
+        'ᴜ' => 'U',
+        'ᴠ' => 'V',
+        'ᴡ' => 'W',
+        'ʏ' => 'Y',
+        'ᴢ' => 'Z',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::NameNormalization, test_case::test_case};
+
+    const FOLD_HOMOGLYPHS: NameNormalization = NameNormalization {
+        strip_invisible: true,
+        collapse_whitespace: true,
+        fold_homoglyphs: true,
+    };
+
+    #[test_case("  Skial   |  Payload  ", "Skial | Payload"; "whitespaces")]
+    #[test_case("Tab\tand\nnew line", "Tab and new line"; "control whitespaces")]
+    #[test_case("Zero\u{200B}width\u{FEFF}", "Zerowidth"; "invisible")]
+    #[test_case("\u{202E}desrever", "desrever"; "bidirectional override")]
+    #[test_case("Bell\u{7}", "Bell"; "control")]
+    #[test_case("🔥 Uncletopia | Paris 🇫🇷", "🔥 Uncletopia | Paris 🇫🇷"; "emoji")]
+    #[test_case("👨\u{200D}👩\u{200D}👧 family", "👨\u{200D}👩\u{200D}👧 family"; "emoji sequence")]
+    #[test_case("Сервер «Москва» — 24/7", "Сервер «Москва» — 24/7"; "cyrillic and punctuation")]
+    #[test_case("日本語のサーバー【2fort】", "日本語のサーバー【2fort】"; "japanese")]
+    #[test_case("\u{3164}\u{3164}Hidden", "Hidden"; "blank characters")]
+    fn test_normalize(name: &str, expected: &str) {
+        assert_eq!(expected, NameNormalization::default().normalize(name));
+    }
+
+    #[test_case("𝐓𝐄𝐀𝐌 𝟐𝐟𝐨𝐫𝐭", "TEAM 2fort"; "mathematical bold")]
+    #[test_case("ＦＵＬＬ　ｗｉｄｔｈ！", "FULL width!"; "fullwidth")]
+    #[test_case("ⓒⓘⓡⓒⓛⓔⓓ Ⓐ", "circled A"; "circled")]
+    #[test_case("🅰🅱 ꜱᴍᴀʟʟ", "AB SMALL"; "squared and small capitals")]
+    #[test_case("Сервер", "Сервер"; "cyrillic is kept")]
+    fn test_fold_homoglyphs(name: &str, expected: &str) {
+        assert_eq!(expected, FOLD_HOMOGLYPHS.normalize(name));
+    }
+
+    #[test]
+    fn test_none() {
+        let name = " \u{200B}𝐓𝐅𝟐  ";
+
+        assert_eq!(name, NameNormalization::none().normalize(name));
+    }
+}
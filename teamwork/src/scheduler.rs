@@ -0,0 +1,316 @@
+use {
+    std::{
+        cmp::Ordering,
+        collections::BinaryHeap,
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+    tokio::sync::Notify,
+};
+
+/// The priority of a request.
+/// When the request budget is exhausted, the requests with the highest priority are sent first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Requests nobody is waiting for, like the thumbnails.
+    Background,
+    #[default]
+    Normal,
+    /// Requests the user is waiting for, like the server details.
+    Interactive,
+}
+
+/// The request budget shared by all the clones of a [`Client`](crate::Client).
+///
+/// This is a token bucket: each request consumes a token, the bucket contains at most `burst` tokens
+/// and a new token is added every `interval`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub interval: Duration,
+    /// How many times a request is sent again when Teamwork.tf answers it receives too many requests.
+    pub max_retries: u32,
+    /// The time to wait when Teamwork.tf does not tell how long to wait.
+    /// It is doubled after each failure.
+    pub backoff: Duration,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            burst: 10,
+            interval: Duration::from_secs(1),
+            max_retries: 5,
+            backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RateLimit {
+    /// The time to wait before the attempt number `attempt` (starting at 1 for the first retry).
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Ticket {
+    priority: Priority,
+    sequence: u64,
+}
+
+impl Ord for Ticket {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // The requests with the same priority are served in order of arrival.
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for Ticket {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+struct State {
+    tokens: f64,
+    last_refill: Instant,
+    blocked_until: Option<Instant>,
+    queue: BinaryHeap<Ticket>,
+    next_sequence: u64,
+}
+
+/// Decide when each request can be sent.
+pub(crate) struct Scheduler {
+    limit: RateLimit,
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+impl Scheduler {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(State {
+                tokens: limit.burst as f64,
+                last_refill: Instant::now(),
+                blocked_until: None,
+                queue: BinaryHeap::new(),
+                next_sequence: 0,
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    /// Wait until a request with this priority can be sent.
+    pub async fn acquire(&self, priority: Priority) {
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            let ticket = Ticket {
+                priority,
+                sequence: state.next_sequence,
+            };
+
+            state.next_sequence += 1;
+            state.queue.push(ticket);
+            ticket
+        };
+        // Remove the ticket from the queue if the request is cancelled while it waits.
+        let _guard = TicketGuard { scheduler: self, ticket };
+
+        loop {
+            // Created before checking the state so a notification sent in between is not lost.
+            let notified = self.notify.notified();
+
+            match self.try_take(ticket) {
+                Ok(()) => return,
+                Err(delay) => {
+                    let _ = tokio::time::timeout(delay, notified).await;
+                }
+            }
+        }
+    }
+
+    /// Stop sending requests for a while, because Teamwork.tf received too many requests.
+    pub fn back_off(&self, delay: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let until = Instant::now() + delay;
+
+        state.tokens = 0.0;
+        state.blocked_until = Some(state.blocked_until.map_or(until, |blocked_until| blocked_until.max(until)));
+    }
+
+    /// Take a token if the ticket is the next to be served, otherwise returns how long to wait before trying again.
+    fn try_take(&self, ticket: Ticket) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        if let Some(blocked_until) = state.blocked_until {
+            if blocked_until > now {
+                return Err(blocked_until - now);
+            }
+
+            // The tokens are not refilled while blocked, but the first request can be sent as soon as possible.
+            state.blocked_until = None;
+            state.last_refill = now;
+            state.tokens = state.tokens.max(1.0);
+        }
+
+        let refilled = now.duration_since(state.last_refill).as_secs_f64() / self.limit.interval.as_secs_f64();
+
+        state.tokens = (state.tokens + refilled).min(self.limit.burst as f64);
+        state.last_refill = now;
+
+        if state.queue.peek() != Some(&ticket) {
+            // Woken up when the ticket in front of this one is served.
+            return Err(self.limit.interval);
+        }
+
+        if state.tokens < 1.0 {
+            return Err(self.limit.interval.mul_f64(1.0 - state.tokens));
+        }
+
+        state.tokens -= 1.0;
+        state.queue.pop();
+        self.notify.notify_waiters();
+
+        Ok(())
+    }
+}
+
+struct TicketGuard<'a> {
+    scheduler: &'a Scheduler,
+    ticket: Ticket,
+}
+
+impl Drop for TicketGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.state.lock().unwrap();
+        let length = state.queue.len();
+
+        state.queue.retain(|ticket| *ticket != self.ticket);
+
+        if state.queue.len() != length {
+            self.scheduler.notify.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{Priority, RateLimit, Scheduler},
+        std::{
+            sync::{Arc, Mutex},
+            time::Duration,
+        },
+        tokio::time::Instant,
+    };
+
+    fn limit(burst: u32) -> RateLimit {
+        RateLimit {
+            burst,
+            interval: Duration::from_secs(1),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_burst_then_wait_for_tokens() {
+        let scheduler = Scheduler::new(RateLimit {
+            interval: Duration::from_millis(100),
+            ..limit(2)
+        });
+        let started = Instant::now();
+
+        scheduler.acquire(Priority::Normal).await;
+        scheduler.acquire(Priority::Normal).await;
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        scheduler.acquire(Priority::Normal).await;
+        assert!(started.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn test_back_off() {
+        let scheduler = Scheduler::new(limit(10));
+        let started = Instant::now();
+
+        scheduler.back_off(Duration::from_millis(200));
+        scheduler.acquire(Priority::Interactive).await;
+
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_highest_priority_first() {
+        let scheduler = Arc::new(Scheduler::new(RateLimit {
+            interval: Duration::from_millis(50),
+            ..limit(1)
+        }));
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // Consume the only token so the next requests have to wait.
+        scheduler.acquire(Priority::Normal).await;
+
+        let mut tasks = Vec::new();
+
+        for priority in [Priority::Background, Priority::Normal, Priority::Interactive] {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+
+            tasks.push(tokio::spawn(async move {
+                scheduler.acquire(priority).await;
+                order.lock().unwrap().push(priority);
+            }));
+            tokio::task::yield_now().await;
+        }
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(
+            vec![Priority::Interactive, Priority::Normal, Priority::Background],
+            *order.lock().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_leaves_the_queue() {
+        let scheduler = Scheduler::new(RateLimit {
+            interval: Duration::from_millis(50),
+            ..limit(1)
+        });
+
+        scheduler.acquire(Priority::Normal).await;
+
+        // This request is cancelled while it waits for a token.
+        let cancelled = tokio::time::timeout(Duration::from_millis(10), scheduler.acquire(Priority::Interactive)).await;
+
+        assert!(cancelled.is_err());
+        assert!(
+            tokio::time::timeout(Duration::from_secs(1), scheduler.acquire(Priority::Background))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let limit = RateLimit {
+            backoff: Duration::from_secs(10),
+            ..Default::default()
+        };
+
+        assert_eq!(Duration::from_secs(10), limit.backoff(1));
+        assert_eq!(Duration::from_secs(20), limit.backoff(2));
+        assert_eq!(Duration::from_secs(40), limit.backoff(3));
+    }
+}
@@ -1,5 +1,8 @@
 use std::fmt::Display;
 
+/// Replace the API key when an URL is displayed.
+pub(crate) const HIDDEN_API_KEY: &str = "?key=***";
+
 /// An URL that does not leak your API key when you print it.
 /// Use UrlWithKey.make_url() to get the final URL.
 /// When displayed the API key is hidden.
@@ -27,6 +30,6 @@ impl UrlWithKey {
 
 impl Display for UrlWithKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}?key=***", self.url)
+        write!(f, "{}{}", self.url, HIDDEN_API_KEY)
     }
 }
@@ -0,0 +1,179 @@
+use {
+    serde::de::DeserializeOwned,
+    std::fmt::{Display, Formatter},
+};
+
+/// An element of a list returned by Teamwork.tf that was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedEntry {
+    /// The position of the element in the list.
+    pub index: usize,
+    /// Why the element was rejected.
+    pub reason: String,
+    /// The element as received, truncated if it's too long.
+    pub entry: String,
+}
+
+impl Display for RejectedEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}: {} ({})", self.index, self.reason, self.entry)
+    }
+}
+
+/// The elements rejected when reading a list returned by Teamwork.tf.
+///
+/// Each element of a list is read on its own: an invalid element is rejected and reported here,
+/// the other elements are still returned.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// The address of the request, without the API key.
+    pub address: String,
+    /// The count of elements in the list.
+    pub total: usize,
+    /// The elements rejected, ordered by position in the list.
+    pub rejected: Vec<RejectedEntry>,
+}
+
+impl ValidationReport {
+    pub fn new(address: impl ToString) -> Self {
+        Self {
+            address: address.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rejected.is_empty()
+    }
+
+    pub fn accepted(&self) -> usize {
+        self.total - self.rejected.len()
+    }
+
+    /// The position in the list of each element accepted.
+    ///
+    /// This is useful to reject an element after the list has been read.
+    pub fn accepted_indices(&self) -> Vec<usize> {
+        (0..self.total)
+            .filter(|index| self.rejected.binary_search_by_key(index, |rejected| rejected.index).is_err())
+            .collect()
+    }
+
+    pub fn reject(&mut self, index: usize, reason: impl ToString, entry: impl ToString) {
+        const MAX_ENTRY_LENGTH: usize = 500;
+
+        let mut entry = entry.to_string();
+
+        if let Some((position, _)) = entry.char_indices().nth(MAX_ENTRY_LENGTH) {
+            entry.truncate(position);
+            entry.push('…');
+        }
+
+        let position = self.rejected.partition_point(|rejected| rejected.index < index);
+
+        self.rejected.insert(
+            position,
+            RejectedEntry {
+                index,
+                reason: reason.to_string(),
+                entry,
+            },
+        );
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} accepted, {} rejected",
+            self.address,
+            self.accepted(),
+            self.rejected.len()
+        )?;
+
+        for rejected in &self.rejected {
+            write!(f, "\n{}", rejected)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Read each element of a list on its own.
+///
+/// `validate` returns the reason why an element that can be read is rejected anyway.
+pub(crate) fn parse_elements<T: DeserializeOwned>(
+    values: Vec<serde_json::Value>,
+    address: impl ToString,
+    validate: impl Fn(&T) -> Option<String>,
+) -> (Vec<T>, ValidationReport) {
+    let mut report = ValidationReport::new(address);
+    let mut elements = Vec::with_capacity(values.len());
+
+    report.total = values.len();
+
+    for (index, value) in values.into_iter().enumerate() {
+        match serde_json::from_value::<T>(value.clone()) {
+            Ok(element) => match validate(&element) {
+                None => elements.push(element),
+                Some(reason) => report.reject(index, reason, value),
+            },
+            Err(error) => report.reject(index, error, value),
+        }
+    }
+
+    (elements, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{parse_elements, ValidationReport},
+        serde_json::json,
+    };
+
+    #[test]
+    fn test_parse_elements() {
+        let values = vec![json!(1), json!("two"), json!(3), json!(-4)];
+        let (elements, report) =
+            parse_elements::<u32>(values, "/numbers", |number| (*number > 2).then(|| "too large".to_string()));
+
+        assert_eq!(vec![1], elements);
+        assert_eq!(1, report.accepted());
+        assert_eq!(vec![0], report.accepted_indices());
+        assert_eq!(
+            vec![1, 2, 3],
+            report.rejected.iter().map(|rejected| rejected.index).collect::<Vec<_>>()
+        );
+        assert_eq!("too large", report.rejected[1].reason);
+        assert_eq!("3", report.rejected[1].entry);
+    }
+
+    #[test]
+    fn test_reject_after_parsing() {
+        let values = vec![json!(1), json!("two"), json!(3), json!(4)];
+        let (_, mut report) = parse_elements::<u32>(values, "/numbers", |_| None);
+
+        assert_eq!(vec![0, 2, 3], report.accepted_indices());
+
+        report.reject(2, "rejected later", "3");
+
+        assert_eq!(vec![0, 3], report.accepted_indices());
+        assert_eq!(2, report.accepted());
+        assert_eq!(
+            vec![1, 2],
+            report.rejected.iter().map(|rejected| rejected.index).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_reject_truncates_entry() {
+        let mut report = ValidationReport::new("/servers");
+
+        report.reject(0, "invalid", "é".repeat(600));
+
+        assert_eq!(501, report.rejected[0].entry.chars().count());
+        assert!(report.rejected[0].entry.ends_with('…'));
+    }
+}